@@ -0,0 +1,57 @@
+//! Output backends.
+//!
+//! An [`OutputBackend`] is whatever finally turns key/mouse/scroll commands
+//! into OS input. [`Performer`](crate::Performer) injects real events through
+//! enigo; [`RecordingBackend`](crate::RecordingBackend) only logs them, so the
+//! layers above can be exercised without an input-injection backend.
+
+use enigo::{Button, InputResult};
+
+use crate::KeyCombo;
+
+pub trait OutputBackend: Send {
+    /// Press the keys of a combo. Keys already held by another combo stay
+    /// down without being pressed again.
+    fn press(&mut self, key_combo: &KeyCombo) -> InputResult<()>;
+
+    /// Release the keys of a combo that no other combo still holds.
+    fn release(&mut self, key_combo: &KeyCombo) -> InputResult<()>;
+
+    /// Press and release a combo.
+    fn perform(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        self.press(key_combo)?;
+        self.release(key_combo)
+    }
+
+    /// Release every key held by any combo.
+    fn release_all(&mut self) -> InputResult<()>;
+
+    /// Type Unicode text, pausing `delay_ms` after each character.
+    fn type_text(&mut self, text: &str, delay_ms: u64) -> InputResult<()>;
+
+    /// Move the mouse by a relative offset.
+    fn mouse_move(&mut self, dx: i32, dy: i32) -> InputResult<()>;
+
+    fn scroll_x(&mut self, value: f64) -> InputResult<()>;
+
+    fn scroll_y(&mut self, value: f64) -> InputResult<()>;
+
+    fn mouse_click(&mut self, button: Button) -> InputResult<()>;
+
+    fn mouse_double_click(&mut self, button: Button) -> InputResult<()> {
+        self.mouse_click(button)?;
+        self.mouse_click(button)
+    }
+
+    fn mouse_press(&mut self, button: Button) -> InputResult<()>;
+
+    fn mouse_release(&mut self, button: Button) -> InputResult<()>;
+
+    /// Press a modifier by raw macOS virtual keycode.
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_press(&mut self, keycode: u16) -> Result<(), String>;
+
+    /// Release a modifier by raw macOS virtual keycode.
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_release(&mut self, keycode: u16) -> Result<(), String>;
+}
@@ -0,0 +1,226 @@
+//! Reference-counted key state.
+//!
+//! Several combos can hold the same key at once — `lb` holding `cmd` while
+//! `lt+a` holds `cmd+shift+a`. A key goes physically down when its first
+//! holder presses it and up only when its last holder releases it, so
+//! releasing one combo never drops a key another combo still needs.
+
+use std::collections::HashMap;
+
+use enigo::{Direction, InputResult};
+
+use crate::{Key, KeyCombo};
+
+#[derive(Debug, Default)]
+pub(crate) struct HeldKeys {
+    counts: HashMap<Key, u32>,
+}
+
+impl HeldKeys {
+    /// Take a hold on every key of the combo, sending `Press` for keys that
+    /// were not held yet.
+    pub(crate) fn press<F>(
+        &mut self,
+        combo: &KeyCombo,
+        mut send: F,
+    ) -> InputResult<()>
+    where
+        F: FnMut(Key, Direction) -> InputResult<()>,
+    {
+        for key in combo.press_order() {
+            if !self.counts.contains_key(&key) {
+                send(key, Direction::Press)?;
+            }
+            *self.counts.entry(key).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    /// Drop one hold on every key of the combo, in reverse press order,
+    /// sending `Release` for keys nobody holds anymore. Keys that are not
+    /// held are ignored. A key whose release fails stays held, so
+    /// [`HeldKeys::release_all`] can retry it; the first error is returned
+    /// after every key was tried.
+    pub(crate) fn release<F>(
+        &mut self,
+        combo: &KeyCombo,
+        mut send: F,
+    ) -> InputResult<()>
+    where
+        F: FnMut(Key, Direction) -> InputResult<()>,
+    {
+        let mut result = Ok(());
+        for key in combo.press_order().rev() {
+            let Some(count) = self.counts.get_mut(&key) else {
+                continue;
+            };
+            if *count > 1 {
+                *count -= 1;
+                continue;
+            }
+            match send(key, Direction::Release) {
+                Ok(()) => {
+                    self.counts.remove(&key);
+                }
+                Err(e) if result.is_ok() => result = Err(e),
+                Err(_) => {}
+            }
+        }
+        result
+    }
+
+    /// Release every held key regardless of how many holds it has. Every
+    /// key is tried; those that fail stay held and the first error is
+    /// returned.
+    pub(crate) fn release_all<F>(&mut self, mut send: F) -> InputResult<()>
+    where
+        F: FnMut(Key, Direction) -> InputResult<()>,
+    {
+        let mut result = Ok(());
+        self.counts
+            .retain(|key, _| match send(*key, Direction::Release) {
+                Ok(()) => false,
+                Err(e) => {
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                    true
+                }
+            });
+        result
+    }
+
+    /// Whether any combo currently holds the key.
+    pub(crate) fn is_held(&self, key: Key) -> bool {
+        self.counts.contains_key(&key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use enigo::InputError;
+
+    use super::*;
+
+    fn combo(s: &str) -> KeyCombo {
+        s.parse().unwrap()
+    }
+
+    fn run(held: &mut HeldKeys, press: bool, c: &str) -> Vec<(Key, Direction)> {
+        let mut sent = Vec::new();
+        let send = |k, d| {
+            sent.push((k, d));
+            Ok(())
+        };
+        if press {
+            held.press(&combo(c), send).unwrap();
+        } else {
+            held.release(&combo(c), send).unwrap();
+        }
+        sent
+    }
+
+    #[test]
+    fn overlapping_holds_keep_shared_modifier() {
+        let mut held = HeldKeys::default();
+        assert_eq!(run(&mut held, true, "cmd"), [(Key::Meta, Direction::Press)]);
+        assert_eq!(
+            run(&mut held, true, "cmd+shift+a"),
+            [(Key::Shift, Direction::Press), (Key::A, Direction::Press)]
+        );
+
+        // Releasing the first hold must not lift cmd under the second.
+        assert!(run(&mut held, false, "cmd").is_empty());
+        assert!(held.is_held(Key::Meta));
+
+        assert_eq!(
+            run(&mut held, false, "cmd+shift+a"),
+            [
+                (Key::A, Direction::Release),
+                (Key::Meta, Direction::Release),
+                (Key::Shift, Direction::Release),
+            ]
+        );
+        assert!(!held.is_held(Key::Meta));
+    }
+
+    #[test]
+    fn tap_inside_hold_leaves_modifier_down() {
+        let mut held = HeldKeys::default();
+        run(&mut held, true, "cmd");
+        assert_eq!(run(&mut held, true, "cmd+c"), [(Key::C, Direction::Press)]);
+        assert_eq!(
+            run(&mut held, false, "cmd+c"),
+            [(Key::C, Direction::Release)]
+        );
+        assert!(held.is_held(Key::Meta));
+    }
+
+    #[test]
+    fn release_without_press_is_ignored() {
+        let mut held = HeldKeys::default();
+        assert!(run(&mut held, false, "ctrl+a").is_empty());
+    }
+
+    #[test]
+    fn release_all_lifts_everything_once() {
+        let mut held = HeldKeys::default();
+        run(&mut held, true, "cmd");
+        run(&mut held, true, "cmd+a");
+        let mut sent = Vec::new();
+        held.release_all(|k, d| {
+            sent.push((k, d));
+            Ok(())
+        })
+        .unwrap();
+        sent.sort_by_key(|(k, _)| k.name());
+        assert_eq!(
+            sent,
+            [
+                (Key::A, Direction::Release),
+                (Key::Meta, Direction::Release)
+            ]
+        );
+        assert!(!held.is_held(Key::Meta));
+    }
+
+    #[test]
+    fn failed_releases_stay_held_for_a_retry() {
+        let mut held = HeldKeys::default();
+        run(&mut held, true, "cmd+shift+a");
+        let fail_on = |bad: Key| {
+            move |k: Key, _| {
+                if k == bad {
+                    Err(InputError::Simulate("no device"))
+                } else {
+                    Ok(())
+                }
+            }
+        };
+
+        // shift fails; a and cmd still go up.
+        assert!(held
+            .release(&combo("cmd+shift+a"), fail_on(Key::Shift))
+            .is_err());
+        assert!(held.is_held(Key::Shift));
+        assert!(!held.is_held(Key::A) && !held.is_held(Key::Meta));
+
+        run(&mut held, true, "ctrl");
+        let mut sent = Vec::new();
+        let result = held.release_all(|k, d| {
+            sent.push((k, d));
+            fail_on(Key::Control)(k, d)
+        });
+        assert!(result.is_err());
+        sent.sort_by_key(|(k, _)| k.name());
+        assert_eq!(
+            sent,
+            [
+                (Key::Control, Direction::Release),
+                (Key::Shift, Direction::Release)
+            ]
+        );
+        assert!(held.is_held(Key::Control));
+        assert!(!held.is_held(Key::Shift));
+    }
+}
@@ -1,199 +1,362 @@
 use enigo::Key as EnigoKey;
 
-/// A key that can be emulated.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Key {
-    Unicode(char),
-    Control,
-    RControl,
-    Meta,
-    #[cfg(target_os = "macos")]
-    RCommand,
-    Shift,
-    RShift,
-    Alt,
-    RAlt,
-    Home,
-    End,
-    PageUp,
-    PageDown,
-    UpArrow,
-    DownArrow,
-    LeftArrow,
-    RightArrow,
-    Delete,
-    Backspace,
-    Escape,
-    Tab,
-    Space,
-    Return,
-    VolumeUp,
-    VolumeDown,
-    VolumeMute,
-    #[cfg(target_os = "macos")]
-    BrightnessUp,
-    #[cfg(target_os = "macos")]
-    BrightnessDown,
-    F1,
-    F2,
-    F3,
-    F4,
-    F5,
-    F6,
-    F7,
-    F8,
-    F9,
-    F10,
-    F11,
-    F12,
-    F13,
-    F14,
-    F15,
-    F16,
-    F17,
-    F18,
-    F19,
-    F20,
+use crate::keycode;
 
-    Apostrophe,
-    Semicolon,
-    Backslash,
-    Grave,
-
-    Other(u32),
-}
+/// Declares [`Key`] and [`Key::ALL`] from one list of variants, so the
+/// list can't miss a key.
+macro_rules! keys {
+    (
+        $(#[$meta:meta])*
+        pub enum Key { $($variant:ident,)* }
+    ) => {
+        $(#[$meta])*
+        pub enum Key {
+            $($variant,)*
+        }
 
-pub(crate) fn key_code_for_key_string(ch: char) -> u16 {
-    match ch {
-        'a' => 0,
-        's' => 1,
-        'd' => 2,
-        'f' => 3,
-        'h' => 4,
-        'g' => 5,
-        'z' => 6,
-        'x' => 7,
-        'c' => 8,
-        'v' => 9,
-        'b' => 11,
-        'q' => 12,
-        'w' => 13,
-        'e' => 14,
-        'r' => 15,
-        'y' => 16,
-        't' => 17,
-        '1' => 18,
-        '2' => 19,
-        '3' => 20,
-        '4' => 21,
-        '6' => 22,
-        '5' => 23,
-        '=' => 24,
-        '9' => 25,
-        '7' => 26,
-        '-' => 27,
-        '8' => 28,
-        '0' => 29,
-        ']' => 30,
-        'o' => 31,
-        'u' => 32,
-        '[' => 33,
-        'i' => 34,
-        'p' => 35,
-        'l' => 37,
-        'j' => 38,
-        '\'' => 39,
-        'k' => 40,
-        ';' => 41,
-        '\\' => 42,
-        ',' => 43,
-        '/' => 44,
-        'n' => 45,
-        'm' => 46,
-        '.' => 47,
-        '*' => 67,
-        '+' => 69,
-        '`' => 50,
-        _ => {
-            unreachable!()
-            //eprintln!("keyString {} Not Found. Aborting...", key_string);
-            //std::process::exit(1);
+        impl Key {
+            /// Every key, in declaration order.
+            pub const ALL: &'static [Key] = &[$(Key::$variant,)*];
         }
-    }
+    };
 }
 
-impl From<Key> for EnigoKey {
-    fn from(key: Key) -> Self {
-        key.to_enigo()
-    }
-}
+keys! {
+    /// A key that can be emulated.
+    ///
+    /// Keys are physical and platform-neutral: every variant exists on every
+    /// OS, so a key name means the same key everywhere. Backends translate a
+    /// key to their native code through the tables in [`crate::keycode`]; keys
+    /// a backend cannot send are reported by [`Key::is_supported`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Key {
+        // Letters
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
 
-impl From<&Key> for EnigoKey {
-    fn from(key: &Key) -> Self {
-        key.to_enigo()
+        // Digits (top row)
+        Digit0,
+        Digit1,
+        Digit2,
+        Digit3,
+        Digit4,
+        Digit5,
+        Digit6,
+        Digit7,
+        Digit8,
+        Digit9,
+
+        // Punctuation (US-ANSI positions)
+        Minus,
+        Equal,
+        BracketLeft,
+        BracketRight,
+        Backslash,
+        Semicolon,
+        Apostrophe,
+        Grave,
+        Comma,
+        Period,
+        Slash,
+
+        // Modifiers
+        Control,
+        RControl,
+        Meta,
+        RMeta,
+        Shift,
+        RShift,
+        Alt,
+        RAlt,
+        CapsLock,
+
+        // Navigation
+        Home,
+        End,
+        PageUp,
+        PageDown,
+        UpArrow,
+        DownArrow,
+        LeftArrow,
+        RightArrow,
+
+        // Editing
+        Delete,
+        Backspace,
+        Insert,
+        Escape,
+        Tab,
+        Space,
+        Return,
+
+        // System
+        PrintScreen,
+        ScrollLock,
+        Pause,
+        NumLock,
+
+        // Media
+        VolumeUp,
+        VolumeDown,
+        VolumeMute,
+        MediaPlayPause,
+        MediaNextTrack,
+        MediaPrevTrack,
+        MediaStop,
+        BrightnessUp,
+        BrightnessDown,
+
+        // Function keys
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+
+        // Numpad
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadDecimal,
+        NumpadMultiply,
+        NumpadPlus,
+        NumpadMinus,
+        NumpadDivide,
+        NumpadEnter,
+        NumpadEquals,
+        NumpadClear,
     }
 }
 
 impl Key {
-    pub fn to_enigo(&self) -> EnigoKey {
+    /// Canonical profile name of the key. `parse_key(key.name())` always
+    /// returns the key back.
+    pub const fn name(self) -> &'static str {
         match self {
+            Key::A => "a",
+            Key::B => "b",
+            Key::C => "c",
+            Key::D => "d",
+            Key::E => "e",
+            Key::F => "f",
+            Key::G => "g",
+            Key::H => "h",
+            Key::I => "i",
+            Key::J => "j",
+            Key::K => "k",
+            Key::L => "l",
+            Key::M => "m",
+            Key::N => "n",
+            Key::O => "o",
+            Key::P => "p",
+            Key::Q => "q",
+            Key::R => "r",
+            Key::S => "s",
+            Key::T => "t",
+            Key::U => "u",
+            Key::V => "v",
+            Key::W => "w",
+            Key::X => "x",
+            Key::Y => "y",
+            Key::Z => "z",
+            Key::Digit0 => "0",
+            Key::Digit1 => "1",
+            Key::Digit2 => "2",
+            Key::Digit3 => "3",
+            Key::Digit4 => "4",
+            Key::Digit5 => "5",
+            Key::Digit6 => "6",
+            Key::Digit7 => "7",
+            Key::Digit8 => "8",
+            Key::Digit9 => "9",
+            Key::Minus => "minus",
+            Key::Equal => "equal",
+            Key::BracketLeft => "bracket_left",
+            Key::BracketRight => "bracket_right",
+            Key::Backslash => "backslash",
+            Key::Semicolon => "semicolon",
+            Key::Apostrophe => "quote",
+            Key::Grave => "grave",
+            Key::Comma => "comma",
+            Key::Period => "period",
+            Key::Slash => "slash",
+            Key::Control => "ctrl",
+            Key::RControl => "rctrl",
+            Key::Meta => "cmd",
+            Key::RMeta => "rcmd",
+            Key::Shift => "shift",
+            Key::RShift => "rshift",
+            Key::Alt => "alt",
+            Key::RAlt => "ralt",
+            Key::CapsLock => "caps_lock",
+            Key::Home => "home",
+            Key::End => "end",
+            Key::PageUp => "page_up",
+            Key::PageDown => "page_down",
+            Key::UpArrow => "arrow_up",
+            Key::DownArrow => "arrow_down",
+            Key::LeftArrow => "arrow_left",
+            Key::RightArrow => "arrow_right",
+            Key::Delete => "delete",
+            Key::Backspace => "backspace",
+            Key::Insert => "insert",
+            Key::Escape => "escape",
+            Key::Tab => "tab",
+            Key::Space => "space",
+            Key::Return => "enter",
+            Key::PrintScreen => "print_screen",
+            Key::ScrollLock => "scroll_lock",
+            Key::Pause => "pause",
+            Key::NumLock => "num_lock",
+            Key::VolumeUp => "volume_up",
+            Key::VolumeDown => "volume_down",
+            Key::VolumeMute => "volume_mute",
+            Key::MediaPlayPause => "media_play_pause",
+            Key::MediaNextTrack => "media_next",
+            Key::MediaPrevTrack => "media_prev",
+            Key::MediaStop => "media_stop",
+            Key::BrightnessUp => "brightness_up",
+            Key::BrightnessDown => "brightness_down",
+            Key::F1 => "f1",
+            Key::F2 => "f2",
+            Key::F3 => "f3",
+            Key::F4 => "f4",
+            Key::F5 => "f5",
+            Key::F6 => "f6",
+            Key::F7 => "f7",
+            Key::F8 => "f8",
+            Key::F9 => "f9",
+            Key::F10 => "f10",
+            Key::F11 => "f11",
+            Key::F12 => "f12",
+            Key::F13 => "f13",
+            Key::F14 => "f14",
+            Key::F15 => "f15",
+            Key::F16 => "f16",
+            Key::F17 => "f17",
+            Key::F18 => "f18",
+            Key::F19 => "f19",
+            Key::F20 => "f20",
+            Key::F21 => "f21",
+            Key::F22 => "f22",
+            Key::F23 => "f23",
+            Key::F24 => "f24",
+            Key::Numpad0 => "kp_0",
+            Key::Numpad1 => "kp_1",
+            Key::Numpad2 => "kp_2",
+            Key::Numpad3 => "kp_3",
+            Key::Numpad4 => "kp_4",
+            Key::Numpad5 => "kp_5",
+            Key::Numpad6 => "kp_6",
+            Key::Numpad7 => "kp_7",
+            Key::Numpad8 => "kp_8",
+            Key::Numpad9 => "kp_9",
+            Key::NumpadDecimal => "kp_decimal",
+            Key::NumpadMultiply => "kp_multiply",
+            Key::NumpadPlus => "kp_plus",
+            Key::NumpadMinus => "kp_minus",
+            Key::NumpadDivide => "kp_divide",
+            Key::NumpadEnter => "kp_enter",
+            Key::NumpadEquals => "kp_equals",
+            Key::NumpadClear => "kp_clear",
+        }
+    }
+
+    /// Whether the current platform's backend can send this key.
+    pub fn is_supported(self) -> bool {
+        self.to_enigo().is_some()
+    }
+
+    /// Translate the key for the enigo backend.
+    ///
+    /// On macOS, modifiers and media keys go through enigo's named keys
+    /// (media keys are system-defined events, not keycodes); everything
+    /// else is posted by virtual keycode, so the key is layout-independent.
+    /// Returns `None` if the key has no equivalent on this platform.
+    #[cfg(target_os = "macos")]
+    pub fn to_enigo(self) -> Option<EnigoKey> {
+        let named = match self {
             Key::Control => EnigoKey::Control,
             Key::RControl => EnigoKey::RControl,
             Key::Meta => EnigoKey::Meta,
-            #[cfg(target_os = "macos")]
-            Key::RCommand => EnigoKey::RCommand,
+            Key::RMeta => EnigoKey::RCommand,
             Key::Shift => EnigoKey::Shift,
             Key::RShift => EnigoKey::RShift,
             Key::Alt => EnigoKey::Alt,
-            Key::RAlt => EnigoKey::Alt,
-            Key::Home => EnigoKey::Home,
-            Key::End => EnigoKey::End,
-            Key::PageUp => EnigoKey::PageUp,
-            Key::PageDown => EnigoKey::PageDown,
-            Key::UpArrow => EnigoKey::UpArrow,
-            Key::DownArrow => EnigoKey::DownArrow,
-            Key::LeftArrow => EnigoKey::LeftArrow,
-            Key::RightArrow => EnigoKey::RightArrow,
-            Key::Delete => EnigoKey::Delete,
-            Key::Backspace => EnigoKey::Backspace,
-            Key::Escape => EnigoKey::Escape,
-            Key::Tab => EnigoKey::Tab,
-            Key::Space => EnigoKey::Space,
-            Key::Return => EnigoKey::Return,
+            Key::RAlt => EnigoKey::ROption,
             Key::VolumeUp => EnigoKey::VolumeUp,
             Key::VolumeDown => EnigoKey::VolumeDown,
             Key::VolumeMute => EnigoKey::VolumeMute,
-            #[cfg(target_os = "macos")]
+            Key::MediaPlayPause => EnigoKey::MediaPlayPause,
+            Key::MediaNextTrack => EnigoKey::MediaNextTrack,
+            Key::MediaPrevTrack => EnigoKey::MediaPrevTrack,
             Key::BrightnessUp => EnigoKey::BrightnessUp,
-            #[cfg(target_os = "macos")]
             Key::BrightnessDown => EnigoKey::BrightnessDown,
-            Key::F1 => EnigoKey::F1,
-            Key::F2 => EnigoKey::F2,
-            Key::F3 => EnigoKey::F3,
-            Key::F4 => EnigoKey::F4,
-            Key::F5 => EnigoKey::F5,
-            Key::F6 => EnigoKey::F6,
-            Key::F7 => EnigoKey::F7,
-            Key::F8 => EnigoKey::F8,
-            Key::F9 => EnigoKey::F9,
-            Key::F10 => EnigoKey::F10,
-            Key::F11 => EnigoKey::F11,
-            Key::F12 => EnigoKey::F12,
-            Key::F13 => EnigoKey::F13,
-            Key::F14 => EnigoKey::F14,
-            Key::F15 => EnigoKey::F15,
-            Key::F16 => EnigoKey::F16,
-            Key::F17 => EnigoKey::F17,
-            Key::F18 => EnigoKey::F18,
-            Key::F19 => EnigoKey::F19,
-            Key::F20 => EnigoKey::F20,
-            Key::Unicode(ch) => EnigoKey::Other(key_code_for_key_string(*ch) as u32),
-            Key::Apostrophe => EnigoKey::Other(key_code_for_key_string('\'') as u32),
-            Key::Semicolon => EnigoKey::Other(key_code_for_key_string(';') as u32),
-            Key::Backslash => EnigoKey::Other(key_code_for_key_string('\\') as u32),
-            Key::Grave => EnigoKey::Other(key_code_for_key_string('`') as u32),
-            Key::Other(code) => EnigoKey::Other(*code),
-        }
+            other => {
+                return keycode::mac::virtual_keycode(other)
+                    .map(|code| EnigoKey::Other(u32::from(code)))
+            }
+        };
+        Some(named)
+    }
+
+    /// Translate the key for the enigo backend.
+    ///
+    /// On X11, enigo's `Other` carries a keysym, so every key goes through
+    /// the keysym table. Returns `None` if the key has no keysym.
+    #[cfg(not(target_os = "macos"))]
+    pub fn to_enigo(self) -> Option<EnigoKey> {
+        keycode::x11::keysym(self).map(EnigoKey::Other)
     }
 }
 
@@ -206,126 +369,107 @@ impl Key {
 ///
 /// ```ignore
 /// let key = parse_key("a");
-/// assert_eq!(key, Some(Key::Unicode('a')));
+/// assert_eq!(key, Some(Key::A));
 /// ```
 pub(crate) fn parse_key(input: &str) -> Option<Key> {
     if input.is_empty() {
         return None;
     }
 
-    if input.len() == 1 {
-        let ch = input.chars().next().expect("input must be not empty");
-        if ch.is_ascii_lowercase() || ch.is_ascii_digit() {
-            return Some(Key::Other(key_code_for_key_string(ch) as u32));
+    let alias = match input {
+        // Modifiers; unsided names are the left key
+        "control" | "lctrl" | "lcontrol" => Some(Key::Control),
+        "rcontrol" => Some(Key::RControl),
+        "meta" | "command" | "super" | "lcmd" | "lmeta" | "lcommand" | "lsuper" => {
+            Some(Key::Meta)
         }
-    }
+        "rmeta" | "rcommand" | "rsuper" => Some(Key::RMeta),
+        "lshift" => Some(Key::Shift),
+        "option" | "lalt" | "loption" => Some(Key::Alt),
+        "roption" => Some(Key::RAlt),
+        "capslock" => Some(Key::CapsLock),
 
-    match input {
-        // Modifiers
-        "ctrl" => Some(Key::Control),
-        "rctrl" => Some(Key::RControl),
-        "meta" => Some(Key::Meta),
-        #[cfg(target_os = "macos")]
-        "rmeta" => Some(Key::RCommand),
-        "cmd" => Some(Key::Meta),
-        #[cfg(target_os = "macos")]
-        "rcmd" => Some(Key::RCommand),
-        "command" => Some(Key::Meta),
-        #[cfg(target_os = "macos")]
-        "rcommand" => Some(Key::RCommand),
-        "super" => Some(Key::Meta),
-        #[cfg(target_os = "macos")]
-        "rsuper" => Some(Key::RCommand),
-        "shift" => Some(Key::Shift),
-        "alt" => Some(Key::Alt),
-        "option" => Some(Key::Alt),
-
-        // Navigation
-        "home" => Some(Key::Home),
-        "end" => Some(Key::End),
-        "page_up" => Some(Key::PageUp),
-        "page_down" => Some(Key::PageDown),
-        "arrow_up" => Some(Key::UpArrow),
-        "arrow_down" => Some(Key::DownArrow),
-        "arrow_left" => Some(Key::LeftArrow),
-        "arrow_right" => Some(Key::RightArrow),
+        // macOS glyphs
+        "⌃" => Some(Key::Control),
+        "⌥" => Some(Key::Alt),
+        "⇧" => Some(Key::Shift),
+        "⌘" => Some(Key::Meta),
+        "⇪" => Some(Key::CapsLock),
+        "↩" | "⏎" | "⌤" => Some(Key::Return),
+        "⌫" => Some(Key::Backspace),
+        "⌦" => Some(Key::Delete),
+        "⎋" => Some(Key::Escape),
+        "⇥" => Some(Key::Tab),
+        "␣" => Some(Key::Space),
+        "←" => Some(Key::LeftArrow),
+        "→" => Some(Key::RightArrow),
+        "↑" => Some(Key::UpArrow),
+        "↓" => Some(Key::DownArrow),
+        "⇞" => Some(Key::PageUp),
+        "⇟" => Some(Key::PageDown),
+        "↖" => Some(Key::Home),
+        "↘" => Some(Key::End),
 
         // Actions
-        "delete" => Some(Key::Delete),
-        "backspace" => Some(Key::Backspace),
         "escape" | "esc" => Some(Key::Escape),
-        "tab" => Some(Key::Tab),
-        "space" | "spacebar" => Some(Key::Space),
-        "enter" | "return" => Some(Key::Return),
+        "spacebar" => Some(Key::Space),
+        "return" => Some(Key::Return),
+        "printscreen" | "print" => Some(Key::PrintScreen),
 
-        // Media
-        "volume_up" => Some(Key::VolumeUp),
-        "volume_down" => Some(Key::VolumeDown),
-        "volume_mute" => Some(Key::VolumeMute),
+        // Special characters
+        "'" | "apostrophe" => Some(Key::Apostrophe),
+        ";" => Some(Key::Semicolon),
+        "\\" => Some(Key::Backslash),
+        "`" | "backtick" | "tilde" => Some(Key::Grave),
+        "." | "dot" => Some(Key::Period),
+        "," => Some(Key::Comma),
+        "/" => Some(Key::Slash),
+        "-" => Some(Key::Minus),
+        "=" => Some(Key::Equal),
+        "[" | "left_bracket" => Some(Key::BracketLeft),
+        "]" | "right_bracket" => Some(Key::BracketRight),
 
-        #[cfg(target_os = "macos")]
-        "brightness_up" => Some(Key::BrightnessUp),
-        #[cfg(target_os = "macos")]
-        "brightness_down" => Some(Key::BrightnessDown),
+        // ANSI letter aliases kept for older profiles
+        "ansi_k" => Some(Key::K),
+        "ansi_n" => Some(Key::N),
+        "ansi_m" => Some(Key::M),
+        _ => None,
+    };
+    if alias.is_some() {
+        return alias;
+    }
 
-        // Special characters
-        // Using codes from
-        "'" | "quote" | "apostrophe" => Some(Key::Apostrophe),
-        ";" | "semicolon" => Some(Key::Semicolon),
-        "\\" | "backslash" => Some(Key::Backslash),
-        "`" | "grave" | "backtick" | "tilde" => Some(Key::Grave),
+    // `keypad_*` is an alias for every `kp_*` name.
+    let name = input.strip_prefix("keypad_").map_or_else(
+        || std::borrow::Cow::Borrowed(input),
+        |rest| std::borrow::Cow::Owned(format!("kp_{rest}")),
+    );
+    Key::ALL.iter().copied().find(|key| key.name() == name)
+}
 
-        // Provide ANSI letter scancode aliases to avoid single-char Unicode path
-        "ansi_k" => Some(Key::Other(0x28)),
-        "ansi_n" => Some(Key::Other(0x2D)),
-        "ansi_m" => Some(Key::Other(0x2E)),
-        // Keypad (numpad) keys
-        "kp_decimal" | "keypad_decimal" => Some(Key::Other(0x41)),
-        "kp_multiply" | "keypad_multiply" => Some(Key::Other(0x43)),
-        "kp_plus" | "keypad_plus" => Some(Key::Other(0x45)),
-        "kp_clear" | "keypad_clear" => Some(Key::Other(0x47)),
-        "kp_divide" | "keypad_divide" => Some(Key::Other(0x4B)),
-        "kp_enter" | "keypad_enter" => Some(Key::Other(0x4C)),
-        "kp_minus" | "keypad_minus" => Some(Key::Other(0x4E)),
-        "kp_equals" | "keypad_equals" => Some(Key::Other(0x51)),
-        "kp_0" | "keypad_0" => Some(Key::Other(0x52)),
-        "kp_1" | "keypad_1" => Some(Key::Other(0x53)),
-        "kp_2" | "keypad_2" => Some(Key::Other(0x54)),
-        "kp_3" | "keypad_3" => Some(Key::Other(0x55)),
-        "kp_4" | "keypad_4" => Some(Key::Other(0x56)),
-        "kp_5" | "keypad_5" => Some(Key::Other(0x57)),
-        "kp_6" | "keypad_6" => Some(Key::Other(0x58)),
-        "kp_7" | "keypad_7" => Some(Key::Other(0x59)),
-        "kp_8" | "keypad_8" => Some(Key::Other(0x5B)),
-        "kp_9" | "keypad_9" => Some(Key::Other(0x5C)),
-        "." | "period" | "dot" => Some(Key::Other(0x2f)),
-        "," | "comma" => Some(Key::Other(0x2b)),
-        "/" | "slash" => Some(Key::Other(0x2c)),
-        "-" | "minus" => Some(Key::Other(0x1b)),
-        "=" | "equal" => Some(Key::Other(0x18)),
-        "[" | "bracket_left" | "left_bracket" => Some(Key::Other(0x21)),
-        "]" | "bracket_right" | "right_bracket" => Some(Key::Other(0x1e)),
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        "f1" => Some(Key::F1),
-        "f2" => Some(Key::F2),
-        "f3" => Some(Key::F3),
-        "f4" => Some(Key::F4),
-        "f5" => Some(Key::F5),
-        "f6" => Some(Key::F6),
-        "f7" => Some(Key::F7),
-        "f8" => Some(Key::F8),
-        "f9" => Some(Key::F9),
-        "f10" => Some(Key::F10),
-        "f11" => Some(Key::F11),
-        "f12" => Some(Key::F12),
-        "f13" => Some(Key::F13),
-        "f14" => Some(Key::F14),
-        "f15" => Some(Key::F15),
-        "f16" => Some(Key::F16),
-        "f17" => Some(Key::F17),
-        "f18" => Some(Key::F18),
-        "f19" => Some(Key::F19),
-        "f20" => Some(Key::F20),
-        _ => None,
+    #[test]
+    fn names_round_trip() {
+        for key in Key::ALL {
+            assert_eq!(parse_key(key.name()), Some(*key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn platform_specific_names_parse_everywhere() {
+        assert_eq!(parse_key("rcmd"), Some(Key::RMeta));
+        assert_eq!(parse_key("brightness_up"), Some(Key::BrightnessUp));
+        assert_eq!(parse_key("f24"), Some(Key::F24));
+        assert_eq!(parse_key("keypad_enter"), Some(Key::NumpadEnter));
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(parse_key("A"), None);
+        assert_eq!(parse_key("@"), None);
+        assert_eq!(parse_key("f25"), None);
     }
 }
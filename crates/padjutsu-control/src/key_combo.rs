@@ -2,10 +2,7 @@ use crate::{
     key::{parse_key, Key},
     Modifier, Modifiers,
 };
-use enigo::{
-    Direction::{Click, Press, Release},
-    Enigo, InputResult, Keyboard,
-};
+use enigo::{InputError, InputResult};
 use smallvec::SmallVec;
 use serde::{
     de::{value::Error as DeError, IntoDeserializer},
@@ -31,6 +28,28 @@ impl KeyCombo {
             },
         }
     }
+
+    /// Every key of the combo in press order: modifiers in canonical order,
+    /// then keys as written. Release order is the reverse.
+    pub fn press_order(&self) -> impl DoubleEndedIterator<Item = Key> + '_ {
+        self.modifiers
+            .iter()
+            .map(|m| m.key())
+            .chain(self.keys.iter().copied())
+    }
+
+    /// Keys in the combo that the current platform's backend cannot send.
+    pub fn unsupported_keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys.iter().copied().filter(|key| !key.is_supported())
+    }
+}
+
+/// Translate a key for enigo, failing instead of panicking on keys the
+/// platform has no equivalent for.
+pub(crate) fn to_enigo_key(key: Key) -> InputResult<enigo::Key> {
+    key.to_enigo().ok_or(InputError::InvalidInput(
+        "key is not supported on this platform",
+    ))
 }
 
 impl<'de> Deserialize<'de> for KeyCombo {
@@ -51,30 +70,7 @@ impl<'de> Deserialize<'de> for KeyCombo {
             where
                 E: serde::de::Error,
             {
-                let mut modifiers: Modifiers = Modifiers::empty();
-                let mut keys: SmallVec<[Key; 4]> = SmallVec::new();
-                for combo in v.split('+') {
-                    let part = combo.trim();
-                    match parse_key(part) {
-                        Some(k) => match k {
-                            Key::Control | Key::Meta | Key::Shift | Key::Alt => {
-                                modifiers.add(Modifier::from(k));
-                            }
-                            _ => {
-                                keys.push(k);
-                            }
-                        },
-                        None => {
-                            return Err(E::custom(format!(
-                                "Invalid key '{part}' in combo '{v}'. \
-                                 Use named keys (e.g. bracket_left, bracket_right, slash, comma) \
-                                 or single lowercase letters (a-z)."
-                            )));
-                        }
-                    }
-                }
-
-                Ok(KeyCombo { modifiers, keys })
+                parse_combo(v).map_err(E::custom)
             }
         }
 
@@ -82,131 +78,74 @@ impl<'de> Deserialize<'de> for KeyCombo {
     }
 }
 
-impl KeyCombo {
-    pub fn perform(&self, enigo: &mut Enigo) -> InputResult<()> {
-        log::info!(
-            "[key_combo] perform: modifiers={:?} keys={:?}",
-            self.modifiers,
-            self.keys
-        );
-        if self.modifiers.contains(Modifier::Ctrl) {
-            log::info!(
-                "[key_combo] perform: Press Control (enigo={:?})",
-                enigo::Key::from(Key::Control)
-            );
-            enigo.key(Key::Control.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Meta) {
-            log::info!(
-                "[key_combo] perform: Press Meta (enigo={:?})",
-                enigo::Key::from(Key::Meta)
-            );
-            enigo.key(Key::Meta.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Shift) {
-            log::info!(
-                "[key_combo] perform: Press Shift (enigo={:?})",
-                enigo::Key::from(Key::Shift)
-            );
-            enigo.key(Key::Shift.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Alt) {
-            log::info!(
-                "[key_combo] perform: Press Alt (enigo={:?})",
-                enigo::Key::from(Key::Alt)
-            );
-            enigo.key(Key::Alt.into(), Press)?;
-        }
-
-        for key in self.keys.iter() {
-            let enigo_key: enigo::Key = key.into();
-            log::info!(
-                "[key_combo] perform: Click key={key:?} enigo_key={enigo_key:?}"
-            );
-            enigo.key(enigo_key, Click)?;
-        }
-
-        if self.modifiers.contains(Modifier::Ctrl) {
-            enigo.key(Key::Control.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Meta) {
-            enigo.key(Key::Meta.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Shift) {
-            enigo.key(Key::Shift.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Alt) {
-            enigo.key(Key::Alt.into(), Release)?;
+/// Parse a combo string.
+///
+/// Terms are joined with `+` and matched case-insensitively. A term may
+/// also be a run of macOS glyphs, with modifier glyphs first (`⌘⇧K`).
+/// Modifiers can appear anywhere; other keys keep their written order,
+/// which is the order they are pressed in.
+fn parse_combo(v: &str) -> Result<KeyCombo, String> {
+    let mut modifiers: Modifiers = Modifiers::empty();
+    let mut keys: SmallVec<[Key; 4]> = SmallVec::new();
+    for combo in v.split('+') {
+        let part = combo.trim();
+        let mut add_modifier = |m: Modifier| {
+            if modifiers.contains(m) {
+                return Err(format!("Duplicate modifier '{part}' in combo '{v}'."));
+            }
+            modifiers.add(m);
+            Ok(())
+        };
+        let mut rest = part.to_lowercase();
+        let key = loop {
+            if let Some(k) = parse_key(&rest) {
+                break Some(k);
+            }
+            // Peel off a leading modifier glyph and retry with the rest.
+            let Some(first) = rest.chars().next().filter(|c| !c.is_ascii()) else {
+                break None;
+            };
+            let glyph = &rest[..first.len_utf8()];
+            match parse_key(glyph).and_then(Modifier::from_key) {
+                Some(m) => {
+                    add_modifier(m)?;
+                    rest = rest[first.len_utf8()..].to_string();
+                }
+                None => break None,
+            }
+        };
+        match key {
+            Some(k) => match Modifier::from_key(k) {
+                Some(m) => add_modifier(m)?,
+                None if keys.contains(&k) => {
+                    return Err(format!("Duplicate key '{part}' in combo '{v}'."));
+                }
+                None => keys.push(k),
+            },
+            None => {
+                return Err(format!(
+                    "Invalid key '{part}' in combo '{v}'. \
+                     Use named keys (e.g. bracket_left, bracket_right, slash, comma), \
+                     single letters (a-z) or macOS glyphs (⌘⇧K)."
+                ));
+            }
         }
-        log::info!("[key_combo] perform: done");
-
-        Ok(())
     }
 
-    pub fn press(&self, enigo: &mut Enigo) -> InputResult<()> {
-        log::info!(
-            "[key_combo] press: modifiers={:?} keys={:?}",
-            self.modifiers,
-            self.keys
-        );
-        if self.modifiers.contains(Modifier::Ctrl) {
-            log::info!("[key_combo] press: Press modifier Control");
-            enigo.key(Key::Control.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Meta) {
-            log::info!("[key_combo] press: Press modifier Meta");
-            enigo.key(Key::Meta.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Shift) {
-            log::info!("[key_combo] press: Press modifier Shift");
-            enigo.key(Key::Shift.into(), Press)?;
-        }
-        if self.modifiers.contains(Modifier::Alt) {
-            log::info!("[key_combo] press: Press modifier Alt");
-            enigo.key(Key::Alt.into(), Press)?;
-        }
-        for key in self.keys.iter() {
-            let enigo_key: enigo::Key = key.into();
-            log::info!(
-                "[key_combo] press: Press key={key:?} enigo_key={enigo_key:?}"
-            );
-            enigo.key(enigo_key, Press)?;
-        }
-        log::info!("[key_combo] press: done");
-
-        Ok(())
-    }
+    Ok(KeyCombo { modifiers, keys })
+}
 
-    pub fn release(&self, enigo: &mut Enigo) -> InputResult<()> {
-        log::info!(
-            "[key_combo] release: modifiers={:?} keys={:?}",
-            self.modifiers,
-            self.keys
-        );
-        if self.modifiers.contains(Modifier::Ctrl) {
-            log::info!("[key_combo] release: Release modifier Control");
-            enigo.key(Key::Control.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Meta) {
-            log::info!("[key_combo] release: Release modifier Meta");
-            enigo.key(Key::Meta.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Shift) {
-            log::info!("[key_combo] release: Release modifier Shift");
-            enigo.key(Key::Shift.into(), Release)?;
-        }
-        if self.modifiers.contains(Modifier::Alt) {
-            log::info!("[key_combo] release: Release modifier Alt");
-            enigo.key(Key::Alt.into(), Release)?;
-        }
-        for key in self.keys.iter() {
-            let enigo_key: enigo::Key = key.into();
-            log::info!(
-                "[key_combo] release: Release key={key:?} enigo_key={enigo_key:?}"
-            );
-            enigo.key(enigo_key, Release)?;
+impl fmt::Display for KeyCombo {
+    /// Canonical form: modifiers in canonical order, then keys in press
+    /// order, all by canonical name. Parsing it gives back an equal combo.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.press_order().map(Key::name);
+        for (i, name) in names.enumerate() {
+            if i > 0 {
+                f.write_str("+")?;
+            }
+            f.write_str(name)?;
         }
-        log::info!("[key_combo] release: done");
         Ok(())
     }
 }
@@ -222,8 +161,6 @@ impl std::str::FromStr for KeyCombo {
 
 #[cfg(test)]
 mod tests {
-    use crate::key::key_code_for_key_string;
-
     use super::*;
     use serde::de::value::Error as DeError;
     use serde::de::IntoDeserializer;
@@ -277,6 +214,148 @@ mod tests {
         assert!(kc.modifiers.contains(Modifier::Alt));
         assert!(kc.modifiers.contains(Modifier::Shift));
         assert_eq!(kc.keys.len(), 1);
-        assert_eq!(kc.keys[0], Key::Other(key_code_for_key_string('a') as u32));
+        assert_eq!(kc.keys[0], Key::A);
+    }
+
+    #[test]
+    fn test_sided_modifiers() {
+        let kc = parse("rcmd+a").unwrap();
+        assert!(kc.modifiers.contains(Modifier::RMeta));
+        assert!(!kc.modifiers.contains(Modifier::Meta));
+        assert_eq!(kc.keys.as_slice(), [Key::A]);
+
+        let kc = parse("lshift+rshift").unwrap();
+        assert!(kc.modifiers.contains(Modifier::Shift));
+        assert!(kc.modifiers.contains(Modifier::RShift));
+        assert!(kc.keys.is_empty());
+
+        let kc = parse("ralt+x").unwrap();
+        assert!(kc.modifiers.contains(Modifier::RAlt));
+        assert!(!kc.modifiers.contains(Modifier::Alt));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        assert_eq!(parse("Cmd+Shift+K").unwrap(), parse("cmd+shift+k").unwrap());
+        assert_eq!(
+            parse("CTRL+Page_Up").unwrap(),
+            parse("ctrl+page_up").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_glyphs() {
+        assert_eq!(parse("⌘⇧K").unwrap(), parse("cmd+shift+k").unwrap());
+        assert_eq!(parse("⌃⌥⌫").unwrap(), parse("ctrl+alt+backspace").unwrap());
+        assert_eq!(parse("⌘+↩").unwrap(), parse("cmd+enter").unwrap());
+        assert!(parse("⌘⇧").unwrap().keys.is_empty());
+        assert!(parse("⌘⇧foo").is_err());
+    }
+
+    #[test]
+    fn test_press_order_preserved() {
+        let kc = parse("b+ctrl+a+c").unwrap();
+        assert_eq!(kc.keys.as_slice(), [Key::B, Key::A, Key::C]);
+    }
+
+    #[test]
+    fn test_duplicate_key_rejected() {
+        let err = parse("a+a").unwrap_err();
+        assert!(err.contains("Duplicate key"));
+    }
+
+    #[test]
+    fn test_duplicate_modifier_rejected() {
+        for combo in ["cmd+cmd+k", "⌘+cmd+k", "⌘⌘k", "ctrl+lctrl"] {
+            let err = parse(combo).unwrap_err();
+            assert!(err.contains("Duplicate modifier"), "{combo}: {err}");
+        }
+        assert!(parse("ctrl+rctrl+k").is_ok());
+    }
+
+    #[test]
+    fn test_display_canonical() {
+        let kc = parse("K+⇧+Command+rctrl").unwrap();
+        assert_eq!(kc.to_string(), "rctrl+shift+cmd+k");
+        assert_eq!(parse("option+[").unwrap().to_string(), "alt+bracket_left");
+    }
+
+    /// Minimal xorshift generator so the property tests are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    fn random_combo(rng: &mut Rng) -> KeyCombo {
+        let non_modifiers: Vec<Key> = Key::ALL
+            .iter()
+            .copied()
+            .filter(|k| Modifier::from_key(*k).is_none())
+            .collect();
+        let mut modifiers = Modifiers::empty();
+        for m in Modifier::ALL {
+            if rng.below(4) == 0 {
+                modifiers.add(m);
+            }
+        }
+        let mut keys: SmallVec<[Key; 4]> = SmallVec::new();
+        let count = if modifiers.is_empty() { 1 } else { 0 } + rng.below(4);
+        while keys.len() < count {
+            let key = non_modifiers[rng.below(non_modifiers.len())];
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        KeyCombo { modifiers, keys }
+    }
+
+    #[test]
+    fn prop_display_parse_round_trip() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..2000 {
+            let combo = random_combo(&mut rng);
+            let text = combo.to_string();
+            let parsed = parse(&text).unwrap_or_else(|e| panic!("{text}: {e}"));
+            assert_eq!(parsed, combo, "{text}");
+            assert_eq!(parsed.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn prop_parse_display_parse_stable() {
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+        for _ in 0..2000 {
+            let combo = random_combo(&mut rng);
+            // Scatter modifiers between the keys and vary the case; the
+            // canonical form must not depend on either.
+            let mut terms: Vec<&str> = combo.keys.iter().map(|k| k.name()).collect();
+            for m in combo.modifiers.iter() {
+                terms.insert(rng.below(terms.len() + 1), m.key().name());
+            }
+            let noisy: Vec<String> = terms
+                .into_iter()
+                .map(|t| {
+                    if rng.below(2) == 0 {
+                        t.to_uppercase()
+                    } else {
+                        t.to_string()
+                    }
+                })
+                .collect();
+            let first = parse(&noisy.join("+")).unwrap();
+            let second = parse(&first.to_string()).unwrap();
+            assert_eq!(first, second);
+            assert_eq!(first, combo);
+        }
     }
 }
@@ -0,0 +1,475 @@
+//! Native keycode tables, one per output backend.
+//!
+//! Tables are plain data and compiled on every platform, so a profile can be
+//! checked against any backend regardless of the host OS. A `None` entry
+//! means the backend has no way to send that key.
+
+/// macOS virtual keycodes (`kVK_*` from Carbon's `Events.h`).
+pub mod mac {
+    use crate::Key;
+
+    /// Virtual keycode for a key, by physical position on an ANSI keyboard.
+    ///
+    /// Media and brightness keys are not keycodes on macOS — they are posted
+    /// as system-defined events — so they return `None` here. PC-only keys
+    /// map to the codes macOS assigns them on PC keyboards (Insert → Help,
+    /// Print Screen/Scroll Lock/Pause → F13/F14/F15, Num Lock → Clear).
+    pub const fn virtual_keycode(key: Key) -> Option<u16> {
+        Some(match key {
+            Key::A => 0x00,
+            Key::S => 0x01,
+            Key::D => 0x02,
+            Key::F => 0x03,
+            Key::H => 0x04,
+            Key::G => 0x05,
+            Key::Z => 0x06,
+            Key::X => 0x07,
+            Key::C => 0x08,
+            Key::V => 0x09,
+            Key::B => 0x0B,
+            Key::Q => 0x0C,
+            Key::W => 0x0D,
+            Key::E => 0x0E,
+            Key::R => 0x0F,
+            Key::Y => 0x10,
+            Key::T => 0x11,
+            Key::Digit1 => 0x12,
+            Key::Digit2 => 0x13,
+            Key::Digit3 => 0x14,
+            Key::Digit4 => 0x15,
+            Key::Digit6 => 0x16,
+            Key::Digit5 => 0x17,
+            Key::Equal => 0x18,
+            Key::Digit9 => 0x19,
+            Key::Digit7 => 0x1A,
+            Key::Minus => 0x1B,
+            Key::Digit8 => 0x1C,
+            Key::Digit0 => 0x1D,
+            Key::BracketRight => 0x1E,
+            Key::O => 0x1F,
+            Key::U => 0x20,
+            Key::BracketLeft => 0x21,
+            Key::I => 0x22,
+            Key::P => 0x23,
+            Key::Return => 0x24,
+            Key::L => 0x25,
+            Key::J => 0x26,
+            Key::Apostrophe => 0x27,
+            Key::K => 0x28,
+            Key::Semicolon => 0x29,
+            Key::Backslash => 0x2A,
+            Key::Comma => 0x2B,
+            Key::Slash => 0x2C,
+            Key::N => 0x2D,
+            Key::M => 0x2E,
+            Key::Period => 0x2F,
+            Key::Tab => 0x30,
+            Key::Space => 0x31,
+            Key::Grave => 0x32,
+            Key::Backspace => 0x33,
+            Key::Escape => 0x35,
+            Key::RMeta => 0x36,
+            Key::Meta => 0x37,
+            Key::Shift => 0x38,
+            Key::CapsLock => 0x39,
+            Key::Alt => 0x3A,
+            Key::Control => 0x3B,
+            Key::RShift => 0x3C,
+            Key::RAlt => 0x3D,
+            Key::RControl => 0x3E,
+            Key::F17 => 0x40,
+            Key::NumpadDecimal => 0x41,
+            Key::NumpadMultiply => 0x43,
+            Key::NumpadPlus => 0x45,
+            Key::NumpadClear | Key::NumLock => 0x47,
+            Key::NumpadDivide => 0x4B,
+            Key::NumpadEnter => 0x4C,
+            Key::NumpadMinus => 0x4E,
+            Key::F18 => 0x4F,
+            Key::F19 => 0x50,
+            Key::NumpadEquals => 0x51,
+            Key::Numpad0 => 0x52,
+            Key::Numpad1 => 0x53,
+            Key::Numpad2 => 0x54,
+            Key::Numpad3 => 0x55,
+            Key::Numpad4 => 0x56,
+            Key::Numpad5 => 0x57,
+            Key::Numpad6 => 0x58,
+            Key::Numpad7 => 0x59,
+            Key::F20 => 0x5A,
+            Key::Numpad8 => 0x5B,
+            Key::Numpad9 => 0x5C,
+            Key::F5 => 0x60,
+            Key::F6 => 0x61,
+            Key::F7 => 0x62,
+            Key::F3 => 0x63,
+            Key::F8 => 0x64,
+            Key::F9 => 0x65,
+            Key::F11 => 0x67,
+            Key::F13 | Key::PrintScreen => 0x69,
+            Key::F16 => 0x6A,
+            Key::F14 | Key::ScrollLock => 0x6B,
+            Key::F10 => 0x6D,
+            Key::F12 => 0x6F,
+            Key::F15 | Key::Pause => 0x71,
+            Key::Insert => 0x72,
+            Key::Home => 0x73,
+            Key::PageUp => 0x74,
+            Key::Delete => 0x75,
+            Key::F4 => 0x76,
+            Key::End => 0x77,
+            Key::F2 => 0x78,
+            Key::PageDown => 0x79,
+            Key::F1 => 0x7A,
+            Key::LeftArrow => 0x7B,
+            Key::RightArrow => 0x7C,
+            Key::DownArrow => 0x7D,
+            Key::UpArrow => 0x7E,
+            Key::VolumeUp
+            | Key::VolumeDown
+            | Key::VolumeMute
+            | Key::MediaPlayPause
+            | Key::MediaNextTrack
+            | Key::MediaPrevTrack
+            | Key::MediaStop
+            | Key::BrightnessUp
+            | Key::BrightnessDown
+            | Key::F21
+            | Key::F22
+            | Key::F23
+            | Key::F24 => return None,
+        })
+    }
+}
+
+/// X11 keysyms (`keysymdef.h` and `XF86keysym.h`).
+pub mod x11 {
+    use crate::Key;
+
+    /// Keysym for a key. Letters, digits and punctuation use the unshifted
+    /// US keysym; the X server resolves it to whichever keycode produces it.
+    pub const fn keysym(key: Key) -> Option<u32> {
+        Some(match key {
+            Key::A => 0x61,
+            Key::B => 0x62,
+            Key::C => 0x63,
+            Key::D => 0x64,
+            Key::E => 0x65,
+            Key::F => 0x66,
+            Key::G => 0x67,
+            Key::H => 0x68,
+            Key::I => 0x69,
+            Key::J => 0x6A,
+            Key::K => 0x6B,
+            Key::L => 0x6C,
+            Key::M => 0x6D,
+            Key::N => 0x6E,
+            Key::O => 0x6F,
+            Key::P => 0x70,
+            Key::Q => 0x71,
+            Key::R => 0x72,
+            Key::S => 0x73,
+            Key::T => 0x74,
+            Key::U => 0x75,
+            Key::V => 0x76,
+            Key::W => 0x77,
+            Key::X => 0x78,
+            Key::Y => 0x79,
+            Key::Z => 0x7A,
+            Key::Digit0 => 0x30,
+            Key::Digit1 => 0x31,
+            Key::Digit2 => 0x32,
+            Key::Digit3 => 0x33,
+            Key::Digit4 => 0x34,
+            Key::Digit5 => 0x35,
+            Key::Digit6 => 0x36,
+            Key::Digit7 => 0x37,
+            Key::Digit8 => 0x38,
+            Key::Digit9 => 0x39,
+            Key::Minus => 0x2D,
+            Key::Equal => 0x3D,
+            Key::BracketLeft => 0x5B,
+            Key::BracketRight => 0x5D,
+            Key::Backslash => 0x5C,
+            Key::Semicolon => 0x3B,
+            Key::Apostrophe => 0x27,
+            Key::Grave => 0x60,
+            Key::Comma => 0x2C,
+            Key::Period => 0x2E,
+            Key::Slash => 0x2F,
+            Key::Control => 0xFFE3,
+            Key::RControl => 0xFFE4,
+            Key::Meta => 0xFFEB,
+            Key::RMeta => 0xFFEC,
+            Key::Shift => 0xFFE1,
+            Key::RShift => 0xFFE2,
+            Key::Alt => 0xFFE9,
+            Key::RAlt => 0xFFEA,
+            Key::CapsLock => 0xFFE5,
+            Key::Home => 0xFF50,
+            Key::LeftArrow => 0xFF51,
+            Key::UpArrow => 0xFF52,
+            Key::RightArrow => 0xFF53,
+            Key::DownArrow => 0xFF54,
+            Key::PageUp => 0xFF55,
+            Key::PageDown => 0xFF56,
+            Key::End => 0xFF57,
+            Key::Delete => 0xFFFF,
+            Key::Backspace => 0xFF08,
+            Key::Insert => 0xFF63,
+            Key::Escape => 0xFF1B,
+            Key::Tab => 0xFF09,
+            Key::Space => 0x20,
+            Key::Return => 0xFF0D,
+            Key::PrintScreen => 0xFF61,
+            Key::ScrollLock => 0xFF14,
+            Key::Pause => 0xFF13,
+            Key::NumLock => 0xFF7F,
+            Key::VolumeUp => 0x1008_FF13,
+            Key::VolumeDown => 0x1008_FF11,
+            Key::VolumeMute => 0x1008_FF12,
+            Key::MediaPlayPause => 0x1008_FF14,
+            Key::MediaStop => 0x1008_FF15,
+            Key::MediaPrevTrack => 0x1008_FF16,
+            Key::MediaNextTrack => 0x1008_FF17,
+            Key::BrightnessUp => 0x1008_FF02,
+            Key::BrightnessDown => 0x1008_FF03,
+            Key::F1 => 0xFFBE,
+            Key::F2 => 0xFFBF,
+            Key::F3 => 0xFFC0,
+            Key::F4 => 0xFFC1,
+            Key::F5 => 0xFFC2,
+            Key::F6 => 0xFFC3,
+            Key::F7 => 0xFFC4,
+            Key::F8 => 0xFFC5,
+            Key::F9 => 0xFFC6,
+            Key::F10 => 0xFFC7,
+            Key::F11 => 0xFFC8,
+            Key::F12 => 0xFFC9,
+            Key::F13 => 0xFFCA,
+            Key::F14 => 0xFFCB,
+            Key::F15 => 0xFFCC,
+            Key::F16 => 0xFFCD,
+            Key::F17 => 0xFFCE,
+            Key::F18 => 0xFFCF,
+            Key::F19 => 0xFFD0,
+            Key::F20 => 0xFFD1,
+            Key::F21 => 0xFFD2,
+            Key::F22 => 0xFFD3,
+            Key::F23 => 0xFFD4,
+            Key::F24 => 0xFFD5,
+            Key::Numpad0 => 0xFFB0,
+            Key::Numpad1 => 0xFFB1,
+            Key::Numpad2 => 0xFFB2,
+            Key::Numpad3 => 0xFFB3,
+            Key::Numpad4 => 0xFFB4,
+            Key::Numpad5 => 0xFFB5,
+            Key::Numpad6 => 0xFFB6,
+            Key::Numpad7 => 0xFFB7,
+            Key::Numpad8 => 0xFFB8,
+            Key::Numpad9 => 0xFFB9,
+            Key::NumpadDecimal => 0xFFAE,
+            Key::NumpadMultiply => 0xFFAA,
+            Key::NumpadPlus => 0xFFAB,
+            Key::NumpadMinus => 0xFFAD,
+            Key::NumpadDivide => 0xFFAF,
+            Key::NumpadEnter => 0xFF8D,
+            Key::NumpadEquals => 0xFFBD,
+            Key::NumpadClear => 0xFF0B,
+        })
+    }
+}
+
+/// Linux input event codes (`KEY_*` from `linux/input-event-codes.h`), as
+/// sent by the uinput backend.
+pub mod evdev {
+    use crate::Key;
+
+    /// Event code for a key, by physical position on a US keyboard. Every
+    /// key has one.
+    pub const fn key_code(key: Key) -> Option<u16> {
+        Some(match key {
+            Key::Escape => 1,
+            Key::Digit1 => 2,
+            Key::Digit2 => 3,
+            Key::Digit3 => 4,
+            Key::Digit4 => 5,
+            Key::Digit5 => 6,
+            Key::Digit6 => 7,
+            Key::Digit7 => 8,
+            Key::Digit8 => 9,
+            Key::Digit9 => 10,
+            Key::Digit0 => 11,
+            Key::Minus => 12,
+            Key::Equal => 13,
+            Key::Backspace => 14,
+            Key::Tab => 15,
+            Key::Q => 16,
+            Key::W => 17,
+            Key::E => 18,
+            Key::R => 19,
+            Key::T => 20,
+            Key::Y => 21,
+            Key::U => 22,
+            Key::I => 23,
+            Key::O => 24,
+            Key::P => 25,
+            Key::BracketLeft => 26,
+            Key::BracketRight => 27,
+            Key::Return => 28,
+            Key::Control => 29,
+            Key::A => 30,
+            Key::S => 31,
+            Key::D => 32,
+            Key::F => 33,
+            Key::G => 34,
+            Key::H => 35,
+            Key::J => 36,
+            Key::K => 37,
+            Key::L => 38,
+            Key::Semicolon => 39,
+            Key::Apostrophe => 40,
+            Key::Grave => 41,
+            Key::Shift => 42,
+            Key::Backslash => 43,
+            Key::Z => 44,
+            Key::X => 45,
+            Key::C => 46,
+            Key::V => 47,
+            Key::B => 48,
+            Key::N => 49,
+            Key::M => 50,
+            Key::Comma => 51,
+            Key::Period => 52,
+            Key::Slash => 53,
+            Key::RShift => 54,
+            Key::NumpadMultiply => 55,
+            Key::Alt => 56,
+            Key::Space => 57,
+            Key::CapsLock => 58,
+            Key::F1 => 59,
+            Key::F2 => 60,
+            Key::F3 => 61,
+            Key::F4 => 62,
+            Key::F5 => 63,
+            Key::F6 => 64,
+            Key::F7 => 65,
+            Key::F8 => 66,
+            Key::F9 => 67,
+            Key::F10 => 68,
+            Key::NumLock => 69,
+            Key::ScrollLock => 70,
+            Key::Numpad7 => 71,
+            Key::Numpad8 => 72,
+            Key::Numpad9 => 73,
+            Key::NumpadMinus => 74,
+            Key::Numpad4 => 75,
+            Key::Numpad5 => 76,
+            Key::Numpad6 => 77,
+            Key::NumpadPlus => 78,
+            Key::Numpad1 => 79,
+            Key::Numpad2 => 80,
+            Key::Numpad3 => 81,
+            Key::Numpad0 => 82,
+            Key::NumpadDecimal => 83,
+            Key::F11 => 87,
+            Key::F12 => 88,
+            Key::NumpadEnter => 96,
+            Key::RControl => 97,
+            Key::NumpadDivide => 98,
+            Key::PrintScreen => 99,
+            Key::RAlt => 100,
+            Key::Home => 102,
+            Key::UpArrow => 103,
+            Key::PageUp => 104,
+            Key::LeftArrow => 105,
+            Key::RightArrow => 106,
+            Key::End => 107,
+            Key::DownArrow => 108,
+            Key::PageDown => 109,
+            Key::Insert => 110,
+            Key::Delete => 111,
+            Key::VolumeMute => 113,
+            Key::VolumeDown => 114,
+            Key::VolumeUp => 115,
+            Key::NumpadEquals => 117,
+            Key::Pause => 119,
+            Key::Meta => 125,
+            Key::RMeta => 126,
+            Key::MediaNextTrack => 163,
+            Key::MediaPlayPause => 164,
+            Key::MediaPrevTrack => 165,
+            Key::MediaStop => 166,
+            Key::F13 => 183,
+            Key::F14 => 184,
+            Key::F15 => 185,
+            Key::F16 => 186,
+            Key::F17 => 187,
+            Key::F18 => 188,
+            Key::F19 => 189,
+            Key::F20 => 190,
+            Key::F21 => 191,
+            Key::F22 => 192,
+            Key::F23 => 193,
+            Key::F24 => 194,
+            Key::BrightnessDown => 224,
+            Key::BrightnessUp => 225,
+            Key::NumpadClear => 355,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::Key;
+
+    #[test]
+    fn mac_keycodes_are_unique_except_pc_aliases() {
+        let aliases = [
+            (Key::NumLock, Key::NumpadClear),
+            (Key::PrintScreen, Key::F13),
+            (Key::ScrollLock, Key::F14),
+            (Key::Pause, Key::F15),
+        ];
+        let mut seen: HashMap<u16, Key> = HashMap::new();
+        for &key in Key::ALL {
+            let Some(code) = mac::virtual_keycode(key) else {
+                continue;
+            };
+            if let Some(prev) = seen.insert(code, key) {
+                assert!(
+                    aliases.contains(&(key, prev)) || aliases.contains(&(prev, key)),
+                    "{prev:?} and {key:?} share keycode 0x{code:02x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn x11_covers_every_key_with_unique_keysyms() {
+        let mut seen: HashMap<u32, Key> = HashMap::new();
+        for &key in Key::ALL {
+            let sym = x11::keysym(key).unwrap_or_else(|| panic!("{key:?}"));
+            assert_eq!(seen.insert(sym, key), None, "duplicate keysym for {key:?}");
+        }
+    }
+
+    #[test]
+    fn evdev_covers_every_key_with_unique_codes() {
+        let mut seen: HashMap<u16, Key> = HashMap::new();
+        for &key in Key::ALL {
+            let code = evdev::key_code(key).unwrap_or_else(|| panic!("{key:?}"));
+            assert_eq!(seen.insert(code, key), None, "duplicate code for {key:?}");
+        }
+    }
+
+    #[test]
+    fn mac_lacks_media_and_high_function_keys() {
+        assert_eq!(mac::virtual_keycode(Key::VolumeUp), None);
+        assert_eq!(mac::virtual_keycode(Key::F21), None);
+        assert_eq!(mac::virtual_keycode(Key::K), Some(0x28));
+    }
+}
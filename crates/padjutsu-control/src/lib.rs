@@ -1,11 +1,21 @@
+mod backend;
+mod held;
 mod key;
+pub mod keycode;
 mod key_combo;
 mod modifiers;
 mod performer;
+mod recording;
+#[cfg(target_os = "linux")]
+mod uinput;
 mod worker;
 
+pub use backend::OutputBackend;
 pub use key_combo::{KeyCombo};
 pub use key::Key;
 pub use modifiers::{Modifier, Modifiers};
 pub use performer::Performer;
-pub use worker::{PerformerCmd, PerformerWorker};
+pub use recording::{OutputLog, OutputOp, Recorded, RecordingBackend};
+#[cfg(target_os = "linux")]
+pub use uinput::UinputBackend;
+pub use worker::{PerformerCmd, PerformerWorker, WorkerStats};
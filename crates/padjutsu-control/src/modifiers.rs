@@ -1,49 +1,101 @@
 use crate::key::Key;
 
+/// A modifier key. Unsided names (`ctrl`, `cmd`, …) refer to the left key.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Modifier {
     Ctrl,
-    Meta,
-    Shift,
+    RCtrl,
     Alt,
+    RAlt,
+    Shift,
+    RShift,
+    Meta,
+    RMeta,
 }
 
 impl Modifier {
     pub const CTRL: u8 = 1 << 0;
-    pub const META: u8 = 1 << 1;
-    pub const SHIFT: u8 = 1 << 2;
-    pub const ALT: u8 = 1 << 3;
+    pub const RCTRL: u8 = 1 << 1;
+    pub const ALT: u8 = 1 << 2;
+    pub const RALT: u8 = 1 << 3;
+    pub const SHIFT: u8 = 1 << 4;
+    pub const RSHIFT: u8 = 1 << 5;
+    pub const META: u8 = 1 << 6;
+    pub const RMETA: u8 = 1 << 7;
+
+    /// Every modifier in canonical order (macOS menu order: ⌃ ⌥ ⇧ ⌘).
+    /// Modifiers are pressed in this order and released in reverse.
+    pub const ALL: [Modifier; 8] = [
+        Modifier::Ctrl,
+        Modifier::RCtrl,
+        Modifier::Alt,
+        Modifier::RAlt,
+        Modifier::Shift,
+        Modifier::RShift,
+        Modifier::Meta,
+        Modifier::RMeta,
+    ];
 
     pub const fn to_bitmap(&self) -> u8 {
         match self {
             Modifier::Ctrl => Self::CTRL,
-            Modifier::Meta => Self::META,
-            Modifier::Shift => Self::SHIFT,
+            Modifier::RCtrl => Self::RCTRL,
             Modifier::Alt => Self::ALT,
+            Modifier::RAlt => Self::RALT,
+            Modifier::Shift => Self::SHIFT,
+            Modifier::RShift => Self::RSHIFT,
+            Modifier::Meta => Self::META,
+            Modifier::RMeta => Self::RMETA,
         }
     }
-}
 
-impl From<Key> for Modifier {
-    // type Err = String;
+    /// The physical key for this modifier.
+    pub const fn key(&self) -> Key {
+        match self {
+            Modifier::Ctrl => Key::Control,
+            Modifier::RCtrl => Key::RControl,
+            Modifier::Alt => Key::Alt,
+            Modifier::RAlt => Key::RAlt,
+            Modifier::Shift => Key::Shift,
+            Modifier::RShift => Key::RShift,
+            Modifier::Meta => Key::Meta,
+            Modifier::RMeta => Key::RMeta,
+        }
+    }
 
-    fn from(key: Key) -> Self {
-        match key {
+    /// The modifier for a key, if the key is one.
+    pub const fn from_key(key: Key) -> Option<Self> {
+        Some(match key {
             Key::Control => Modifier::Ctrl,
-            Key::Meta => Modifier::Meta,
-            Key::Shift => Modifier::Shift,
+            Key::RControl => Modifier::RCtrl,
             Key::Alt => Modifier::Alt,
-            _ => panic!("Invalid modifier key"),
-        }
+            Key::RAlt => Modifier::RAlt,
+            Key::Shift => Modifier::Shift,
+            Key::RShift => Modifier::RShift,
+            Key::Meta => Modifier::Meta,
+            Key::RMeta => Modifier::RMeta,
+            _ => return None,
+        })
     }
 }
+
+impl From<Key> for Modifier {
+    fn from(key: Key) -> Self {
+        Self::from_key(key).expect("Invalid modifier key")
+    }
+}
+
 impl From<u8> for Modifier {
     fn from(value: u8) -> Self {
         match value {
             Self::CTRL => Modifier::Ctrl,
-            Self::META => Modifier::Meta,
-            Self::SHIFT => Modifier::Shift,
+            Self::RCTRL => Modifier::RCtrl,
             Self::ALT => Modifier::Alt,
+            Self::RALT => Modifier::RAlt,
+            Self::SHIFT => Modifier::Shift,
+            Self::RSHIFT => Modifier::RShift,
+            Self::META => Modifier::Meta,
+            Self::RMETA => Modifier::RMeta,
             _ => panic!("Invalid modifier bitmap"),
         }
     }
@@ -89,6 +141,11 @@ impl Modifiers {
     pub const fn len(&self) -> usize {
         self.0.count_ones() as usize
     }
+
+    /// Iterate the set modifiers in canonical order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Modifier> + '_ {
+        Modifier::ALL.into_iter().filter(|m| self.contains(*m))
+    }
 }
 
 #[cfg(test)]
@@ -97,10 +154,17 @@ mod tests {
 
     #[test]
     fn test_modifier_to_bitmap_and_from() {
-        assert_eq!(Modifier::from(Modifier::Ctrl.to_bitmap()), Modifier::Ctrl);
-        assert_eq!(Modifier::from(Modifier::Meta.to_bitmap()), Modifier::Meta);
-        assert_eq!(Modifier::from(Modifier::Shift.to_bitmap()), Modifier::Shift);
-        assert_eq!(Modifier::from(Modifier::Alt.to_bitmap()), Modifier::Alt);
+        for m in Modifier::ALL {
+            assert_eq!(Modifier::from(m.to_bitmap()), m);
+        }
+    }
+
+    #[test]
+    fn test_modifier_key_round_trip() {
+        for m in Modifier::ALL {
+            assert_eq!(Modifier::from_key(m.key()), Some(m));
+        }
+        assert_eq!(Modifier::from_key(Key::A), None);
     }
 
     #[test]
@@ -116,6 +180,7 @@ mod tests {
         mods.add(Modifier::Ctrl);
         assert!(mods.contains(Modifier::Ctrl));
         assert!(!mods.contains(Modifier::Alt));
+        assert!(!mods.contains(Modifier::RCtrl));
     }
 
     #[test]
@@ -148,4 +213,15 @@ mod tests {
         assert!(!mods.contains(Modifier::Meta));
         assert_eq!(mods.len(), 2);
     }
+
+    #[test]
+    fn test_modifiers_iter_is_canonical() {
+        let mods = Modifiers::from_values(&[
+            Modifier::RMeta,
+            Modifier::Shift,
+            Modifier::Ctrl,
+        ]);
+        let order: Vec<_> = mods.iter().collect();
+        assert_eq!(order, [Modifier::Ctrl, Modifier::Shift, Modifier::RMeta]);
+    }
 }
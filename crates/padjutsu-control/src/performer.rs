@@ -1,9 +1,15 @@
+use std::thread;
+use std::time::Duration;
+
 use enigo::{
-    Axis, Button, Coordinate, Direction, Enigo, InputResult, Mouse, NewConError,
-    Settings,
+    Axis, Button, Coordinate, Direction, Enigo, InputResult, Keyboard, Mouse,
+    NewConError, Settings,
 };
 
-use crate::KeyCombo;
+use crate::backend::OutputBackend;
+use crate::held::HeldKeys;
+use crate::key_combo::to_enigo_key;
+use crate::{Key, KeyCombo};
 
 /// Wrap a CG/Cocoa-using closure in a macOS autorelease pool so any
 /// internally-allocated CFData/NSObject autoreleased values are freed
@@ -235,6 +241,7 @@ mod smooth_scroll {
 
 pub struct Performer {
     enigo: Enigo,
+    held: HeldKeys,
 }
 
 // SAFETY: This is safe because we're only accessing Enigo through a Mutex,
@@ -248,23 +255,66 @@ impl Performer {
     pub fn new() -> Result<Self, NewConError> {
         let settings = Settings::default();
         let enigo = Enigo::new(&settings)?;
-        Ok(Self { enigo })
+        Ok(Self {
+            enigo,
+            held: HeldKeys::default(),
+        })
     }
 
     /// Perform key combo.
-    /// This will press and release the keys in the key combo.
+    /// This will press and release the keys in the key combo. Keys already
+    /// held by another combo stay down.
     pub fn perform(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
-        with_pool(|| key_combo.perform(&mut self.enigo))
+        self.press(key_combo)?;
+        self.release(key_combo)
     }
 
     /// Press keys.
+    /// Keys are reference-counted: a key already held by another combo is
+    /// not pressed again.
     pub fn press(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
-        with_pool(|| key_combo.press(&mut self.enigo))
+        log::info!("[performer] press: {key_combo}");
+        let Self { enigo, held } = self;
+        with_pool(|| {
+            held.press(key_combo, |key, dir| enigo.key(to_enigo_key(key)?, dir))
+        })
     }
 
     /// Release keys.
+    /// Only keys no other combo still holds are released.
     pub fn release(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
-        with_pool(|| key_combo.release(&mut self.enigo))
+        log::info!("[performer] release: {key_combo}");
+        let Self { enigo, held } = self;
+        with_pool(|| {
+            held.release(key_combo, |key, dir| enigo.key(to_enigo_key(key)?, dir))
+        })
+    }
+
+    /// Whether any pressed combo still holds the key down.
+    pub fn is_held(&self, key: Key) -> bool {
+        self.held.is_held(key)
+    }
+
+    /// Release every key held by any combo.
+    pub fn release_all(&mut self) -> InputResult<()> {
+        let Self { enigo, held } = self;
+        with_pool(|| held.release_all(|key, dir| enigo.key(to_enigo_key(key)?, dir)))
+    }
+
+    /// Type arbitrary Unicode text, independent of the keyboard layout.
+    /// With a non-zero `delay_ms`, characters are entered one at a time
+    /// with a pause after each, for apps that drop fast input.
+    pub fn type_text(&mut self, text: &str, delay_ms: u64) -> InputResult<()> {
+        if delay_ms == 0 {
+            return with_pool(|| self.enigo.text(text));
+        }
+        let delay = Duration::from_millis(delay_ms);
+        let mut buf = [0u8; 4];
+        for ch in text.chars() {
+            with_pool(|| self.enigo.text(ch.encode_utf8(&mut buf)))?;
+            thread::sleep(delay);
+        }
+        Ok(())
     }
 
     /// Move mouse.
@@ -334,3 +384,63 @@ impl Performer {
         with_pool(|| raw_modifier::post_flags_changed(keycode, false))
     }
 }
+
+impl OutputBackend for Performer {
+    fn press(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        Performer::press(self, key_combo)
+    }
+
+    fn release(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        Performer::release(self, key_combo)
+    }
+
+    fn perform(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        Performer::perform(self, key_combo)
+    }
+
+    fn release_all(&mut self) -> InputResult<()> {
+        Performer::release_all(self)
+    }
+
+    fn type_text(&mut self, text: &str, delay_ms: u64) -> InputResult<()> {
+        Performer::type_text(self, text, delay_ms)
+    }
+
+    fn mouse_move(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        Performer::mouse_move(self, dx, dy)
+    }
+
+    fn scroll_x(&mut self, value: f64) -> InputResult<()> {
+        Performer::scroll_x(self, value)
+    }
+
+    fn scroll_y(&mut self, value: f64) -> InputResult<()> {
+        Performer::scroll_y(self, value)
+    }
+
+    fn mouse_click(&mut self, button: Button) -> InputResult<()> {
+        Performer::mouse_click(self, button)
+    }
+
+    fn mouse_double_click(&mut self, button: Button) -> InputResult<()> {
+        Performer::mouse_double_click(self, button)
+    }
+
+    fn mouse_press(&mut self, button: Button) -> InputResult<()> {
+        Performer::mouse_press(self, button)
+    }
+
+    fn mouse_release(&mut self, button: Button) -> InputResult<()> {
+        Performer::mouse_release(self, button)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_press(&mut self, keycode: u16) -> Result<(), String> {
+        Performer::raw_modifier_press(self, keycode)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_release(&mut self, keycode: u16) -> Result<(), String> {
+        Performer::raw_modifier_release(self, keycode)
+    }
+}
@@ -0,0 +1,252 @@
+//! Recording output backend.
+//!
+//! Captures the OS-level operations a real backend would inject — individual
+//! key downs/ups after reference counting, mouse moves, scrolls and button
+//! events — with a timestamp relative to when the backend was created.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use enigo::{Axis, Button, Direction, InputResult};
+
+use crate::backend::OutputBackend;
+use crate::held::HeldKeys;
+use crate::{Key, KeyCombo};
+
+/// A single OS-level output operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputOp {
+    Key(Key, Direction),
+    Text(String),
+    MouseMove {
+        dx: i32,
+        dy: i32,
+    },
+    Scroll(Axis, f64),
+    Button(Button, Direction),
+    #[cfg(target_os = "macos")]
+    RawModifier {
+        keycode: u16,
+        pressed: bool,
+    },
+}
+
+/// An operation and when it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recorded {
+    /// Time since the backend was created.
+    pub at: Duration,
+    pub op: OutputOp,
+}
+
+/// Shared handle to the log of a [`RecordingBackend`]. Stays readable after
+/// the backend is moved into a worker.
+#[derive(Debug, Clone, Default)]
+pub struct OutputLog(Arc<Mutex<Vec<Recorded>>>);
+
+impl OutputLog {
+    fn lock(&self) -> MutexGuard<'_, Vec<Recorded>> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Every recorded entry, in order.
+    pub fn entries(&self) -> Vec<Recorded> {
+        self.lock().clone()
+    }
+
+    /// Every recorded operation without timestamps, in order.
+    pub fn ops(&self) -> Vec<OutputOp> {
+        self.lock().iter().map(|r| r.op.clone()).collect()
+    }
+
+    /// Remove and return every recorded entry.
+    pub fn take(&self) -> Vec<Recorded> {
+        std::mem::take(&mut *self.lock())
+    }
+}
+
+/// Output backend that records instead of injecting input.
+#[derive(Debug)]
+pub struct RecordingBackend {
+    log: OutputLog,
+    held: HeldKeys,
+    started: Instant,
+}
+
+impl RecordingBackend {
+    /// Create a backend and a handle to its log.
+    pub fn new() -> (Self, OutputLog) {
+        let log = OutputLog::default();
+        let backend = Self {
+            log: log.clone(),
+            held: HeldKeys::default(),
+            started: Instant::now(),
+        };
+        (backend, log)
+    }
+
+    fn record(&self, op: OutputOp) {
+        let at = self.started.elapsed();
+        self.log.lock().push(Recorded { at, op });
+    }
+}
+
+impl OutputBackend for RecordingBackend {
+    fn press(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        let Self { log, held, started } = self;
+        held.press(key_combo, |key, dir| {
+            let at = started.elapsed();
+            log.lock().push(Recorded {
+                at,
+                op: OutputOp::Key(key, dir),
+            });
+            Ok(())
+        })
+    }
+
+    fn release(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        let Self { log, held, started } = self;
+        held.release(key_combo, |key, dir| {
+            let at = started.elapsed();
+            log.lock().push(Recorded {
+                at,
+                op: OutputOp::Key(key, dir),
+            });
+            Ok(())
+        })
+    }
+
+    fn release_all(&mut self) -> InputResult<()> {
+        let Self { log, held, started } = self;
+        held.release_all(|key, dir| {
+            let at = started.elapsed();
+            log.lock().push(Recorded {
+                at,
+                op: OutputOp::Key(key, dir),
+            });
+            Ok(())
+        })
+    }
+
+    /// Records one `Text` per character when `delay_ms` is non-zero, like
+    /// the real backend, but does not sleep.
+    fn type_text(&mut self, text: &str, delay_ms: u64) -> InputResult<()> {
+        if delay_ms == 0 {
+            self.record(OutputOp::Text(text.to_owned()));
+        } else {
+            for ch in text.chars() {
+                self.record(OutputOp::Text(ch.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn mouse_move(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        self.record(OutputOp::MouseMove { dx, dy });
+        Ok(())
+    }
+
+    fn scroll_x(&mut self, value: f64) -> InputResult<()> {
+        self.record(OutputOp::Scroll(Axis::Horizontal, value));
+        Ok(())
+    }
+
+    fn scroll_y(&mut self, value: f64) -> InputResult<()> {
+        self.record(OutputOp::Scroll(Axis::Vertical, value));
+        Ok(())
+    }
+
+    fn mouse_click(&mut self, button: Button) -> InputResult<()> {
+        self.record(OutputOp::Button(button, Direction::Click));
+        Ok(())
+    }
+
+    fn mouse_press(&mut self, button: Button) -> InputResult<()> {
+        self.record(OutputOp::Button(button, Direction::Press));
+        Ok(())
+    }
+
+    fn mouse_release(&mut self, button: Button) -> InputResult<()> {
+        self.record(OutputOp::Button(button, Direction::Release));
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_press(&mut self, keycode: u16) -> Result<(), String> {
+        self.record(OutputOp::RawModifier {
+            keycode,
+            pressed: true,
+        });
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn raw_modifier_release(&mut self, keycode: u16) -> Result<(), String> {
+        self.record(OutputOp::RawModifier {
+            keycode,
+            pressed: false,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PerformerCmd, PerformerWorker};
+
+    fn combo(s: &str) -> KeyCombo {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn records_key_level_output() {
+        let (mut backend, log) = RecordingBackend::new();
+        backend.press(&combo("cmd")).unwrap();
+        backend.perform(&combo("cmd+c")).unwrap();
+        backend.release(&combo("cmd")).unwrap();
+        assert_eq!(
+            log.ops(),
+            [
+                OutputOp::Key(Key::Meta, Direction::Press),
+                OutputOp::Key(Key::C, Direction::Press),
+                OutputOp::Key(Key::C, Direction::Release),
+                OutputOp::Key(Key::Meta, Direction::Release),
+            ]
+        );
+        let entries = log.entries();
+        assert!(entries.windows(2).all(|w| w[0].at <= w[1].at));
+    }
+
+    #[test]
+    fn worker_drives_recording_backend() {
+        let (backend, log) = RecordingBackend::new();
+        let worker = PerformerWorker::spawn(backend);
+        for cmd in [
+            PerformerCmd::MouseMove { dx: 3, dy: 1 },
+            PerformerCmd::MousePress(Button::Left),
+            PerformerCmd::KeyTap(combo("ctrl+a")),
+            PerformerCmd::ScrollY(2.0),
+            PerformerCmd::MouseRelease(Button::Left),
+        ] {
+            worker.send(cmd).unwrap();
+        }
+        // Dropping the worker drains the queue and joins the thread.
+        drop(worker);
+        assert_eq!(
+            log.ops(),
+            [
+                OutputOp::MouseMove { dx: 3, dy: 1 },
+                OutputOp::Button(Button::Left, Direction::Press),
+                OutputOp::Key(Key::Control, Direction::Press),
+                OutputOp::Key(Key::A, Direction::Press),
+                OutputOp::Key(Key::A, Direction::Release),
+                OutputOp::Key(Key::Control, Direction::Release),
+                OutputOp::Scroll(Axis::Vertical, 2.0),
+                OutputOp::Button(Button::Left, Direction::Release),
+            ]
+        );
+    }
+}
@@ -0,0 +1,579 @@
+//! Linux uinput output backend.
+//!
+//! Creates a virtual keyboard and a virtual pointer through `/dev/uinput`
+//! and writes evdev events to them. The kernel treats the devices like
+//! real hardware, so the output works the same under X11 and every Wayland
+//! compositor, with no display-server protocol in between.
+//!
+//! - keys are sent by evdev code, so left and right modifiers stay distinct;
+//! - the keyboard does not advertise `EV_REP`, so the kernel never
+//!   synthesises repeats for a key we hold — repeat is the daemon's job;
+//! - scrolling emits `REL_WHEEL_HI_RES`/`REL_HWHEEL_HI_RES` (120 units per
+//!   detent) alongside the legacy `REL_WHEEL`/`REL_HWHEEL` detents, so
+//!   fractional values scroll smoothly in clients that support it.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+use enigo::{Button, InputError, InputResult};
+
+use crate::backend::OutputBackend;
+use crate::held::HeldKeys;
+use crate::keycode::evdev;
+use crate::{Key, KeyCombo};
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const SYN_REPORT: u16 = 0;
+
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+const REL_WHEEL_HI_RES: u16 = 0x0b;
+const REL_HWHEEL_HI_RES: u16 = 0x0c;
+
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const BTN_SIDE: u16 = 0x113;
+const BTN_EXTRA: u16 = 0x114;
+
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// Hi-res wheel units per detent.
+const HI_RES_PER_DETENT: i32 = 120;
+
+const fn ioc(dir: u64, nr: u64, size: usize) -> u64 {
+    (dir << 30) | ((size as u64) << 16) | ((b'U' as u64) << 8) | nr
+}
+
+const UI_DEV_CREATE: u64 = ioc(0, 1, 0);
+const UI_DEV_DESTROY: u64 = ioc(0, 2, 0);
+const UI_DEV_SETUP: u64 = ioc(1, 3, size_of::<libc::uinput_setup>());
+const UI_SET_EVBIT: u64 = ioc(1, 100, size_of::<libc::c_int>());
+const UI_SET_KEYBIT: u64 = ioc(1, 101, size_of::<libc::c_int>());
+const UI_SET_RELBIT: u64 = ioc(1, 102, size_of::<libc::c_int>());
+const SYSNAME_LEN: usize = 64;
+const UI_GET_SYSNAME: u64 = ioc(2, 44, SYSNAME_LEN);
+
+/// One uinput device. Destroyed on drop.
+#[derive(Debug)]
+struct Device {
+    file: File,
+}
+
+impl Device {
+    fn create(
+        name: &str,
+        product: u16,
+        keys: impl IntoIterator<Item = u16>,
+        rels: &[u16],
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")?;
+        let dev = Self { file };
+
+        dev.ioctl_int(UI_SET_EVBIT, EV_KEY)?;
+        for key in keys {
+            dev.ioctl_int(UI_SET_KEYBIT, key)?;
+        }
+        if !rels.is_empty() {
+            dev.ioctl_int(UI_SET_EVBIT, EV_REL)?;
+            for &rel in rels {
+                dev.ioctl_int(UI_SET_RELBIT, rel)?;
+            }
+        }
+
+        // SAFETY: uinput_setup is plain old data; all-zero is valid.
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id = libc::input_id {
+            bustype: BUS_VIRTUAL,
+            vendor: 0,
+            product,
+            version: 1,
+        };
+        for (dst, src) in setup.name.iter_mut().zip(name.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        // SAFETY: UI_DEV_SETUP reads one uinput_setup from the pointer.
+        dev.check(unsafe {
+            libc::ioctl(dev.fd(), UI_DEV_SETUP as _, &raw const setup)
+        })?;
+        // SAFETY: UI_DEV_CREATE takes no argument.
+        dev.check(unsafe { libc::ioctl(dev.fd(), UI_DEV_CREATE as _) })?;
+        Ok(dev)
+    }
+
+    fn fd(&self) -> libc::c_int {
+        self.file.as_raw_fd()
+    }
+
+    fn check(&self, ret: libc::c_int) -> io::Result<()> {
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ioctl_int(&self, request: u64, value: u16) -> io::Result<()> {
+        // SAFETY: the UI_SET_*BIT requests take an int by value.
+        self.check(unsafe {
+            libc::ioctl(self.fd(), request as _, libc::c_int::from(value))
+        })
+    }
+
+    /// The evdev node (`/dev/input/eventN`) the kernel created for us.
+    fn event_node(&self) -> Option<PathBuf> {
+        let mut buf = [0u8; SYSNAME_LEN];
+        // SAFETY: UI_GET_SYSNAME writes at most SYSNAME_LEN bytes.
+        let ret =
+            unsafe { libc::ioctl(self.fd(), UI_GET_SYSNAME as _, buf.as_mut_ptr()) };
+        self.check(ret).ok()?;
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        let sysname = std::str::from_utf8(&buf[..len]).ok()?;
+        let dir = PathBuf::from("/sys/devices/virtual/input").join(sysname);
+        std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .find(|n| n.starts_with("event"))
+            .map(|n| PathBuf::from("/dev/input").join(n))
+    }
+
+    /// Write the events followed by a `SYN_REPORT`.
+    fn emit(&mut self, events: &[(u16, u16, i32)]) -> InputResult<()> {
+        let mut buf =
+            Vec::with_capacity((events.len() + 1) * size_of::<libc::input_event>());
+        for &(type_, code, value) in events
+            .iter()
+            .chain(std::iter::once(&(EV_SYN, SYN_REPORT, 0)))
+        {
+            // SAFETY: input_event is plain old data; the kernel fills in
+            // the timestamp.
+            let mut ev: libc::input_event = unsafe { std::mem::zeroed() };
+            ev.type_ = type_;
+            ev.code = code;
+            ev.value = value;
+            // SAFETY: reading the bytes of a fully initialised POD struct.
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    (&raw const ev).cast::<u8>(),
+                    size_of::<libc::input_event>(),
+                )
+            };
+            buf.extend_from_slice(bytes);
+        }
+        self.file
+            .write_all(&buf)
+            .map_err(|_| InputError::Simulate("failed to write uinput event"))
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        // SAFETY: UI_DEV_DESTROY takes no argument.
+        unsafe {
+            libc::ioctl(self.fd(), UI_DEV_DESTROY as _);
+        }
+    }
+}
+
+/// Accumulates fractional wheel motion into hi-res units and whole detents.
+#[derive(Debug, Default)]
+struct Wheel {
+    /// Sub-unit remainder not yet sent as hi-res.
+    remainder: f64,
+    /// Hi-res units sent since the last whole detent.
+    partial: i32,
+}
+
+impl Wheel {
+    /// Feed a motion in detents; returns `(hi_res_units, detents)` to emit.
+    fn push(&mut self, detents: f64) -> (i32, i32) {
+        let exact = detents * f64::from(HI_RES_PER_DETENT) + self.remainder;
+        let hi_res = exact.trunc();
+        self.remainder = exact - hi_res;
+        #[allow(clippy::cast_possible_truncation)]
+        let hi_res = hi_res as i32;
+        self.partial += hi_res;
+        let whole = self.partial / HI_RES_PER_DETENT;
+        self.partial -= whole * HI_RES_PER_DETENT;
+        (hi_res, whole)
+    }
+}
+
+/// Output backend writing to virtual uinput devices.
+#[derive(Debug)]
+pub struct UinputBackend {
+    keyboard: Device,
+    pointer: Device,
+    held: HeldKeys,
+    wheel_y: Wheel,
+    wheel_x: Wheel,
+}
+
+impl UinputBackend {
+    /// Create the virtual keyboard and pointer. Needs write access to
+    /// `/dev/uinput` (usually the `input` group or a udev rule).
+    pub fn new() -> io::Result<Self> {
+        let keys = Key::ALL.iter().filter_map(|&k| evdev::key_code(k));
+        let keyboard = Device::create("padjutsu virtual keyboard", 1, keys, &[])?;
+        let buttons = [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE, BTN_SIDE, BTN_EXTRA];
+        let pointer = Device::create(
+            "padjutsu virtual pointer",
+            2,
+            buttons,
+            &[
+                REL_X,
+                REL_Y,
+                REL_WHEEL,
+                REL_HWHEEL,
+                REL_WHEEL_HI_RES,
+                REL_HWHEEL_HI_RES,
+            ],
+        )?;
+        Ok(Self {
+            keyboard,
+            pointer,
+            held: HeldKeys::default(),
+            wheel_y: Wheel::default(),
+            wheel_x: Wheel::default(),
+        })
+    }
+
+    /// Whether `type_text` can type `text`: ASCII only, as on a US layout.
+    pub fn can_type(text: &str) -> bool {
+        text.chars().all(|ch| {
+            ascii_key(ch)
+                .and_then(|(k, _)| evdev::key_code(k))
+                .is_some()
+        })
+    }
+
+    /// The evdev node of the virtual keyboard, e.g. to read events back.
+    pub fn keyboard_node(&self) -> Option<PathBuf> {
+        self.keyboard.event_node()
+    }
+
+    /// The evdev node of the virtual pointer.
+    pub fn pointer_node(&self) -> Option<PathBuf> {
+        self.pointer.event_node()
+    }
+
+    fn send_key(keyboard: &mut Device, key: Key, down: bool) -> InputResult<()> {
+        let code = evdev::key_code(key)
+            .ok_or(InputError::InvalidInput("key has no evdev code"))?;
+        keyboard.emit(&[(EV_KEY, code, i32::from(down))])
+    }
+
+    fn button(&mut self, button: Button, down: bool) -> InputResult<()> {
+        let code = match button {
+            Button::Left => BTN_LEFT,
+            Button::Right => BTN_RIGHT,
+            Button::Middle => BTN_MIDDLE,
+            Button::Back => BTN_SIDE,
+            Button::Forward => BTN_EXTRA,
+            // Wheel "buttons" scroll one detent on press.
+            Button::ScrollUp | Button::ScrollDown => {
+                let dir = if button == Button::ScrollUp {
+                    -1.0
+                } else {
+                    1.0
+                };
+                return if down {
+                    self.scroll_y_detents(dir)
+                } else {
+                    Ok(())
+                };
+            }
+            Button::ScrollLeft | Button::ScrollRight => {
+                let dir = if button == Button::ScrollLeft {
+                    -1.0
+                } else {
+                    1.0
+                };
+                return if down {
+                    self.scroll_x_detents(dir)
+                } else {
+                    Ok(())
+                };
+            }
+        };
+        self.pointer.emit(&[(EV_KEY, code, i32::from(down))])
+    }
+
+    /// Positive values scroll down, like enigo.
+    fn scroll_y_detents(&mut self, value: f64) -> InputResult<()> {
+        // REL_WHEEL is positive upwards.
+        let (hi_res, whole) = self.wheel_y.push(-value);
+        self.emit_wheel(REL_WHEEL_HI_RES, hi_res, REL_WHEEL, whole)
+    }
+
+    /// Positive values scroll right.
+    fn scroll_x_detents(&mut self, value: f64) -> InputResult<()> {
+        let (hi_res, whole) = self.wheel_x.push(value);
+        self.emit_wheel(REL_HWHEEL_HI_RES, hi_res, REL_HWHEEL, whole)
+    }
+
+    fn emit_wheel(
+        &mut self,
+        hi_res_code: u16,
+        hi_res: i32,
+        code: u16,
+        whole: i32,
+    ) -> InputResult<()> {
+        let mut events = Vec::with_capacity(2);
+        if hi_res != 0 {
+            events.push((EV_REL, hi_res_code, hi_res));
+        }
+        if whole != 0 {
+            events.push((EV_REL, code, whole));
+        }
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.pointer.emit(&events)
+    }
+}
+
+/// The key and shift state that types an ASCII character on a US layout.
+fn ascii_key(ch: char) -> Option<(Key, bool)> {
+    let lower = ch.to_ascii_lowercase();
+    let shifted = ch.is_ascii_uppercase();
+    if lower.is_ascii_lowercase() {
+        let name = lower.to_string();
+        return crate::key::parse_key(&name).map(|k| (k, shifted));
+    }
+    Some(match ch {
+        '0'..='9' => {
+            let name = ch.to_string();
+            return crate::key::parse_key(&name).map(|k| (k, false));
+        }
+        ' ' => (Key::Space, false),
+        '\n' => (Key::Return, false),
+        '\t' => (Key::Tab, false),
+        '-' => (Key::Minus, false),
+        '_' => (Key::Minus, true),
+        '=' => (Key::Equal, false),
+        '+' => (Key::Equal, true),
+        '[' => (Key::BracketLeft, false),
+        '{' => (Key::BracketLeft, true),
+        ']' => (Key::BracketRight, false),
+        '}' => (Key::BracketRight, true),
+        '\\' => (Key::Backslash, false),
+        '|' => (Key::Backslash, true),
+        ';' => (Key::Semicolon, false),
+        ':' => (Key::Semicolon, true),
+        '\'' => (Key::Apostrophe, false),
+        '"' => (Key::Apostrophe, true),
+        '`' => (Key::Grave, false),
+        '~' => (Key::Grave, true),
+        ',' => (Key::Comma, false),
+        '<' => (Key::Comma, true),
+        '.' => (Key::Period, false),
+        '>' => (Key::Period, true),
+        '/' => (Key::Slash, false),
+        '?' => (Key::Slash, true),
+        '!' => (Key::Digit1, true),
+        '@' => (Key::Digit2, true),
+        '#' => (Key::Digit3, true),
+        '$' => (Key::Digit4, true),
+        '%' => (Key::Digit5, true),
+        '^' => (Key::Digit6, true),
+        '&' => (Key::Digit7, true),
+        '*' => (Key::Digit8, true),
+        '(' => (Key::Digit9, true),
+        ')' => (Key::Digit0, true),
+        _ => return None,
+    })
+}
+
+impl OutputBackend for UinputBackend {
+    fn press(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        log::info!("[uinput] press: {key_combo}");
+        let Self { keyboard, held, .. } = self;
+        held.press(key_combo, |key, _| Self::send_key(keyboard, key, true))
+    }
+
+    fn release(&mut self, key_combo: &KeyCombo) -> InputResult<()> {
+        log::info!("[uinput] release: {key_combo}");
+        let Self { keyboard, held, .. } = self;
+        held.release(key_combo, |key, _| Self::send_key(keyboard, key, false))
+    }
+
+    fn release_all(&mut self) -> InputResult<()> {
+        let Self { keyboard, held, .. } = self;
+        held.release_all(|key, _| Self::send_key(keyboard, key, false))
+    }
+
+    /// uinput sends keycodes, not characters, so only ASCII is supported,
+    /// typed as on a US layout. Nothing is typed unless the whole text can
+    /// be. Keys held by a combo are left alone.
+    fn type_text(&mut self, text: &str, delay_ms: u64) -> InputResult<()> {
+        let codes = text
+            .chars()
+            .map(|ch| {
+                let (key, shift) = ascii_key(ch).ok_or(InputError::InvalidInput(
+                    "uinput can only type ASCII text",
+                ))?;
+                let code = evdev::key_code(key)
+                    .ok_or(InputError::InvalidInput("key has no evdev code"))?;
+                Ok((code, shift))
+            })
+            .collect::<InputResult<Vec<_>>>()?;
+        let shift_code =
+            evdev::key_code(Key::Shift).expect("shift has an evdev code");
+        let delay = std::time::Duration::from_millis(delay_ms);
+        for (code, shift) in codes {
+            let need_shift = shift && !self.held.is_held(Key::Shift);
+            if need_shift {
+                self.keyboard.emit(&[(EV_KEY, shift_code, 1)])?;
+            }
+            let typed = self
+                .keyboard
+                .emit(&[(EV_KEY, code, 1)])
+                .and_then(|()| self.keyboard.emit(&[(EV_KEY, code, 0)]));
+            // Shift goes up even if the key failed, or it sticks.
+            if need_shift {
+                let released = self.keyboard.emit(&[(EV_KEY, shift_code, 0)]);
+                typed.and(released)?;
+            } else {
+                typed?;
+            }
+            if delay_ms > 0 {
+                std::thread::sleep(delay);
+            }
+        }
+        Ok(())
+    }
+
+    fn mouse_move(&mut self, dx: i32, dy: i32) -> InputResult<()> {
+        let mut events = Vec::with_capacity(2);
+        if dx != 0 {
+            events.push((EV_REL, REL_X, dx));
+        }
+        if dy != 0 {
+            events.push((EV_REL, REL_Y, dy));
+        }
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.pointer.emit(&events)
+    }
+
+    fn scroll_x(&mut self, value: f64) -> InputResult<()> {
+        self.scroll_x_detents(value)
+    }
+
+    fn scroll_y(&mut self, value: f64) -> InputResult<()> {
+        self.scroll_y_detents(value)
+    }
+
+    fn mouse_click(&mut self, button: Button) -> InputResult<()> {
+        self.button(button, true)?;
+        self.button(button, false)
+    }
+
+    fn mouse_press(&mut self, button: Button) -> InputResult<()> {
+        self.button(button, true)
+    }
+
+    fn mouse_release(&mut self, button: Button) -> InputResult<()> {
+        self.button(button, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn wheel_emits_detent_every_120_units() {
+        let mut wheel = Wheel::default();
+        assert_eq!(wheel.push(0.5), (60, 0));
+        assert_eq!(wheel.push(0.5), (60, 1));
+        assert_eq!(wheel.push(-1.0), (-120, -1));
+        assert_eq!(wheel.push(2.25), (270, 2));
+    }
+
+    #[test]
+    fn wheel_carries_sub_unit_remainder() {
+        let mut wheel = Wheel::default();
+        let total: i32 = (0..120).map(|_| wheel.push(0.005).0).sum();
+        // 120 * 0.005 detents = 0.6 detents = 72 units, modulo rounding.
+        assert!((71..=72).contains(&total), "{total}");
+    }
+
+    #[test]
+    fn ascii_covers_printable_range() {
+        for ch in ' '..='~' {
+            assert!(ascii_key(ch).is_some(), "{ch:?}");
+        }
+        assert_eq!(ascii_key('A'), Some((Key::A, true)));
+        assert_eq!(ascii_key('?'), Some((Key::Slash, true)));
+        assert_eq!(ascii_key('é'), None);
+        assert!(UinputBackend::can_type("Hello, world!\n"));
+        assert!(!UinputBackend::can_type("café"));
+    }
+
+    fn read_events(node: &mut File, n: usize) -> Vec<(u16, u16, i32)> {
+        let size = size_of::<libc::input_event>();
+        let mut out = Vec::new();
+        while out.len() < n {
+            let mut buf = vec![0u8; size];
+            node.read_exact(&mut buf).unwrap();
+            // SAFETY: buf holds exactly one input_event written by the kernel.
+            let ev: libc::input_event =
+                unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) };
+            if ev.type_ != EV_SYN {
+                out.push((ev.type_, ev.code, ev.value));
+            }
+        }
+        out
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn events_read_back_from_evdev_node() {
+        let mut backend = UinputBackend::new().unwrap();
+        // Give udev a moment to create the device nodes.
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let mut kbd = File::open(backend.keyboard_node().unwrap()).unwrap();
+        let mut ptr = File::open(backend.pointer_node().unwrap()).unwrap();
+
+        backend.perform(&"rctrl+a".parse().unwrap()).unwrap();
+        assert_eq!(
+            read_events(&mut kbd, 4),
+            [
+                (EV_KEY, 97, 1),
+                (EV_KEY, 30, 1),
+                (EV_KEY, 30, 0),
+                (EV_KEY, 97, 0),
+            ]
+        );
+
+        backend.scroll_y(0.5).unwrap();
+        backend.scroll_y(0.5).unwrap();
+        backend.mouse_move(3, -2).unwrap();
+        assert_eq!(
+            read_events(&mut ptr, 5),
+            [
+                (EV_REL, REL_WHEEL_HI_RES, -60),
+                (EV_REL, REL_WHEEL_HI_RES, -60),
+                (EV_REL, REL_WHEEL, -1),
+                (EV_REL, REL_X, 3),
+                (EV_REL, REL_Y, -2),
+            ]
+        );
+    }
+}
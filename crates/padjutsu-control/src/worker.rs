@@ -1,31 +1,51 @@
 //! Async, coalescing performer worker.
 //!
-//! Runs `Performer` operations on a dedicated real-time thread, decoupling the
+//! Runs output-backend operations on a dedicated real-time thread, decoupling the
 //! event loop from `CGEventPost` latency. Under heavy graphics load,
 //! `CGEventPost` blocks waiting on WindowServer; without this worker, that
 //! blocks the entire event loop and inputs from the gamepad pile up.
 //!
-//! The worker also coalesces consecutive `MouseMove` and `Scroll` commands —
-//! mouse position is a state, not deltas, so collapsing pending moves into a
-//! single post avoids a long catch-up tail when the queue gets behind.
+//! Commands travel on two lanes:
+//!
+//! - the **lossless** lane carries discrete key/button/text commands. It is
+//!   unbounded, so a `KeyRelease` or `MouseRelease` is never dropped and keys
+//!   cannot get stuck because the worker fell behind;
+//! - the **motion** lane carries `MouseMove` and `Scroll` commands. It is
+//!   bounded and drops on overflow, and the worker coalesces consecutive
+//!   pending moves into a single post — mouse position is a state, not
+//!   deltas, so this avoids a long catch-up tail when the queue gets behind.
+//!
+//! Each discrete command records how many motion commands were sent before
+//! it, and the worker flushes that much motion first, so "move then click"
+//! still clicks at the destination.
 
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::thread;
 
-use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use crossbeam_channel::{bounded, select, unbounded, Receiver, Sender, TrySendError};
 use enigo::Button;
 
-use crate::performer::Performer;
+use crate::backend::OutputBackend;
 use crate::KeyCombo;
 
+/// Capacity of the motion lane.
+const MOTION_CAPACITY: usize = 1024;
+
 /// Commands sent to the worker thread.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum PerformerCmd {
     KeyTap(KeyCombo),
     KeyPress(KeyCombo),
     KeyRelease(KeyCombo),
-    MouseMove { dx: i32, dy: i32 },
+    /// Type Unicode text, pausing `delay_ms` after each character.
+    Type {
+        text: String,
+        delay_ms: u64,
+    },
+    MouseMove {
+        dx: i32,
+        dy: i32,
+    },
     ScrollX(f64),
     ScrollY(f64),
     MouseClick(Button),
@@ -36,52 +56,144 @@ pub enum PerformerCmd {
     RawModifierPress(u16),
     #[cfg(target_os = "macos")]
     RawModifierRelease(u16),
+    /// Release every key and button the backend still holds down.
+    ReleaseAll,
+}
+
+impl PerformerCmd {
+    /// Whether the command goes on the coalescing motion lane.
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            PerformerCmd::MouseMove { .. }
+                | PerformerCmd::ScrollX(_)
+                | PerformerCmd::ScrollY(_)
+        )
+    }
+}
+
+/// Snapshot of the worker's queues.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Discrete commands waiting on the lossless lane.
+    pub lossless_depth: usize,
+    /// Highest lossless depth seen since the worker started.
+    pub lossless_peak: usize,
+    /// Motion commands waiting on the motion lane.
+    pub motion_depth: usize,
+    /// Motion commands dropped because the motion lane was full.
+    pub motion_dropped: u64,
+    /// Commands dropped because the worker thread is gone.
+    pub disconnected_dropped: u64,
+}
+
+/// A discrete command tagged with the number of motion commands sent
+/// before it.
+#[derive(Debug)]
+enum Lossless {
+    Cmd { motion_seq: u64, cmd: PerformerCmd },
+    Stop,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    motion_sent: AtomicU64,
+    motion_dropped: AtomicU64,
+    disconnected_dropped: AtomicU64,
+    lossless_peak: AtomicUsize,
 }
 
 /// Handle to the worker thread. Drop to terminate the worker.
 pub struct PerformerWorker {
-    tx: Sender<PerformerCmd>,
-    stop: Arc<AtomicBool>,
+    lossless_tx: Sender<Lossless>,
+    motion_tx: Sender<PerformerCmd>,
+    counters: Counters,
     join: Option<thread::JoinHandle<()>>,
 }
 
 impl PerformerWorker {
-    /// Spawn a worker thread that owns the given `Performer`.
-    /// The worker thread sets itself to macOS realtime priority on macOS.
-    pub fn spawn(mut performer: Performer) -> Self {
-        let (tx, rx) = bounded::<PerformerCmd>(1024);
-        let stop = Arc::new(AtomicBool::new(false));
-        let stop_w = stop.clone();
+    /// Spawn a worker thread that owns the given backend, usually a
+    /// `Performer`. The worker thread sets itself to macOS realtime priority
+    /// on macOS.
+    pub fn spawn<B: OutputBackend + 'static>(mut backend: B) -> Self {
+        let (lossless_tx, lossless_rx) = unbounded::<Lossless>();
+        let (motion_tx, motion_rx) = bounded::<PerformerCmd>(MOTION_CAPACITY);
         let join = thread::Builder::new()
             .name("performer-worker".into())
             .stack_size(512 * 1024)
             .spawn(move || {
                 #[cfg(target_os = "macos")]
                 set_realtime_priority_2ms();
-                run(&mut performer, rx, stop_w);
+                run(&mut backend, &lossless_rx, &motion_rx);
             })
             .expect("failed to spawn performer worker");
         Self {
-            tx,
-            stop,
+            lossless_tx,
+            motion_tx,
+            counters: Counters::default(),
             join: Some(join),
         }
     }
 
-    /// Send a command to the worker. Non-blocking. If the queue is full
-    /// (worker backed up), the command is dropped and `Err` is returned.
-    /// For movement-style commands the caller should not retry — the next
-    /// tick will produce a fresh state.
-    pub fn try_send(&self, cmd: PerformerCmd) -> Result<(), TrySendError<PerformerCmd>> {
-        self.tx.try_send(cmd)
+    /// Send a command to the worker. Never blocks.
+    ///
+    /// Discrete commands are always queued. Motion commands are dropped
+    /// with `TrySendError::Full` when the motion lane is backed up; the
+    /// caller should not retry — the next tick will produce a fresh state.
+    /// `TrySendError::Disconnected` means the worker thread is gone.
+    pub fn send(&self, cmd: PerformerCmd) -> Result<(), TrySendError<PerformerCmd>> {
+        let c = &self.counters;
+        if cmd.is_motion() {
+            let res = self.motion_tx.try_send(cmd);
+            match &res {
+                Ok(()) => {
+                    c.motion_sent.fetch_add(1, Ordering::Release);
+                }
+                Err(TrySendError::Full(_)) => {
+                    c.motion_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    c.disconnected_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            return res;
+        }
+        let motion_seq = c.motion_sent.load(Ordering::Acquire);
+        match self.lossless_tx.send(Lossless::Cmd { motion_seq, cmd }) {
+            Ok(()) => {
+                c.lossless_peak
+                    .fetch_max(self.lossless_tx.len(), Ordering::Relaxed);
+                Ok(())
+            }
+            Err(err) => {
+                c.disconnected_dropped.fetch_add(1, Ordering::Relaxed);
+                match err.into_inner() {
+                    Lossless::Cmd { cmd, .. } => {
+                        Err(TrySendError::Disconnected(cmd))
+                    }
+                    Lossless::Stop => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Current queue depths and drop counters.
+    pub fn stats(&self) -> WorkerStats {
+        let c = &self.counters;
+        WorkerStats {
+            lossless_depth: self.lossless_tx.len(),
+            lossless_peak: c.lossless_peak.load(Ordering::Relaxed),
+            motion_depth: self.motion_tx.len(),
+            motion_dropped: c.motion_dropped.load(Ordering::Relaxed),
+            disconnected_dropped: c.disconnected_dropped.load(Ordering::Relaxed),
+        }
     }
 }
 
 impl Drop for PerformerWorker {
     fn drop(&mut self) {
-        self.stop.store(true, Ordering::Release);
-        // Send a no-op-ish wake to unblock recv if needed.
-        let _ = self.tx.try_send(PerformerCmd::MouseMove { dx: 0, dy: 0 });
+        // Everything sent before the stop marker still runs.
+        let _ = self.lossless_tx.send(Lossless::Stop);
         if let Some(j) = self.join.take() {
             let _ = j.join();
         }
@@ -89,32 +201,93 @@ impl Drop for PerformerWorker {
 }
 
 fn run(
-    performer: &mut Performer,
-    rx: Receiver<PerformerCmd>,
-    stop: Arc<AtomicBool>,
+    backend: &mut dyn OutputBackend,
+    lossless_rx: &Receiver<Lossless>,
+    motion_rx: &Receiver<PerformerCmd>,
 ) {
-    while !stop.load(Ordering::Acquire) {
-        // Block for at least one command.
-        let first = match rx.recv() {
-            Ok(c) => c,
-            Err(_) => return,
+    let mut scheduler = Scheduler::default();
+    let mut discrete: Vec<(u64, PerformerCmd)> = Vec::with_capacity(16);
+    let mut motion: Vec<PerformerCmd> = Vec::with_capacity(16);
+    let mut stop = false;
+    while !stop {
+        // Block for at least one command on either lane.
+        let first = select! {
+            recv(lossless_rx) -> msg => match msg {
+                Ok(m) => Some(m),
+                Err(_) => return,
+            },
+            recv(motion_rx) -> msg => match msg {
+                Ok(c) => {
+                    motion.push(c);
+                    None
+                }
+                Err(_) => return,
+            },
         };
 
-        // Drain everything currently queued so we can coalesce homogeneous
-        // streams (mouse moves + scrolls) and execute the rest in order.
-        let mut batch: Vec<PerformerCmd> = Vec::with_capacity(16);
-        batch.push(first);
-        while let Ok(c) = rx.try_recv() {
-            batch.push(c);
+        // Drain both lanes so we can coalesce motion and execute the rest
+        // in order. Discrete commands are drained first: anything they
+        // wait for on the motion lane was sent before them and is already
+        // queued.
+        let lossless = first.into_iter().chain(lossless_rx.try_iter());
+        for msg in lossless {
+            match msg {
+                Lossless::Cmd { motion_seq, cmd } => {
+                    discrete.push((motion_seq, cmd));
+                }
+                Lossless::Stop => stop = true,
+            }
+        }
+        motion.extend(motion_rx.try_iter());
+
+        for cmd in scheduler.schedule(discrete.drain(..), motion.drain(..)) {
+            execute_one(backend, &cmd);
         }
+    }
+}
 
-        execute_batch(performer, &batch);
+/// Merges the two lanes back into execution order.
+#[derive(Debug, Default)]
+struct Scheduler {
+    /// Motion commands received so far.
+    motion_seen: u64,
+}
+
+impl Scheduler {
+    /// Order a drained batch: each discrete command runs after the motion
+    /// sent before it, trailing motion runs last, and consecutive motion is
+    /// coalesced.
+    fn schedule(
+        &mut self,
+        discrete: impl IntoIterator<Item = (u64, PerformerCmd)>,
+        motion: impl IntoIterator<Item = PerformerCmd>,
+    ) -> Vec<PerformerCmd> {
+        let mut motion = motion.into_iter().peekable();
+        let mut out = Vec::new();
+        let mut pending: Vec<PerformerCmd> = Vec::new();
+        for (motion_seq, cmd) in discrete {
+            while self.motion_seen < motion_seq {
+                let Some(m) = motion.next() else { break };
+                pending.push(m);
+                self.motion_seen += 1;
+            }
+            out.extend(coalesce(&pending));
+            pending.clear();
+            out.push(cmd);
+        }
+        for m in motion {
+            pending.push(m);
+            self.motion_seen += 1;
+        }
+        out.extend(coalesce(&pending));
+        out
     }
 }
 
-/// Execute a batch of commands, coalescing consecutive movement/scroll
-/// commands into single posts to avoid catch-up tails under load.
-fn execute_batch(performer: &mut Performer, batch: &[PerformerCmd]) {
+/// Coalesce consecutive movement/scroll commands into single commands to
+/// avoid catch-up tails under load. Commands that sum to zero are dropped.
+fn coalesce(batch: &[PerformerCmd]) -> Vec<PerformerCmd> {
+    let mut out = Vec::new();
     let mut i = 0;
     while i < batch.len() {
         match &batch[i] {
@@ -132,7 +305,10 @@ fn execute_batch(performer: &mut Performer, batch: &[PerformerCmd]) {
                     }
                 }
                 if sum_dx != 0 || sum_dy != 0 {
-                    let _ = performer.mouse_move(sum_dx, sum_dy);
+                    out.push(PerformerCmd::MouseMove {
+                        dx: sum_dx,
+                        dy: sum_dy,
+                    });
                 }
             }
             PerformerCmd::ScrollX(_) => {
@@ -146,7 +322,7 @@ fn execute_batch(performer: &mut Performer, batch: &[PerformerCmd]) {
                     }
                 }
                 if sum != 0.0 {
-                    let _ = performer.scroll_x(sum);
+                    out.push(PerformerCmd::ScrollX(sum));
                 }
             }
             PerformerCmd::ScrollY(_) => {
@@ -160,19 +336,20 @@ fn execute_batch(performer: &mut Performer, batch: &[PerformerCmd]) {
                     }
                 }
                 if sum != 0.0 {
-                    let _ = performer.scroll_y(sum);
+                    out.push(PerformerCmd::ScrollY(sum));
                 }
             }
-            // Non-coalescing commands: execute one at a time.
+            // Non-coalescing commands pass through one at a time.
             other => {
-                execute_one(performer, other);
+                out.push(other.clone());
                 i += 1;
             }
         }
     }
+    out
 }
 
-fn execute_one(performer: &mut Performer, cmd: &PerformerCmd) {
+fn execute_one(performer: &mut dyn OutputBackend, cmd: &PerformerCmd) {
     match cmd {
         PerformerCmd::KeyTap(k) => {
             let _ = performer.perform(k);
@@ -183,6 +360,9 @@ fn execute_one(performer: &mut Performer, cmd: &PerformerCmd) {
         PerformerCmd::KeyRelease(k) => {
             let _ = performer.release(k);
         }
+        PerformerCmd::Type { text, delay_ms } => {
+            let _ = performer.type_text(text, *delay_ms);
+        }
         PerformerCmd::MouseMove { dx, dy } => {
             let _ = performer.mouse_move(*dx, *dy);
         }
@@ -212,6 +392,9 @@ fn execute_one(performer: &mut Performer, cmd: &PerformerCmd) {
         PerformerCmd::RawModifierRelease(kc) => {
             let _ = performer.raw_modifier_release(*kc);
         }
+        PerformerCmd::ReleaseAll => {
+            let _ = performer.release_all();
+        }
     }
 }
 
@@ -292,41 +475,98 @@ fn set_realtime_priority_2ms() {
 mod tests {
     use super::*;
 
+    fn tap(s: &str) -> PerformerCmd {
+        PerformerCmd::KeyTap(s.parse().unwrap())
+    }
+
     #[test]
-    fn coalesce_mouse_moves_sums_deltas() {
-        let cmds = vec![
+    fn coalesce_merges_only_adjacent_motion() {
+        let out = coalesce(&[
             PerformerCmd::MouseMove { dx: 5, dy: 0 },
             PerformerCmd::MouseMove { dx: 3, dy: 2 },
-            PerformerCmd::MouseMove { dx: -1, dy: -1 },
-        ];
-        // We can't directly test execute_batch without a Performer, but we can
-        // verify the coalescing logic by extracting it.
-        let mut sum_dx: i32 = 0;
-        let mut sum_dy: i32 = 0;
-        for c in &cmds {
-            if let PerformerCmd::MouseMove { dx, dy } = c {
-                sum_dx += dx;
-                sum_dy += dy;
-            }
-        }
-        assert_eq!(sum_dx, 7);
-        assert_eq!(sum_dy, 1);
+            PerformerCmd::ScrollY(1.0),
+            PerformerCmd::ScrollY(-1.0),
+            PerformerCmd::MouseMove { dx: 1, dy: 1 },
+        ]);
+        assert_eq!(
+            out,
+            [
+                PerformerCmd::MouseMove { dx: 8, dy: 2 },
+                PerformerCmd::MouseMove { dx: 1, dy: 1 },
+            ]
+        );
     }
 
     #[test]
-    fn coalesce_breaks_at_non_movement() {
-        let cmds = vec![
-            PerformerCmd::MouseMove { dx: 5, dy: 0 },
-            PerformerCmd::MouseClick(Button::Left),
-            PerformerCmd::MouseMove { dx: 3, dy: 0 },
-        ];
-        // Manually trace: first batch should coalesce only first MouseMove,
-        // then click, then second MouseMove. We assert there are 3 distinct
-        // operations conceptually. Real verification is in integration.
-        let movement_segments: Vec<_> = cmds
-            .windows(1)
-            .map(|w| matches!(w[0], PerformerCmd::MouseMove { .. }))
-            .collect();
-        assert_eq!(movement_segments, vec![true, false, true]);
+    fn schedule_flushes_prior_motion_before_discrete() {
+        let mut s = Scheduler::default();
+        // move, click, move, release — sent in this order.
+        let out = s.schedule(
+            [
+                (1, PerformerCmd::MouseClick(Button::Left)),
+                (2, PerformerCmd::MouseRelease(Button::Left)),
+            ],
+            [
+                PerformerCmd::MouseMove { dx: 1, dy: 0 },
+                PerformerCmd::MouseMove { dx: 2, dy: 0 },
+            ],
+        );
+        assert_eq!(
+            out,
+            [
+                PerformerCmd::MouseMove { dx: 1, dy: 0 },
+                PerformerCmd::MouseClick(Button::Left),
+                PerformerCmd::MouseMove { dx: 2, dy: 0 },
+                PerformerCmd::MouseRelease(Button::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn schedule_tracks_motion_across_batches() {
+        let mut s = Scheduler::default();
+        let first = s.schedule([], [PerformerCmd::MouseMove { dx: 1, dy: 0 }]);
+        assert_eq!(first, [PerformerCmd::MouseMove { dx: 1, dy: 0 }]);
+        // The key was sent after that move; nothing more to flush.
+        let second =
+            s.schedule([(1, tap("a"))], [PerformerCmd::MouseMove { dx: 4, dy: 0 }]);
+        assert_eq!(second, [tap("a"), PerformerCmd::MouseMove { dx: 4, dy: 0 }]);
+    }
+
+    #[test]
+    fn discrete_commands_keep_their_order() {
+        let mut s = Scheduler::default();
+        let cmds = [tap("a"), tap("b"), tap("c")];
+        let out = s.schedule(cmds.iter().cloned().map(|c| (0, c)), []);
+        assert_eq!(out, cmds);
+    }
+
+    #[test]
+    fn motion_commands_use_motion_lane() {
+        assert!(PerformerCmd::ScrollX(1.0).is_motion());
+        assert!(PerformerCmd::MouseMove { dx: 0, dy: 0 }.is_motion());
+        assert!(!PerformerCmd::MouseRelease(Button::Left).is_motion());
+        assert!(!tap("a").is_motion());
+    }
+
+    #[test]
+    fn release_all_lifts_keys_still_held() {
+        use crate::recording::{OutputOp, RecordingBackend};
+        use enigo::Direction::{Press, Release};
+
+        let (backend, log) = RecordingBackend::new();
+        let worker = PerformerWorker::spawn(backend);
+        worker
+            .send(PerformerCmd::KeyPress("cmd".parse().unwrap()))
+            .unwrap();
+        worker.send(PerformerCmd::ReleaseAll).unwrap();
+        drop(worker);
+        assert_eq!(
+            log.ops(),
+            [
+                OutputOp::Key(crate::Key::Meta, Press),
+                OutputOp::Key(crate::Key::Meta, Release),
+            ]
+        );
     }
 }
@@ -1,16 +1,3 @@
-//! IOKit/IOHIDManager standalone probe.
-//!
-//! Opens HID gamepad devices directly via IOHIDManager and measures the
-//! delta between sequential value-change callbacks per HID element.
-//!
-//! Compare this to the SDL2 baseline measured via PADJUTSU_METRICS=1.
-//! If IOKit min dt is ~4-8ms vs SDL2's 22ms, the IOKit path is worth the migration.
-//!
-//! Usage: `cargo run --example iokit_probe -p padjutsu-gamepad --release`
-//! Move sticks for 30 seconds, then Ctrl-C.
-
-#![cfg(target_os = "macos")]
-
 #[link(name = "IOKit", kind = "framework")]
 #[link(name = "CoreFoundation", kind = "framework")]
 extern "C" {}
@@ -312,7 +299,7 @@ fn report() {
     println!("==========================================\n");
 }
 
-fn main() {
+pub(crate) fn main() {
     println!("[iokit-probe] starting. Move sticks for ~30s, then Ctrl-C.");
 
     unsafe {
@@ -0,0 +1,23 @@
+//! IOKit/IOHIDManager standalone probe.
+//!
+//! Opens HID gamepad devices directly via IOHIDManager and measures the
+//! delta between sequential value-change callbacks per HID element.
+//!
+//! Compare this to the SDL2 baseline measured via PADJUTSU_METRICS=1.
+//! If IOKit min dt is ~4-8ms vs SDL2's 22ms, the IOKit path is worth the migration.
+//!
+//! Usage: `cargo run --example iokit_probe -p padjutsu-gamepad --release`
+//! Move sticks for 30 seconds, then Ctrl-C.
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "macos")]
+fn main() {
+    macos::main();
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+    eprintln!("[iokit-probe] IOHIDManager is only available on macOS");
+}
@@ -0,0 +1,939 @@
+//! Linux evdev backend.
+//!
+//! Reads gamepads straight from `/dev/input/event*` instead of going through
+//! SDL. The runtime thread sleeps in `poll(2)` on the device nodes and an
+//! inotify watch on `/dev/input`, so input is delivered as soon as the kernel
+//! has it and hotplug needs no rescanning.
+//!
+//! Buttons follow the kernel gamepad spec (`Documentation/input/gamepad.rst`),
+//! which names face buttons by position: `BTN_SOUTH` is `A`, `BTN_EAST` is
+//! `B`, `BTN_WEST` is `X` and `BTN_NORTH` is `Y`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use ahash::{AHashMap, AHashSet};
+use crossbeam_channel::Receiver;
+
+use crate::command::Command;
+use crate::events::ControllerEvent;
+use crate::manager::Inner;
+use crate::runtime::{broadcast, metrics_tick, set_realtime_priority};
+use crate::types::{Axis, Button, ControllerId, ControllerInfo};
+
+const INPUT_DIR: &str = "/dev/input";
+
+/// How long `poll` sleeps without input before checking for commands.
+const COMMAND_POLL_MS: libc::c_int = 20;
+
+const EV_SYN: u16 = 0x00;
+const SYN_REPORT: u16 = 0x00;
+const SYN_DROPPED: u16 = 0x03;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const EV_FF: u16 = 0x15;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+const ABS_CNT: usize = 0x40;
+
+const BTN_SOUTH: u16 = 0x130;
+const BTN_EAST: u16 = 0x131;
+const BTN_NORTH: u16 = 0x133;
+const BTN_WEST: u16 = 0x134;
+const BTN_TL: u16 = 0x136;
+const BTN_TR: u16 = 0x137;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const BTN_SELECT: u16 = 0x13a;
+const BTN_START: u16 = 0x13b;
+const BTN_MODE: u16 = 0x13c;
+const BTN_THUMBL: u16 = 0x13d;
+const BTN_THUMBR: u16 = 0x13e;
+const BTN_DPAD_UP: u16 = 0x220;
+const BTN_DPAD_DOWN: u16 = 0x221;
+const BTN_DPAD_LEFT: u16 = 0x222;
+const BTN_DPAD_RIGHT: u16 = 0x223;
+const KEY_CNT: usize = 0x300;
+
+/// Key codes `map_button` knows, read back when resyncing.
+const BUTTON_CODES: [u16; 17] = [
+    BTN_SOUTH,
+    BTN_EAST,
+    BTN_NORTH,
+    BTN_WEST,
+    BTN_TL,
+    BTN_TR,
+    BTN_TL2,
+    BTN_TR2,
+    BTN_SELECT,
+    BTN_START,
+    BTN_MODE,
+    BTN_THUMBL,
+    BTN_THUMBR,
+    BTN_DPAD_UP,
+    BTN_DPAD_DOWN,
+    BTN_DPAD_LEFT,
+    BTN_DPAD_RIGHT,
+];
+/// Absolute axes `translate` knows, read back when resyncing.
+const ABS_CODES: [u16; 8] = [
+    ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ, ABS_HAT0X, ABS_HAT0Y,
+];
+
+const FF_RUMBLE: u16 = 0x50;
+const FF_CNT: usize = 0x80;
+
+/// Trigger travel past which the trigger also counts as a pressed button,
+/// matching the SDL backend (20000 of 32767).
+const TRIGGER_THRESHOLD: f32 = 20000.0 / 32767.0;
+
+const fn ioc(dir: u64, nr: u64, size: usize) -> u64 {
+    (dir << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+}
+
+const IOC_WRITE: u64 = 1;
+const IOC_READ: u64 = 2;
+
+const EVIOCGID: u64 = ioc(IOC_READ, 0x02, size_of::<libc::input_id>());
+const fn eviocgname(len: usize) -> u64 {
+    ioc(IOC_READ, 0x06, len)
+}
+const fn eviocgkey(len: usize) -> u64 {
+    ioc(IOC_READ, 0x18, len)
+}
+const fn eviocgbit(ev: u16, len: usize) -> u64 {
+    ioc(IOC_READ, 0x20 + ev as u64, len)
+}
+const fn eviocgabs(abs: u16) -> u64 {
+    ioc(
+        IOC_READ,
+        0x40 + abs as u64,
+        size_of::<libc::input_absinfo>(),
+    )
+}
+const EVIOCSFF: u64 = ioc(IOC_WRITE, 0x80, size_of::<libc::ff_effect>());
+
+fn map_button(code: u16) -> Option<Button> {
+    Some(match code {
+        BTN_SOUTH => Button::A,
+        BTN_EAST => Button::B,
+        BTN_WEST => Button::X,
+        BTN_NORTH => Button::Y,
+        BTN_TL => Button::LeftShoulder,
+        BTN_TR => Button::RightShoulder,
+        BTN_TL2 => Button::LeftTrigger,
+        BTN_TR2 => Button::RightTrigger,
+        BTN_SELECT => Button::Back,
+        BTN_START => Button::Start,
+        BTN_MODE => Button::Guide,
+        BTN_THUMBL => Button::LeftStick,
+        BTN_THUMBR => Button::RightStick,
+        BTN_DPAD_UP => Button::DPadUp,
+        BTN_DPAD_DOWN => Button::DPadDown,
+        BTN_DPAD_LEFT => Button::DPadLeft,
+        BTN_DPAD_RIGHT => Button::DPadRight,
+        _ => return None,
+    })
+}
+
+fn map_axis(code: u16) -> Option<Axis> {
+    Some(match code {
+        ABS_X => Axis::LeftX,
+        ABS_Y => Axis::LeftY,
+        ABS_RX => Axis::RightX,
+        ABS_RY => Axis::RightY,
+        ABS_Z => Axis::LeftTrigger,
+        ABS_RZ => Axis::RightTrigger,
+        _ => return None,
+    })
+}
+
+/// Reported range of an absolute axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AbsRange {
+    min: i32,
+    max: i32,
+}
+
+impl AbsRange {
+    /// Map a raw value onto [-1.0, 1.0] for sticks or [0.0, 1.0] for
+    /// triggers, like the SDL backend does.
+    fn normalize(self, value: i32, axis: Axis) -> f32 {
+        let span = (self.max - self.min).max(1) as f32;
+        let t = ((value - self.min) as f32 / span).clamp(0.0, 1.0);
+        match axis {
+            Axis::LeftTrigger | Axis::RightTrigger => t,
+            _ => t * 2.0 - 1.0,
+        }
+    }
+}
+
+/// Per-device input state: turns raw evdev events into controller events.
+#[derive(Debug, Default)]
+struct PadState {
+    ranges: AHashMap<u16, AbsRange>,
+    buttons: AHashSet<Button>,
+    /// Analog triggers also drive the trigger buttons, so the digital
+    /// `BTN_TL2`/`BTN_TR2` codes are ignored when the axis exists.
+    analog_triggers: (bool, bool),
+    hat: (i32, i32),
+    /// Last raw value of each stick and trigger axis.
+    axes: AHashMap<u16, i32>,
+    /// Button codes the device has; only these are read back on resync.
+    key_codes: Vec<u16>,
+    /// Set by `SYN_DROPPED`: the kernel queue overflowed, so events up to the
+    /// next `SYN_REPORT` are incomplete and skipped.
+    dropped: bool,
+}
+
+impl PadState {
+    fn with_ranges(ranges: AHashMap<u16, AbsRange>) -> Self {
+        let analog_triggers =
+            (ranges.contains_key(&ABS_Z), ranges.contains_key(&ABS_RZ));
+        Self {
+            ranges,
+            analog_triggers,
+            ..Self::default()
+        }
+    }
+
+    fn set_button(
+        &mut self,
+        id: ControllerId,
+        button: Button,
+        pressed: bool,
+        out: &mut Vec<ControllerEvent>,
+    ) {
+        if pressed {
+            if self.buttons.insert(button) {
+                out.push(ControllerEvent::ButtonPressed { id, button });
+            }
+        } else if self.buttons.remove(&button) {
+            out.push(ControllerEvent::ButtonReleased { id, button });
+        }
+    }
+
+    /// Handle one raw event. Returns true when a `SYN_REPORT` ends a dropped
+    /// stretch and the caller has to `resync` from the device.
+    fn feed(
+        &mut self,
+        id: ControllerId,
+        type_: u16,
+        code: u16,
+        value: i32,
+        out: &mut Vec<ControllerEvent>,
+    ) -> bool {
+        match (type_, code) {
+            (EV_SYN, SYN_DROPPED) => {
+                self.dropped = true;
+                false
+            }
+            (EV_SYN, SYN_REPORT) => std::mem::take(&mut self.dropped),
+            (EV_SYN, _) => false,
+            _ if self.dropped => false,
+            _ => {
+                self.translate(id, type_, code, value, out);
+                false
+            }
+        }
+    }
+
+    /// Bring the state in line with the device's: `keys` is the
+    /// `EVIOCGKEY` bitmask and `abs` the current axis values. Only what
+    /// changed is emitted.
+    fn resync(
+        &mut self,
+        id: ControllerId,
+        keys: &[u8],
+        abs: &[(u16, i32)],
+        out: &mut Vec<ControllerEvent>,
+    ) {
+        let keys: Vec<(u16, i32)> = self
+            .key_codes
+            .iter()
+            .map(|&code| (code, i32::from(test_bit(keys, code as usize))))
+            .collect();
+        for (code, value) in keys {
+            self.translate(id, EV_KEY, code, value, out);
+        }
+        for &(code, value) in abs {
+            // The hat already only reports edges.
+            let hat = code == ABS_HAT0X || code == ABS_HAT0Y;
+            if hat || self.axes.get(&code) != Some(&value) {
+                self.translate(id, EV_ABS, code, value, out);
+            }
+        }
+    }
+
+    fn translate(
+        &mut self,
+        id: ControllerId,
+        type_: u16,
+        code: u16,
+        value: i32,
+        out: &mut Vec<ControllerEvent>,
+    ) {
+        match type_ {
+            EV_KEY => {
+                // 2 is autorepeat; buttons only care about edges.
+                if value == 2 {
+                    return;
+                }
+                let digital_trigger = (code == BTN_TL2 && self.analog_triggers.0)
+                    || (code == BTN_TR2 && self.analog_triggers.1);
+                if digital_trigger {
+                    return;
+                }
+                if let Some(button) = map_button(code) {
+                    self.set_button(id, button, value != 0, out);
+                }
+            }
+            EV_ABS if code == ABS_HAT0X || code == ABS_HAT0Y => {
+                let (neg, pos, prev) = if code == ABS_HAT0X {
+                    let prev = self.hat.0;
+                    self.hat.0 = value.signum();
+                    (Button::DPadLeft, Button::DPadRight, prev)
+                } else {
+                    let prev = self.hat.1;
+                    self.hat.1 = value.signum();
+                    (Button::DPadUp, Button::DPadDown, prev)
+                };
+                let next = value.signum();
+                if prev != next {
+                    self.set_button(id, neg, next < 0, out);
+                    self.set_button(id, pos, next > 0, out);
+                }
+            }
+            EV_ABS => {
+                let Some(axis) = map_axis(code) else { return };
+                let Some(range) = self.ranges.get(&code).copied() else {
+                    return;
+                };
+                self.axes.insert(code, value);
+                let norm = range.normalize(value, axis);
+                out.push(ControllerEvent::AxisMotion {
+                    id,
+                    axis,
+                    value: norm,
+                });
+                let trigger = match axis {
+                    Axis::LeftTrigger => Button::LeftTrigger,
+                    Axis::RightTrigger => Button::RightTrigger,
+                    _ => return,
+                };
+                self.set_button(id, trigger, norm > TRIGGER_THRESHOLD, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn test_bit(bits: &[u8], bit: usize) -> bool {
+    bits.get(bit / 8).is_some_and(|b| b & (1 << (bit % 8)) != 0)
+}
+
+fn ioctl_read<T>(fd: RawFd, request: u64, out: *mut T) -> io::Result<()> {
+    // SAFETY: callers pass a request whose encoded size matches `T`.
+    let ret = unsafe { libc::ioctl(fd, request as _, out) };
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// An open gamepad device.
+struct Pad {
+    id: ControllerId,
+    path: PathBuf,
+    file: File,
+    state: PadState,
+    rumble_effect: Option<i16>,
+}
+
+impl Pad {
+    /// Open a device node and return it with its info if it is a gamepad.
+    fn open(path: &Path, id: ControllerId) -> Option<(Self, ControllerInfo)> {
+        // Read-write is needed for force feedback; fall back to read-only.
+        let flags = libc::O_NONBLOCK | libc::O_CLOEXEC;
+        let (file, writable) = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(flags)
+            .open(path)
+        {
+            Ok(f) => (f, true),
+            Err(_) => (
+                OpenOptions::new()
+                    .read(true)
+                    .custom_flags(flags)
+                    .open(path)
+                    .ok()?,
+                false,
+            ),
+        };
+        let fd = file.as_raw_fd();
+
+        let mut keys = [0u8; KEY_CNT / 8];
+        ioctl_read(fd, eviocgbit(EV_KEY, keys.len()), keys.as_mut_ptr()).ok()?;
+        if !test_bit(&keys, BTN_SOUTH as usize) {
+            return None;
+        }
+
+        // SAFETY: input_id is plain old data; all-zero is valid.
+        let mut input_id: libc::input_id = unsafe { std::mem::zeroed() };
+        let _ = ioctl_read(fd, EVIOCGID, &raw mut input_id);
+
+        let mut name = [0u8; 256];
+        let _ = ioctl_read(fd, eviocgname(name.len()), name.as_mut_ptr());
+        let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = String::from_utf8_lossy(&name[..len]).into_owned();
+
+        let mut abs_bits = [0u8; ABS_CNT / 8];
+        let _ =
+            ioctl_read(fd, eviocgbit(EV_ABS, abs_bits.len()), abs_bits.as_mut_ptr());
+        let mut ranges = AHashMap::new();
+        for code in [ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ] {
+            if !test_bit(&abs_bits, code as usize) {
+                continue;
+            }
+            // SAFETY: input_absinfo is plain old data; all-zero is valid.
+            let mut info: libc::input_absinfo = unsafe { std::mem::zeroed() };
+            if ioctl_read(fd, eviocgabs(code), &raw mut info).is_ok() {
+                ranges.insert(
+                    code,
+                    AbsRange {
+                        min: info.minimum,
+                        max: info.maximum,
+                    },
+                );
+            }
+        }
+
+        let mut ff_bits = [0u8; FF_CNT / 8];
+        let has_rumble = writable
+            && ioctl_read(fd, eviocgbit(EV_FF, ff_bits.len()), ff_bits.as_mut_ptr())
+                .is_ok()
+            && test_bit(&ff_bits, FF_RUMBLE as usize);
+
+        let key_codes = BUTTON_CODES
+            .into_iter()
+            .filter(|&code| test_bit(&keys, code as usize))
+            .collect();
+        let info = ControllerInfo {
+            id,
+            name,
+            supports_rumble: has_rumble,
+            vendor_id: input_id.vendor,
+            product_id: input_id.product,
+        };
+        let pad = Self {
+            id,
+            path: path.to_owned(),
+            file,
+            state: PadState {
+                key_codes,
+                ..PadState::with_ranges(ranges)
+            },
+            rumble_effect: None,
+        };
+        Some((pad, info))
+    }
+
+    /// Read everything pending. Returns `Err` once the device is gone.
+    fn drain(&mut self, out: &mut Vec<ControllerEvent>) -> io::Result<()> {
+        const EV_SIZE: usize = size_of::<libc::input_event>();
+        let mut buf = [0u8; EV_SIZE * 64];
+        loop {
+            let n = match self.file.read(&mut buf) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for chunk in buf[..n].chunks_exact(EV_SIZE) {
+                // SAFETY: the kernel writes whole input_event structs.
+                let ev: libc::input_event =
+                    unsafe { std::ptr::read_unaligned(chunk.as_ptr().cast()) };
+                if self.state.feed(self.id, ev.type_, ev.code, ev.value, out) {
+                    self.resync(out);
+                }
+            }
+        }
+    }
+
+    /// Read the current key and axis state back after dropped events.
+    fn resync(&mut self, out: &mut Vec<ControllerEvent>) {
+        let fd = self.file.as_raw_fd();
+        let mut keys = [0u8; KEY_CNT / 8];
+        if let Err(e) = ioctl_read(fd, eviocgkey(keys.len()), keys.as_mut_ptr()) {
+            eprintln!(
+                "[padjutsu-gamepad] failed to resync {}: {e}",
+                self.path.display()
+            );
+            return;
+        }
+        let abs: Vec<(u16, i32)> = ABS_CODES
+            .into_iter()
+            .filter_map(|code| {
+                // SAFETY: input_absinfo is plain old data; all-zero is valid.
+                let mut info: libc::input_absinfo = unsafe { std::mem::zeroed() };
+                ioctl_read(fd, eviocgabs(code), &raw mut info)
+                    .ok()
+                    .map(|()| (code, info.value))
+            })
+            .collect();
+        self.state.resync(self.id, &keys, &abs, out);
+    }
+
+    fn write_event(&mut self, type_: u16, code: u16, value: i32) -> io::Result<()> {
+        // SAFETY: input_event is plain old data; all-zero is valid.
+        let mut ev: libc::input_event = unsafe { std::mem::zeroed() };
+        ev.type_ = type_;
+        ev.code = code;
+        ev.value = value;
+        // SAFETY: viewing a fully initialised POD struct as bytes.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&raw const ev).cast::<u8>(),
+                size_of::<libc::input_event>(),
+            )
+        };
+        self.file.write_all(bytes)
+    }
+
+    /// Upload (or update) a rumble effect and play it once.
+    fn rumble(&mut self, low: u16, high: u16, ms: u32) -> io::Result<()> {
+        // SAFETY: ff_effect is plain old data; all-zero is valid.
+        let mut effect: libc::ff_effect = unsafe { std::mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        effect.id = self.rumble_effect.unwrap_or(-1);
+        effect.replay.length = u16::try_from(ms).unwrap_or(u16::MAX);
+        // SAFETY: the effect union starts with ff_rumble_effect for
+        // FF_RUMBLE and is large enough to hold it.
+        unsafe {
+            (&raw mut effect.u).cast::<libc::ff_rumble_effect>().write(
+                libc::ff_rumble_effect {
+                    strong_magnitude: low,
+                    weak_magnitude: high,
+                },
+            );
+        }
+        ioctl_read(self.file.as_raw_fd(), EVIOCSFF, &raw mut effect)?;
+        self.rumble_effect = Some(effect.id);
+        #[allow(clippy::cast_sign_loss)]
+        self.write_event(EV_FF, effect.id as u16, 1)
+    }
+
+    fn stop_rumble(&mut self) -> io::Result<()> {
+        match self.rumble_effect {
+            #[allow(clippy::cast_sign_loss)]
+            Some(effect) => self.write_event(EV_FF, effect as u16, 0),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Inotify watch on `/dev/input` for hotplug.
+struct Hotplug {
+    fd: OwnedFd,
+}
+
+impl Hotplug {
+    fn new() -> io::Result<Self> {
+        // SAFETY: plain syscall; the returned fd is checked below.
+        let raw =
+            unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `raw` is a fresh fd we own.
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+        let dir = std::ffi::CString::new(INPUT_DIR).expect("no NUL in path");
+        let mask = libc::IN_CREATE | libc::IN_ATTRIB | libc::IN_DELETE;
+        // SAFETY: `dir` is a valid C string.
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), dir.as_ptr(), mask) } < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    /// Pending changes as `(node path, removed)`.
+    fn changes(&self) -> Vec<(PathBuf, bool)> {
+        const HEADER: usize = size_of::<libc::inotify_event>();
+        let mut out = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            // SAFETY: reading into a local buffer of the given length.
+            let n = unsafe {
+                libc::read(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len())
+            };
+            let Ok(n) = usize::try_from(n) else { break };
+            if n == 0 {
+                break;
+            }
+            let mut off = 0;
+            while off + HEADER <= n {
+                // SAFETY: the kernel writes whole inotify_event headers.
+                let ev: libc::inotify_event =
+                    unsafe { std::ptr::read_unaligned(buf[off..].as_ptr().cast()) };
+                let name_len = ev.len as usize;
+                let name = &buf[off + HEADER..(off + HEADER + name_len).min(n)];
+                let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                let name = String::from_utf8_lossy(&name[..end]);
+                if name.starts_with("event") {
+                    let removed = ev.mask & libc::IN_DELETE != 0;
+                    out.push((Path::new(INPUT_DIR).join(&*name), removed));
+                }
+                off += HEADER + name_len;
+            }
+        }
+        out
+    }
+}
+
+/// Starts the evdev-backed runtime thread.
+pub(crate) fn start_evdev_thread(
+    inner: Arc<Inner>,
+    cmd_rx: Receiver<Command>,
+    ready_tx: Option<std::sync::mpsc::Sender<()>>,
+) {
+    thread::spawn(move || {
+        set_realtime_priority();
+        crate::metrics::init();
+
+        let hotplug = match Hotplug::new() {
+            Ok(h) => Some(h),
+            Err(e) => {
+                eprintln!("[padjutsu-gamepad] hotplug disabled: {e}");
+                None
+            }
+        };
+        let mut pads: AHashMap<ControllerId, Pad> = AHashMap::new();
+        let mut next_id: ControllerId = 0;
+
+        let mut connect = |pads: &mut AHashMap<ControllerId, Pad>, path: &Path| {
+            if pads.values().any(|p| p.path == path) {
+                return;
+            }
+            if let Some((pad, info)) = Pad::open(path, next_id) {
+                next_id += 1;
+                pads.insert(pad.id, pad);
+                if let Ok(mut map) = inner.controllers_info.write() {
+                    map.insert(info.id, info.clone());
+                }
+                broadcast(&inner, ControllerEvent::Connected(info));
+            }
+        };
+        let disconnect = |pads: &mut AHashMap<ControllerId, Pad>,
+                          id: ControllerId| {
+            if pads.remove(&id).is_some() {
+                if let Ok(mut map) = inner.controllers_info.write() {
+                    map.remove(&id);
+                }
+                broadcast(&inner, ControllerEvent::Disconnected(id));
+            }
+        };
+
+        // Initial enumeration
+        if let Ok(entries) = std::fs::read_dir(INPUT_DIR) {
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|n| n.to_string_lossy().starts_with("event"))
+                })
+                .collect();
+            paths.sort();
+            for path in paths {
+                connect(&mut pads, &path);
+            }
+        }
+
+        if let Some(tx) = ready_tx {
+            let _ = tx.send(());
+        }
+
+        let mut events = Vec::new();
+        loop {
+            let mut fds: Vec<libc::pollfd> = Vec::with_capacity(pads.len() + 1);
+            let mut ids: Vec<ControllerId> = Vec::with_capacity(pads.len());
+            if let Some(h) = &hotplug {
+                fds.push(libc::pollfd {
+                    fd: h.fd.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                });
+            }
+            for pad in pads.values() {
+                fds.push(libc::pollfd {
+                    fd: pad.file.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                });
+                ids.push(pad.id);
+            }
+            // SAFETY: `fds` is a valid array of the given length.
+            let ready = unsafe {
+                libc::poll(
+                    fds.as_mut_ptr(),
+                    fds.len() as libc::nfds_t,
+                    COMMAND_POLL_MS,
+                )
+            };
+
+            if ready > 0 {
+                let pad_fds = if hotplug.is_some() {
+                    &fds[1..]
+                } else {
+                    &fds[..]
+                };
+                for (pfd, id) in pad_fds.iter().zip(&ids) {
+                    if pfd.revents == 0 {
+                        continue;
+                    }
+                    let Some(pad) = pads.get_mut(id) else {
+                        continue;
+                    };
+                    let gone = pad.drain(&mut events).is_err()
+                        || pfd.revents & (libc::POLLERR | libc::POLLHUP) != 0;
+                    for event in events.drain(..) {
+                        broadcast(&inner, event);
+                    }
+                    if gone {
+                        disconnect(&mut pads, *id);
+                    }
+                }
+                if let Some(h) = hotplug.as_ref().filter(|_| fds[0].revents != 0) {
+                    for (path, removed) in h.changes() {
+                        if removed {
+                            let id =
+                                pads.values().find(|p| p.path == path).map(|p| p.id);
+                            if let Some(id) = id {
+                                disconnect(&mut pads, id);
+                            }
+                        } else {
+                            connect(&mut pads, &path);
+                        }
+                    }
+                }
+            }
+
+            // Handle commands
+            while let Ok(cmd) = cmd_rx.try_recv() {
+                match cmd {
+                    Command::Rumble { id, low, high, ms } => {
+                        if let Some(pad) = pads.get_mut(&id) {
+                            if let Err(e) = pad.rumble(low, high, ms) {
+                                eprintln!("Failed to set rumble: {e}");
+                            }
+                        }
+                    }
+                    Command::StopRumble { id } => {
+                        if let Some(pad) = pads.get_mut(&id) {
+                            if let Err(e) = pad.stop_rumble() {
+                                eprintln!("Failed to stop rumble: {e}");
+                            }
+                        }
+                    }
+                }
+            }
+
+            metrics_tick();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad() -> PadState {
+        let stick = AbsRange {
+            min: -32768,
+            max: 32767,
+        };
+        let trigger = AbsRange { min: 0, max: 255 };
+        PadState::with_ranges(AHashMap::from_iter([
+            (ABS_X, stick),
+            (ABS_Y, stick),
+            (ABS_Z, trigger),
+        ]))
+    }
+
+    fn run(
+        state: &mut PadState,
+        events: &[(u16, u16, i32)],
+    ) -> Vec<ControllerEvent> {
+        let mut out = Vec::new();
+        for &(t, c, v) in events {
+            state.translate(7, t, c, v, &mut out);
+        }
+        out
+    }
+
+    fn buttons(events: &[ControllerEvent]) -> Vec<(Button, bool)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                ControllerEvent::ButtonPressed { button, .. } => {
+                    Some((*button, true))
+                }
+                ControllerEvent::ButtonReleased { button, .. } => {
+                    Some((*button, false))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn face_buttons_follow_positions_and_ignore_repeat() {
+        let mut s = pad();
+        let out = run(
+            &mut s,
+            &[
+                (EV_KEY, BTN_SOUTH, 1),
+                (EV_KEY, BTN_SOUTH, 2),
+                (EV_KEY, BTN_NORTH, 1),
+                (EV_KEY, BTN_SOUTH, 0),
+            ],
+        );
+        assert_eq!(
+            buttons(&out),
+            [(Button::A, true), (Button::Y, true), (Button::A, false)]
+        );
+    }
+
+    #[test]
+    fn stick_normalizes_to_unit_range() {
+        let mut s = pad();
+        let out = run(&mut s, &[(EV_ABS, ABS_X, -32768), (EV_ABS, ABS_Y, 32767)]);
+        let values: Vec<f32> = out
+            .iter()
+            .filter_map(|e| match e {
+                ControllerEvent::AxisMotion { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values, [-1.0, 1.0]);
+    }
+
+    #[test]
+    fn analog_trigger_drives_button_and_hides_digital_code() {
+        let mut s = pad();
+        let out = run(
+            &mut s,
+            &[
+                (EV_KEY, BTN_TL2, 1),
+                (EV_ABS, ABS_Z, 200),
+                (EV_ABS, ABS_Z, 255),
+                (EV_ABS, ABS_Z, 10),
+                (EV_KEY, BTN_TL2, 0),
+            ],
+        );
+        assert_eq!(
+            buttons(&out),
+            [(Button::LeftTrigger, true), (Button::LeftTrigger, false)]
+        );
+    }
+
+    #[test]
+    fn digital_trigger_used_without_axis() {
+        let mut s = PadState::default();
+        let out = run(&mut s, &[(EV_KEY, BTN_TR2, 1), (EV_KEY, BTN_TR2, 0)]);
+        assert_eq!(
+            buttons(&out),
+            [(Button::RightTrigger, true), (Button::RightTrigger, false)]
+        );
+    }
+
+    #[test]
+    fn dropped_events_are_skipped_and_resynced() {
+        let mut s = pad();
+        s.key_codes = vec![BTN_SOUTH, BTN_EAST];
+        let mut out = Vec::new();
+        let mut resync = Vec::new();
+        for (t, c, v) in [
+            (EV_KEY, BTN_SOUTH, 1),
+            (EV_ABS, ABS_Y, 0),
+            (EV_SYN, SYN_REPORT, 0),
+            (EV_SYN, SYN_DROPPED, 0),
+            (EV_KEY, BTN_EAST, 1),
+            (EV_SYN, SYN_REPORT, 0),
+        ] {
+            resync.push(s.feed(7, t, c, v, &mut out));
+        }
+        assert_eq!(resync, [false, false, false, false, false, true]);
+        assert_eq!(buttons(&out), [(Button::A, true)]);
+
+        // By now the device has A up, B down, the stick pushed right and
+        // the hat left.
+        let mut keys = [0u8; KEY_CNT / 8];
+        keys[usize::from(BTN_EAST / 8)] |= 1 << (BTN_EAST % 8);
+        out.clear();
+        s.resync(
+            7,
+            &keys,
+            &[(ABS_X, 32767), (ABS_Y, 0), (ABS_HAT0X, -1), (ABS_HAT0Y, 0)],
+            &mut out,
+        );
+        assert_eq!(
+            buttons(&out),
+            [
+                (Button::A, false),
+                (Button::B, true),
+                (Button::DPadLeft, true)
+            ]
+        );
+        let axes: Vec<(Axis, f32)> = out
+            .iter()
+            .filter_map(|e| match e {
+                ControllerEvent::AxisMotion { axis, value, .. } => {
+                    Some((*axis, *value))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(axes, [(Axis::LeftX, 1.0)]);
+    }
+
+    #[test]
+    fn hat_maps_to_dpad_edges() {
+        let mut s = pad();
+        let out = run(
+            &mut s,
+            &[
+                (EV_ABS, ABS_HAT0X, -1),
+                (EV_ABS, ABS_HAT0X, 1),
+                (EV_ABS, ABS_HAT0X, 0),
+                (EV_ABS, ABS_HAT0Y, -1),
+            ],
+        );
+        assert_eq!(
+            buttons(&out),
+            [
+                (Button::DPadLeft, true),
+                (Button::DPadLeft, false),
+                (Button::DPadRight, true),
+                (Button::DPadRight, false),
+                (Button::DPadUp, true),
+            ]
+        );
+    }
+}
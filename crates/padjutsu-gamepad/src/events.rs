@@ -3,7 +3,8 @@ use crossbeam_channel::Receiver;
 use crate::types::{Button, ControllerId, ControllerInfo, Axis};
 
 /// Events emitted by the manager about controller lifecycle and input.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ControllerEvent {
     /// A controller or joystick has been connected and enumerated.
     Connected(ControllerInfo),
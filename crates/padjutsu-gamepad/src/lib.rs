@@ -1,23 +1,27 @@
 mod command;
+#[cfg(target_os = "linux")]
+mod evdev;
 mod events;
 mod handle;
 mod manager;
 mod metrics;
 mod runtime;
 mod types;
+mod virtual_pad;
 
 use thiserror::Error;
 
 pub use crate::events::{ControllerEvent, EventReceiver};
 pub use crate::handle::ControllerHandle;
-pub use crate::manager::ControllerManager;
+pub use crate::manager::{Backend, ControllerManager};
 pub use crate::runtime::set_realtime_priority;
 pub use crate::types::{Button, ControllerId, ControllerInfo, Axis};
+pub use crate::virtual_pad::{Rumble, VirtualController};
 
 /// Error type for controller management operations.
 #[derive(Debug, Error)]
 pub enum Error {
-    /// Failed to initialize the backend (SDL2 or subsystems).
+    /// Failed to initialize the backend (SDL2, evdev or subsystems).
     #[error("Backend init failed: {0}")]
     BackendInit(String),
     /// Requested controller was not found.
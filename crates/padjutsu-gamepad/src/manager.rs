@@ -5,11 +5,38 @@ use ahash::AHashMap;
 use crossbeam_channel::{bounded, unbounded, Sender};
 
 use crate::command::Command;
-use crate::Result;
+use crate::{Error, Result};
 use crate::events::{ControllerEvent, EventReceiver};
 use crate::handle::ControllerHandle;
-use crate::runtime::start_runtime_thread;
 use crate::types::{ControllerId, ControllerInfo};
+use crate::virtual_pad::{VirtualController, VirtualHub};
+
+/// Where controller input comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// SDL2 game controller API, polled every millisecond.
+    #[cfg(feature = "sdl2-backend")]
+    Sdl2,
+    /// Linux `/dev/input/event*` devices, event-driven with inotify hotplug.
+    #[cfg(target_os = "linux")]
+    Evdev,
+    /// No devices; controllers are created with
+    /// [`ControllerManager::connect_virtual`]. For tests.
+    Virtual,
+}
+
+#[cfg(not(any(feature = "sdl2-backend", target_os = "linux")))]
+compile_error!("padjutsu-gamepad needs the `sdl2-backend` feature on this platform");
+
+impl Default for Backend {
+    /// SDL2 when built in, evdev otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "sdl2-backend")]
+        return Backend::Sdl2;
+        #[cfg(not(feature = "sdl2-backend"))]
+        return Backend::Evdev;
+    }
+}
 
 /// Shared state used by the manager, the runtime loop and controller handles.
 pub(crate) struct Inner {
@@ -21,12 +48,19 @@ pub(crate) struct Inner {
 /// Manager responsible for discovering controllers and emitting events.
 pub struct ControllerManager {
     pub(crate) inner: Arc<Inner>,
+    virtual_hub: Option<Arc<VirtualHub>>,
 }
 
 impl ControllerManager {
-    /// Creates a new manager and starts the background runtime thread.
+    /// Creates a new manager on the default backend and starts the
+    /// background runtime thread.
     /// Blocks briefly until the initial device enumeration completes (up to 1s).
     pub fn new() -> Result<Self> {
+        Self::with_backend(Backend::default())
+    }
+
+    /// Creates a new manager on the given backend.
+    pub fn with_backend(backend: Backend) -> Result<Self> {
         let (cmd_tx, cmd_rx) = unbounded::<Command>();
         let inner = Arc::new(Inner {
             subscribers: Mutex::new(Vec::new()),
@@ -34,14 +68,46 @@ impl ControllerManager {
             cmd_tx,
         });
 
+        if backend == Backend::Virtual {
+            return Ok(Self {
+                inner,
+                virtual_hub: Some(Arc::new(VirtualHub::new(cmd_rx))),
+            });
+        }
+
         let inner_clone = inner.clone();
         let (ready_tx, ready_rx) = std::sync::mpsc::channel();
-        start_runtime_thread(inner_clone, cmd_rx, Some(ready_tx));
+        match backend {
+            #[cfg(feature = "sdl2-backend")]
+            Backend::Sdl2 => {
+                crate::runtime::start_sdl_thread(inner_clone, cmd_rx, Some(ready_tx))
+            }
+            #[cfg(target_os = "linux")]
+            Backend::Evdev => {
+                crate::evdev::start_evdev_thread(inner_clone, cmd_rx, Some(ready_tx))
+            }
+            Backend::Virtual => unreachable!("handled above"),
+        }
 
         // Best-effort wait for the initial enumeration. Time out if backend fails.
         let _ = ready_rx.recv_timeout(Duration::from_secs(1));
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            virtual_hub: None,
+        })
+    }
+
+    /// Plugs in a virtual controller that supports rumble. Only available
+    /// on [`Backend::Virtual`].
+    pub fn connect_virtual(&self, name: &str) -> Result<VirtualController> {
+        let hub = self.virtual_hub.clone().ok_or(Error::Unsupported)?;
+        Ok(VirtualController::connect(
+            self.inner.clone(),
+            hub,
+            name,
+            true,
+        ))
     }
 
     /// Subscribes to controller events. Dropped subscribers are cleaned automatically.
@@ -1,18 +1,28 @@
+#[cfg(feature = "sdl2-backend")]
 use std::sync::Arc;
+#[cfg(feature = "sdl2-backend")]
 use std::thread;
 use std::time::Instant;
 
+#[cfg(feature = "sdl2-backend")]
 use crossbeam_channel::Receiver;
+#[cfg(feature = "sdl2-backend")]
 use ahash::{AHashMap, AHashSet};
+#[cfg(feature = "sdl2-backend")]
 use sdl2::controller::{Button as SdlButton, GameController, Axis as SdlAxis};
+#[cfg(feature = "sdl2-backend")]
 use sdl2::event::Event;
+#[cfg(feature = "sdl2-backend")]
 use sdl2::haptic::Haptic;
+#[cfg(feature = "sdl2-backend")]
 use sdl2::joystick::Joystick;
 
+#[cfg(feature = "sdl2-backend")]
 use crate::command::Command;
 use crate::events::ControllerEvent;
 use crate::manager::Inner;
 use crate::metrics::Metrics;
+#[cfg(feature = "sdl2-backend")]
 use crate::types::{Button, ControllerId, ControllerInfo, Axis};
 
 // --- Mach real-time thread priority via raw FFI ---
@@ -119,7 +129,8 @@ pub fn set_realtime_priority() {
 }
 
 /// Starts the SDL2-backed runtime thread that drives device discovery and events.
-pub(crate) fn start_runtime_thread(
+#[cfg(feature = "sdl2-backend")]
+pub(crate) fn start_sdl_thread(
     inner: Arc<Inner>,
     cmd_rx: Receiver<Command>,
     ready_tx: Option<std::sync::mpsc::Sender<()>>,
@@ -268,7 +279,7 @@ pub(crate) fn start_runtime_thread(
                             let id = which as ControllerId;
                             if button_state
                                 .get_mut(&id)
-                                .map_or(false, |s| s.remove(&btn))
+                                .is_some_and(|s| s.remove(&btn))
                             {
                                 broadcast(
                                     &inner,
@@ -408,7 +419,7 @@ pub(crate) fn start_runtime_thread(
                                 let id = which as ControllerId;
                                 if button_state
                                     .get_mut(&id)
-                                    .map_or(false, |s| s.remove(&btn))
+                                    .is_some_and(|s| s.remove(&btn))
                                 {
                                     broadcast(
                                         &inner,
@@ -522,6 +533,7 @@ pub(crate) fn start_runtime_thread(
     });
 }
 
+#[cfg(feature = "sdl2-backend")]
 fn map_sdl_button(button: SdlButton) -> Option<Button> {
     Some(match button {
         SdlButton::A => Button::A,
@@ -543,6 +555,7 @@ fn map_sdl_button(button: SdlButton) -> Option<Button> {
     })
 }
 
+#[cfg(feature = "sdl2-backend")]
 fn map_sdl_axis(axis: SdlAxis) -> Option<Axis> {
     Some(match axis {
         SdlAxis::LeftX => Axis::LeftX,
@@ -558,7 +571,7 @@ thread_local! {
     static METRICS: std::cell::RefCell<Metrics> = std::cell::RefCell::new(Metrics::default());
 }
 
-fn broadcast(inner: &Inner, event: ControllerEvent) {
+pub(crate) fn broadcast(inner: &Inner, event: ControllerEvent) {
     use crossbeam_channel::TrySendError;
     if crate::metrics::is_enabled() {
         METRICS.with(|m| {
@@ -591,8 +604,8 @@ fn broadcast(inner: &Inner, event: ControllerEvent) {
     }
 }
 
-/// Periodic metrics flush — called from the SDL event loop on every iteration.
-fn metrics_tick() {
+/// Periodic metrics flush — called from the backend event loop on every iteration.
+pub(crate) fn metrics_tick() {
     if crate::metrics::is_enabled() {
         METRICS.with(|m| m.borrow_mut().maybe_report());
     }
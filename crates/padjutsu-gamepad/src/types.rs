@@ -3,6 +3,7 @@ pub type ControllerId = u32;
 
 /// Logical controller buttons supported by this crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, padjutsu_bit_derive::Bit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Button {
     A,
     B,
@@ -23,8 +24,32 @@ pub enum Button {
     DPadRight,
 }
 
+impl Button {
+    /// Every button, in declaration order.
+    pub const ALL: [Button; 17] = [
+        Button::A,
+        Button::B,
+        Button::X,
+        Button::Y,
+        Button::Back,
+        Button::Guide,
+        Button::Start,
+        Button::LeftStick,
+        Button::RightStick,
+        Button::LeftShoulder,
+        Button::RightShoulder,
+        Button::LeftTrigger,
+        Button::RightTrigger,
+        Button::DPadUp,
+        Button::DPadDown,
+        Button::DPadLeft,
+        Button::DPadRight,
+    ];
+}
+
 /// Analog axes supported by this crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Axis {
     LeftX,
     LeftY,
@@ -35,7 +60,8 @@ pub enum Axis {
 }
 
 /// Controller meta information that remains stable across events.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ControllerInfo {
     pub id: ControllerId,
     pub name: String,
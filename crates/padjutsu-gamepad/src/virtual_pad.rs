@@ -0,0 +1,261 @@
+//! Virtual controller backend.
+//!
+//! Controllers are created and driven from code instead of hardware. Input
+//! goes through the same subscriber broadcast as the real backends, and
+//! rumble commands sent through a `ControllerHandle` are captured so they
+//! can be asserted on.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ahash::{AHashMap, AHashSet};
+use crossbeam_channel::Receiver;
+
+use crate::command::Command;
+use crate::events::ControllerEvent;
+use crate::manager::Inner;
+use crate::runtime::broadcast;
+use crate::types::{Axis, Button, ControllerId, ControllerInfo};
+
+/// Trigger travel past which the trigger also counts as a pressed button,
+/// matching the SDL backend (20000 of 32767).
+const TRIGGER_THRESHOLD: f32 = 20000.0 / 32767.0;
+
+/// A rumble command received by a virtual controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rumble {
+    Play { low: u16, high: u16, ms: u32 },
+    Stop,
+}
+
+/// State shared by the virtual controllers of one manager.
+pub(crate) struct VirtualHub {
+    cmd_rx: Receiver<Command>,
+    next_id: AtomicU32,
+    rumbles: Mutex<AHashMap<ControllerId, Vec<Rumble>>>,
+}
+
+impl VirtualHub {
+    pub(crate) fn new(cmd_rx: Receiver<Command>) -> Self {
+        Self {
+            cmd_rx,
+            next_id: AtomicU32::new(0),
+            rumbles: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Move pending commands into the per-controller rumble logs.
+    fn collect(&self) {
+        let Ok(mut rumbles) = self.rumbles.lock() else {
+            return;
+        };
+        for cmd in self.cmd_rx.try_iter() {
+            let (id, rumble) = match cmd {
+                Command::Rumble { id, low, high, ms } => {
+                    (id, Rumble::Play { low, high, ms })
+                }
+                Command::StopRumble { id } => (id, Rumble::Stop),
+            };
+            rumbles.entry(id).or_default().push(rumble);
+        }
+    }
+}
+
+/// A controller driven from code. Dropping it disconnects the controller.
+pub struct VirtualController {
+    info: ControllerInfo,
+    inner: Arc<Inner>,
+    hub: Arc<VirtualHub>,
+    buttons: AHashSet<Button>,
+}
+
+impl VirtualController {
+    pub(crate) fn connect(
+        inner: Arc<Inner>,
+        hub: Arc<VirtualHub>,
+        name: &str,
+        supports_rumble: bool,
+    ) -> Self {
+        let id = hub.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = ControllerInfo {
+            id,
+            name: name.to_string(),
+            supports_rumble,
+            vendor_id: 0,
+            product_id: 0,
+        };
+        if let Ok(mut map) = inner.controllers_info.write() {
+            map.insert(id, info.clone());
+        }
+        broadcast(&inner, ControllerEvent::Connected(info.clone()));
+        Self {
+            info,
+            inner,
+            hub,
+            buttons: AHashSet::new(),
+        }
+    }
+
+    /// Returns the unique identifier of the controller.
+    pub fn id(&self) -> ControllerId {
+        self.info.id
+    }
+
+    /// Returns the info announced when the controller connected.
+    pub fn info(&self) -> &ControllerInfo {
+        &self.info
+    }
+
+    fn set_button(&mut self, button: Button, pressed: bool) {
+        let id = self.info.id;
+        if pressed {
+            if self.buttons.insert(button) {
+                broadcast(
+                    &self.inner,
+                    ControllerEvent::ButtonPressed { id, button },
+                );
+            }
+        } else if self.buttons.remove(&button) {
+            broadcast(&self.inner, ControllerEvent::ButtonReleased { id, button });
+        }
+    }
+
+    /// Press a button. Pressing a held button does nothing.
+    pub fn press(&mut self, button: Button) {
+        self.set_button(button, true);
+    }
+
+    /// Release a button. Releasing a button that is not held does nothing.
+    pub fn release(&mut self, button: Button) {
+        self.set_button(button, false);
+    }
+
+    /// Move an axis; the value is clamped to [-1.0, 1.0]. Triggers also
+    /// press and release their buttons, like the SDL backend.
+    pub fn set_axis(&mut self, axis: Axis, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        broadcast(
+            &self.inner,
+            ControllerEvent::AxisMotion {
+                id: self.info.id,
+                axis,
+                value,
+            },
+        );
+        let trigger = match axis {
+            Axis::LeftTrigger => Button::LeftTrigger,
+            Axis::RightTrigger => Button::RightTrigger,
+            _ => return,
+        };
+        self.set_button(trigger, value > TRIGGER_THRESHOLD);
+    }
+
+    /// Rumble commands sent to this controller so far, oldest first.
+    pub fn rumbles(&self) -> Vec<Rumble> {
+        self.hub.collect();
+        self.hub
+            .rumbles
+            .lock()
+            .map(|r| r.get(&self.info.id).cloned().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    /// Unplug the controller.
+    pub fn disconnect(self) {}
+}
+
+impl Drop for VirtualController {
+    fn drop(&mut self) {
+        let id = self.info.id;
+        if let Ok(mut map) = self.inner.controllers_info.write() {
+            map.remove(&id);
+        }
+        broadcast(&self.inner, ControllerEvent::Disconnected(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Backend, ControllerManager};
+
+    use super::*;
+
+    fn manager() -> ControllerManager {
+        ControllerManager::with_backend(Backend::Virtual).unwrap()
+    }
+
+    #[test]
+    fn lifecycle_goes_through_broadcast() {
+        let manager = manager();
+        let rx = manager.subscribe();
+        let mut pad = manager.connect_virtual("Pad").unwrap();
+        let id = pad.id();
+        assert_eq!(manager.controllers().len(), 1);
+
+        pad.press(Button::A);
+        pad.press(Button::A);
+        pad.release(Button::A);
+        pad.disconnect();
+        assert!(manager.controllers().is_empty());
+
+        let events: Vec<_> = rx.try_iter().collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ControllerEvent::Connected(info),
+                ControllerEvent::ButtonPressed { button: Button::A, .. },
+                ControllerEvent::ButtonReleased { button: Button::A, .. },
+                ControllerEvent::Disconnected(gone),
+            ] if info.id == id && *gone == id
+        ));
+    }
+
+    #[test]
+    fn trigger_axis_drives_trigger_button() {
+        let manager = manager();
+        let mut pad = manager.connect_virtual("Pad").unwrap();
+        let rx = manager.subscribe();
+        pad.set_axis(Axis::LeftTrigger, 0.9);
+        pad.set_axis(Axis::LeftTrigger, 0.1);
+        let buttons: Vec<_> = rx
+            .try_iter()
+            .filter_map(|e| match e {
+                ControllerEvent::ButtonPressed { button, .. } => {
+                    Some((button, true))
+                }
+                ControllerEvent::ButtonReleased { button, .. } => {
+                    Some((button, false))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            buttons,
+            [(Button::LeftTrigger, true), (Button::LeftTrigger, false)]
+        );
+    }
+
+    #[test]
+    fn rumble_through_handle_is_captured() {
+        let manager = manager();
+        let pad = manager.connect_virtual("Pad").unwrap();
+        let other = manager.connect_virtual("Other").unwrap();
+        let handle = manager.controller(pad.id()).unwrap();
+        handle.rumble(1.0, 0.0, Duration::from_millis(50)).unwrap();
+        handle.stop_rumble().unwrap();
+        assert_eq!(
+            pad.rumbles(),
+            [
+                Rumble::Play {
+                    low: 65535,
+                    high: 0,
+                    ms: 50
+                },
+                Rumble::Stop,
+            ]
+        );
+        assert!(other.rumbles().is_empty());
+    }
+}
@@ -9,15 +9,18 @@ use thiserror::Error;
 use padjutsu_bit_mask::Bitmask;
 use padjutsu_gamepad::Button;
 
-pub use profile_watcher::{ProfileWatcher, ProfileEvent};
+pub use profile_watcher::{load_profile_file, ProfileWatcher, ProfileEvent};
 
-pub use profile_parse::parse_profile;
+pub use profile_parse::{
+    button_name, chord_name, parse_button, parse_chord, parse_profile,
+    parse_profile_tests, parse_rule_sources,
+};
 pub use profile::{
-    Profile, ButtonAction, ButtonRule, ControllerSettings, ControllerSettingsMap,
-    StickRules, ArrowsParams, Axis, MouseParams, MouseRuntimeParams,
-    ScrollParams, ScrollRuntimeParams, StepperParams,
-    StickMode, StickSide, AppRules, RuleMap, ButtonRules, Macros, MouseButton,
-    MouseClickType, RawModifierKey,
+    Profile, ProfileTest, ButtonAction, ButtonRule, ControllerSettings,
+    ControllerSettingsMap, StickRules, ArrowsParams, Axis, MouseParams,
+    MouseRuntimeParams, ScrollParams, ScrollRuntimeParams, StepperParams, StickMode,
+    StickSide, AppRules, RuleMap, ButtonRules, Macros, MouseButton, MouseClickType,
+    RawModifierKey, RuleOrigin, RuleSource, HookEvent, Hooks, ShellAction,
 };
 // pub use profile::resolve_profile;
 pub use workspace::Workspace;
@@ -25,6 +25,9 @@ pub type ButtonRules = AHashMap<ButtonChord, ButtonRule>;
 /// A set of rules to handle stick movements for an app.
 pub type StickRules = AHashMap<StickSide, StickMode>;
 
+/// Shell commands to run on daemon events.
+pub type Hooks = AHashMap<HookEvent, Box<str>>;
+
 /// Profile is a collection of rules and settings for controllers and applications.
 #[derive(Debug, Clone)]
 pub struct Profile {
@@ -36,6 +39,71 @@ pub struct Profile {
     pub rules: RuleMap,
     /// Shell to run for shell actions.
     pub shell: Option<Box<str>>,
+    /// Commands to run on daemon events.
+    pub hooks: Hooks,
+}
+
+/// A daemon event a profile can hook a shell command to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    ControllerConnected,
+    ControllerDisconnected,
+    ProfileLoaded,
+    /// The profile file changed but could not be read or parsed.
+    ProfileFailed,
+    AppChanged,
+    /// A different rule set is in effect: another app's, `common`'s, none
+    /// because the app is blacklisted or none at all.
+    LayerChanged,
+    Paused,
+    Resumed,
+}
+
+impl HookEvent {
+    /// The event's key under `hooks:`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ControllerConnected => "controller_connected",
+            Self::ControllerDisconnected => "controller_disconnected",
+            Self::ProfileLoaded => "profile_loaded",
+            Self::ProfileFailed => "profile_failed",
+            Self::AppChanged => "app_changed",
+            Self::LayerChanged => "layer_changed",
+            Self::Paused => "paused",
+            Self::Resumed => "resumed",
+        }
+    }
+}
+
+/// A binding test embedded in a profile: the actions `input` should produce
+/// with each of `apps` frontmost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileTest {
+    pub name: String,
+    /// Bundle IDs to run the test with; empty means no frontmost app.
+    pub apps: Vec<BundleId>,
+    /// Input script, as taken by `padjutsud simulate`.
+    pub input: String,
+    /// Expected actions, in order.
+    pub expect: Vec<String>,
+}
+
+/// The profile section a set of rules for an app was written in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleOrigin {
+    /// The `common` section.
+    Common,
+    /// A selector naming the app's bundle ID.
+    App(Box<str>),
+    /// A selector reaching the app through one of its groups.
+    Group { selector: Box<str>, group: Box<str> },
+}
+
+/// One section's rules for an app, before merging.
+#[derive(Debug, Clone)]
+pub struct RuleSource {
+    pub origin: RuleOrigin,
+    pub rules: AppRules,
 }
 
 /// A set of rules to handle controller settings for an app.
@@ -43,6 +111,8 @@ pub struct Profile {
 pub struct AppRules {
     pub buttons: ButtonRules,
     pub sticks: StickRules,
+    /// Shell for this app's shell actions, instead of the profile's.
+    pub shell: Option<Box<str>>,
 }
 
 /// Controller parameters.
@@ -122,7 +192,10 @@ pub enum ButtonAction {
     /// Tap: press+release immediately on button press. No key repeat. Use `tap:`.
     TapKeystroke(Arc<KeyCombo>),
     Macros(Arc<Macros>),
-    Shell(String),
+    Shell(Arc<ShellAction>),
+    /// Start the command on button down, terminate it on button up. Use
+    /// `shell_hold:`.
+    ShellHold(Arc<ShellAction>),
     MouseClick {
         button: MouseButton,
         click_type: MouseClickType,
@@ -132,6 +205,28 @@ pub enum ButtonAction {
     /// Send a raw modifier key as a FlagsChanged CGEvent (macOS).
     /// This is needed for apps that listen for modifier-only keypresses.
     RawModifier(RawModifierKey),
+    /// Type Unicode text regardless of the keyboard layout. Use `type:`.
+    /// `delay_ms` is the pause after each character (`type_delay_ms`); other
+    /// output waits until the whole text is typed.
+    Type {
+        text: String,
+        delay_ms: u64,
+    },
+    /// Pause or resume all other mappings; stays live while paused. With
+    /// `pause_ms`, pausing resumes by itself after that long.
+    ToggleEnabled {
+        pause_ms: Option<u64>,
+    },
+}
+
+/// A shell command for a button action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellAction {
+    pub command: String,
+    /// Terminate the command if it still runs after this long.
+    pub timeout_ms: Option<u64>,
+    /// Don't start the command while an earlier run of it is still going.
+    pub single_instance: bool,
 }
 
 /// A rule for a gamepad button.
@@ -1,6 +1,11 @@
 use serde::Deserialize;
 
-use crate::{v1::ProfileV1, Profile, profile::ProfileError};
+use padjutsu_gamepad::Button;
+
+use crate::{
+    v1::ProfileV1, ButtonChord, Profile, ProfileTest, RuleSource,
+    profile::ProfileError,
+};
 
 /// Parse yaml profile.
 pub fn parse_profile(input: &str) -> Result<Profile, ProfileError> {
@@ -15,6 +20,59 @@ pub fn parse_profile(input: &str) -> Result<Profile, ProfileError> {
     }
 }
 
+/// Parse the binding tests embedded in a yaml profile.
+pub fn parse_profile_tests(input: &str) -> Result<Vec<ProfileTest>, ProfileError> {
+    let version = parse_version(input)?;
+    match version {
+        1 => {
+            let profile: ProfileV1 = serde_yaml::from_str(input)?;
+            Ok(profile.parse_tests()?)
+        }
+        _ => Err(ProfileError::UnsupportedVersion(version)),
+    }
+}
+
+/// Parse the sections of a yaml profile that apply to `bundle_id`, in the
+/// order they are merged.
+pub fn parse_rule_sources(
+    input: &str,
+    bundle_id: &str,
+) -> Result<Vec<RuleSource>, ProfileError> {
+    let version = parse_version(input)?;
+    match version {
+        1 => {
+            let profile: ProfileV1 = serde_yaml::from_str(input)?;
+            Ok(profile.rule_sources(bundle_id)?)
+        }
+        _ => Err(ProfileError::UnsupportedVersion(version)),
+    }
+}
+
+/// Parse a button name as written in profiles (`a`, `lt`, `dpad_left`, ...).
+pub fn parse_button(name: &str) -> Option<Button> {
+    crate::v1::parse_button_name(name).ok()
+}
+
+/// Parse a chord as written in profiles (`lt+a`).
+pub fn parse_chord(input: &str) -> Option<ButtonChord> {
+    crate::v1::parse_chord(input).ok()
+}
+
+/// The canonical name of a button in profiles.
+pub fn button_name(button: Button) -> &'static str {
+    crate::v1::button_name(button)
+}
+
+/// A chord in profile syntax, buttons in declaration order.
+pub fn chord_name(chord: &ButtonChord) -> String {
+    Button::ALL
+        .iter()
+        .filter(|button| chord.contains(**button))
+        .map(|button| button_name(*button))
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
 /// A profile with a version.
 #[derive(Debug, Clone, Deserialize)]
 struct VersionedProfile {
@@ -87,4 +145,380 @@ rules:
             other => panic!("expected Scroll, got {other:?}"),
         }
     }
+
+    #[test]
+    fn parse_type_action_with_delay() {
+        let yaml = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        type: "git commit -m \"ёж @\""
+        type_delay_ms: 15
+"#;
+        let profile = parse_profile(yaml).expect("should parse");
+        let rules = profile.rules.get("common").expect("common rules");
+        let chord = crate::ButtonChord::new(&[padjutsu_gamepad::Button::A]);
+        let rule = rules.buttons.get(&chord).expect("a rule");
+        match &rule.action {
+            crate::ButtonAction::Type { text, delay_ms } => {
+                assert_eq!(text, "git commit -m \"ёж @\"");
+                assert_eq!(*delay_ms, 15);
+            }
+            other => panic!("expected Type, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_type_action_conflicts_with_keystroke() {
+        let yaml = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        type: hello
+        keystroke: enter
+"#;
+        assert!(matches!(
+            parse_profile(yaml),
+            Err(ProfileError::V1Profile(crate::v1::Error::InvalidActions(_)))
+        ));
+    }
+
+    #[test]
+    fn parse_type_delay_requires_type() {
+        let yaml = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: enter
+        type_delay_ms: 15
+"#;
+        assert!(matches!(
+            parse_profile(yaml),
+            Err(ProfileError::V1Profile(crate::v1::Error::InvalidOption(..)))
+        ));
+    }
+
+    #[test]
+    fn parse_type_delay_is_capped() {
+        let yaml = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        type: hello
+        type_delay_ms: 5000
+"#;
+        assert!(matches!(
+            parse_profile(yaml),
+            Err(ProfileError::V1Profile(crate::v1::Error::InvalidOption(..)))
+        ));
+    }
+
+    #[test]
+    fn parse_shell_options_require_shell() {
+        for option in ["timeout_ms: 1000", "single_instance: true"] {
+            let yaml = format!(
+                "version: 1\nrules:\n  common:\n    buttons:\n      a:\n        keystroke: enter\n        {option}\n"
+            );
+            assert!(
+                matches!(
+                    parse_profile(&yaml),
+                    Err(ProfileError::V1Profile(crate::v1::Error::InvalidOption(
+                        ..
+                    )))
+                ),
+                "{option}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_rejects_keys_the_platform_cannot_send() {
+        let Some(key) = padjutsu_control::Key::ALL
+            .iter()
+            .find(|key| !key.is_supported())
+        else {
+            // Every key can be sent here.
+            return;
+        };
+        let yaml = format!(
+            "version: 1\nrules:\n  common:\n    buttons:\n      a:\n        tap: cmd+{}\n",
+            key.name()
+        );
+        assert!(matches!(
+            parse_profile(&yaml),
+            Err(ProfileError::V1Profile(crate::v1::Error::UnsupportedKey(
+                ..
+            )))
+        ));
+    }
+
+    #[test]
+    fn parse_toggle_enabled_action() {
+        let yaml = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      start+back:
+        toggle_enabled: true
+        pause_ms: 60000
+      a:
+        toggle_enabled: false
+        keystroke: enter
+"#;
+        let profile = parse_profile(yaml).expect("should parse");
+        let rules = profile.rules.get("common").expect("common rules");
+        let chord = parse_chord("start+back").unwrap();
+        match &rules.buttons[&chord].action {
+            crate::ButtonAction::ToggleEnabled { pause_ms } => {
+                assert_eq!(*pause_ms, Some(60000));
+            }
+            other => panic!("expected ToggleEnabled, got {other:?}"),
+        }
+        let chord = parse_chord("a").unwrap();
+        assert!(matches!(
+            rules.buttons[&chord].action,
+            crate::ButtonAction::Keystroke(_)
+        ));
+    }
+
+    #[test]
+    fn parse_hooks() {
+        let yaml = r#"
+version: 1
+hooks:
+  profile_failed: notify-send padjutsu "$PADJUTSU_ERROR"
+  layer_changed: ""
+"#;
+        let profile = parse_profile(yaml).expect("should parse");
+        assert_eq!(
+            profile
+                .hooks
+                .get(&crate::HookEvent::ProfileFailed)
+                .map(|c| &**c),
+            Some(r#"notify-send padjutsu "$PADJUTSU_ERROR""#)
+        );
+        assert_eq!(profile.hooks.len(), 1);
+
+        let typo = "version: 1\nhooks:\n  profile_fail: echo\n";
+        assert!(parse_profile(typo).is_err());
+    }
+
+    #[test]
+    fn parse_shell_options_and_app_shell() {
+        let yaml = r#"
+version: 1
+shell: /bin/zsh
+rules:
+  common:
+    buttons:
+      a:
+        shell: make build
+        timeout_ms: 30000
+        single_instance: true
+      b:
+        shell_hold: rec -q /tmp/ptt.wav
+  com.apple.Terminal:
+    shell: /bin/bash
+    buttons:
+      x:
+        shell: ls
+"#;
+        let profile = parse_profile(yaml).expect("should parse");
+        let common = profile.rules.get("common").expect("common rules");
+        assert_eq!(common.shell, None);
+        let shell = |rules: &crate::AppRules, chord: &str| match &rules.buttons
+            [&parse_chord(chord).unwrap()]
+            .action
+        {
+            crate::ButtonAction::Shell(shell) => (false, (**shell).clone()),
+            crate::ButtonAction::ShellHold(shell) => (true, (**shell).clone()),
+            other => panic!("expected a shell action, got {other:?}"),
+        };
+        assert_eq!(
+            shell(common, "a"),
+            (
+                false,
+                crate::ShellAction {
+                    command: "make build".into(),
+                    timeout_ms: Some(30000),
+                    single_instance: true,
+                }
+            )
+        );
+        let (hold, action) = shell(common, "b");
+        assert!(hold);
+        assert_eq!(action.command, "rec -q /tmp/ptt.wav");
+        assert!(!action.single_instance);
+
+        let terminal = profile.rules.get("com.apple.Terminal").unwrap();
+        assert_eq!(terminal.shell.as_deref(), Some("/bin/bash"));
+        assert_eq!(shell(terminal, "a").1.command, "make build");
+
+        let both = "version: 1\nrules:\n  common:\n    buttons:\n      a:\n        \
+                    shell: ls\n        shell_hold: ls\n";
+        assert!(parse_profile(both).is_err());
+    }
+
+    #[test]
+    fn parse_tests_expands_groups_and_names_cases() {
+        let yaml = r#"
+version: 1
+groups:
+  messaging: [com.tinyspeck.slackmacgap, ru.keepcoder.Telegram]
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: enter
+tests:
+  - input: press a; release a
+    expect: [tap enter]
+  - name: tab switching
+    app: $messaging
+    input: press lt; press dpad_left
+    expect: ["tap cmd+shift+tab"]
+"#;
+        let tests = parse_profile_tests(yaml).expect("should parse");
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].name, "common: press a; release a");
+        assert!(tests[0].apps.is_empty());
+        assert_eq!(tests[1].name, "tab switching");
+        assert_eq!(
+            tests[1].apps,
+            vec![
+                Box::<str>::from("com.tinyspeck.slackmacgap"),
+                Box::<str>::from("ru.keepcoder.Telegram"),
+            ]
+        );
+        assert_eq!(tests[1].expect, vec!["tap cmd+shift+tab".to_string()]);
+    }
+
+    #[test]
+    fn parse_tests_rejects_unknown_group() {
+        let yaml = r#"
+version: 1
+tests:
+  - app: $nope
+    input: press a
+    expect: []
+"#;
+        assert!(matches!(
+            parse_profile_tests(yaml),
+            Err(ProfileError::V1Profile(crate::v1::Error::BadSelector(_)))
+        ));
+    }
+
+    const OVERLAPPING: &str = r#"
+version: 1
+groups:
+  messaging: [com.tinyspeck.slackmacgap, ru.keepcoder.Telegram]
+rules:
+  common:
+    buttons:
+      lt+dpad_left:
+        keystroke: cmd+shift+tab
+  ru.keepcoder.Telegram:
+    buttons:
+      lt+dpad_left:
+        keystroke: cmd+[
+  $messaging:
+    buttons:
+      lt+dpad_left:
+        keystroke: ctrl+shift+tab
+"#;
+
+    #[test]
+    fn app_section_overrides_its_groups() {
+        let chord = parse_chord("lt+dpad_left").unwrap();
+        for _ in 0..8 {
+            let profile = parse_profile(OVERLAPPING).expect("should parse");
+            let rule = &profile.rules["ru.keepcoder.Telegram"].buttons[&chord];
+            match &rule.action {
+                crate::ButtonAction::Keystroke(combo) => {
+                    assert_eq!(combo.to_string(), "cmd+bracket_left");
+                }
+                other => panic!("expected Keystroke, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn later_group_wins_for_an_app_in_both() {
+        let chord = parse_chord("a").unwrap();
+        // Each group types its own initial.
+        let winner = |first: &str, second: &str| {
+            let text = format!(
+                "version: 1
+groups:
+  chat: [com.app]
+  work: [com.app]
+rules:
+  ${first}:
+    buttons:
+      a:
+        keystroke: {}
+  ${second}:
+    buttons:
+      a:
+        keystroke: {}
+",
+                &first[..1],
+                &second[..1],
+            );
+            let profile = parse_profile(&text).expect("should parse");
+            match &profile.rules["com.app"].buttons[&chord].action {
+                crate::ButtonAction::Keystroke(combo) => combo.to_string(),
+                other => panic!("expected Keystroke, got {other:?}"),
+            }
+        };
+        assert_eq!(winner("chat", "work"), "w");
+        assert_eq!(winner("work", "chat"), "c");
+    }
+
+    #[test]
+    fn rule_sources_follow_merge_order() {
+        let origins = |app: &str| -> Vec<crate::RuleOrigin> {
+            parse_rule_sources(OVERLAPPING, app)
+                .unwrap()
+                .into_iter()
+                .map(|source| source.origin)
+                .collect()
+        };
+        let group = crate::RuleOrigin::Group {
+            selector: "$messaging".into(),
+            group: "messaging".into(),
+        };
+        assert_eq!(
+            origins("ru.keepcoder.Telegram"),
+            [
+                crate::RuleOrigin::Common,
+                group.clone(),
+                crate::RuleOrigin::App("ru.keepcoder.Telegram".into()),
+            ]
+        );
+        assert_eq!(
+            origins("com.tinyspeck.slackmacgap"),
+            [crate::RuleOrigin::Common, group]
+        );
+        assert_eq!(origins("com.apple.Safari"), [crate::RuleOrigin::Common]);
+    }
+
+    #[test]
+    fn chord_names_round_trip() {
+        for button in Button::ALL {
+            assert_eq!(parse_button(button_name(button)), Some(button));
+        }
+        let chord = parse_chord("a + left_trigger").unwrap();
+        assert_eq!(chord_name(&chord), "a+lt");
+        assert_eq!(parse_chord("lt+q"), None);
+    }
 }
@@ -23,7 +23,7 @@ pub enum WatcherError {
 }
 
 pub enum ProfileEvent {
-    Changed(Profile),
+    Changed(Box<Profile>),
     Removed,
     Error(WatcherError),
 }
@@ -31,25 +31,22 @@ pub enum ProfileEvent {
 type ProfileEventSender = mpsc::Sender<ProfileEvent>;
 pub type ProfileEventReceiver = mpsc::Receiver<ProfileEvent>;
 
+/// Read and parse the profile at `path`, the same way the watcher does when
+/// the file changes.
+pub fn load_profile_file(path: &Path) -> Result<Profile, WatcherError> {
+    let content = fs::read_to_string(path)?;
+    Ok(parse_profile(&content)?)
+}
+
 fn send_profile_event(path: &Path, tx: &ProfileEventSender) {
     debug!("[watcher] reading profile from {}", path.display());
-    match fs::read_to_string(path) {
-        Ok(content) => {
-            match parse_profile(&content) {
-                Ok(workspace) => {
-                    debug!("[watcher] profile parsed successfully, sending Changed event");
-                    let _ = tx.send(ProfileEvent::Changed(workspace));
-                }
-                Err(e) => {
-                    warn!("[watcher] profile parse error: {e}");
-                    let error = WatcherError::Parse(e);
-                    let _ = tx.send(ProfileEvent::Error(error));
-                }
-            }
+    match load_profile_file(path) {
+        Ok(workspace) => {
+            debug!("[watcher] profile parsed successfully, sending Changed event");
+            let _ = tx.send(ProfileEvent::Changed(Box::new(workspace)));
         }
-        Err(e) => {
-            warn!("[watcher] failed to read profile: {e}");
-            let error = WatcherError::Io(e);
+        Err(error) => {
+            warn!("[watcher] failed to load profile: {error}");
             let _ = tx.send(ProfileEvent::Error(error));
         }
     };
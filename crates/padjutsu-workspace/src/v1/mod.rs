@@ -7,6 +7,7 @@ mod combo;
 use thiserror::Error;
 
 pub use profile::ProfileV1;
+pub(crate) use parse::{button_name, parse_button_name, parse_chord};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -18,6 +19,8 @@ pub enum Error {
     InvalidTrigger(String),
     #[error("invalid actions for {0}")]
     InvalidActions(String),
+    #[error("invalid option for {0}: {1}")]
+    InvalidOption(String, String),
     #[error("invalid id: {0} -> {1}")]
     InvalidId(String, String),
     #[error("invalid button: {0}")]
@@ -30,6 +33,8 @@ pub enum Error {
     InvalidAxis(String),
     #[error("key parse error: {0}")]
     KeyParse(String),
+    #[error("key '{0}' in '{1}' is not supported on this platform")]
+    UnsupportedKey(String, String),
     #[error("no profile matches path \"{0}\"")]
     ProfileNotFound(String),
     #[error("selector error: {0}")]
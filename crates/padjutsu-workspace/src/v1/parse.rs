@@ -7,14 +7,18 @@ use padjutsu_gamepad::Button;
 use crate::v1::profile::{ProfileV1ButtonRule, ProfileV1Stick};
 use crate::profile::{
     AppRules, ArrowsParams, Axis, ButtonAction, ButtonRule, ButtonRules,
-    ControllerSettings, ControllerSettingsMap, Macros, MouseButton, MouseClickType,
-    MouseParams, MouseRuntimeParams, Profile, RawModifierKey, RuleMap, ScrollParams,
-    ScrollRuntimeParams, StepperParams, StickMode, StickRules, StickSide,
+    ControllerSettings, ControllerSettingsMap, HookEvent, Hooks, Macros,
+    MouseButton, MouseClickType, MouseParams, MouseRuntimeParams, Profile,
+    ProfileTest, RawModifierKey, RuleMap, RuleOrigin, RuleSource, ScrollParams,
+    ScrollRuntimeParams, ShellAction, StepperParams, StickMode, StickRules,
+    StickSide,
 };
 use crate::ButtonChord;
 
 use super::Error;
-use super::profile::{ProfileV1, ProfileV1App, ProfileV1ControllerSettings};
+use super::profile::{
+    ProfileV1, ProfileV1App, ProfileV1ControllerSettings, ProfileV1Hooks,
+};
 use super::strings::COMMON_BUNDLE_ID;
 use super::selector::Selector;
 use super::combo::parse_terms_with_delim;
@@ -38,10 +42,9 @@ impl ProfileV1 {
             rules.insert(COMMON_BUNDLE_ID.into(), common_rules);
         }
 
-        for (selector, app_actions) in self.rules.clone().into_iter() {
-            let parsed_selector = Selector::parse(&selector)?;
+        for (selector, parsed_selector, app_actions) in self.sections()? {
             let bundle_ids = parsed_selector.materialize(&self.groups)?;
-            let app_rules = parse_app_rules(app_actions, &selector)?;
+            let app_rules = parse_app_rules(app_actions.clone(), selector)?;
 
             for bundle_id in bundle_ids {
                 // Using common rules as default. If there are no common rules, use empty rules.
@@ -50,6 +53,9 @@ impl ProfileV1 {
                     if let Some(current_rules) = rules.get_mut(&bundle_id) {
                         current_rules.buttons.extend(app_rules.buttons.clone());
                         current_rules.sticks.extend(app_rules.sticks.clone());
+                        if app_rules.shell.is_some() {
+                            current_rules.shell = app_rules.shell.clone();
+                        }
 
                         current_rules.clone()
                     } else {
@@ -57,6 +63,9 @@ impl ProfileV1 {
                             common_rules.clone().unwrap_or_default();
                         default_rules.buttons.extend(app_rules.buttons.clone());
                         default_rules.sticks.extend(app_rules.sticks.clone());
+                        if app_rules.shell.is_some() {
+                            default_rules.shell = app_rules.shell.clone();
+                        }
 
                         rules.insert(bundle_id.clone(), default_rules.clone());
                         default_rules
@@ -75,8 +84,100 @@ impl ProfileV1 {
             controllers,
             rules,
             shell: self.shell.clone(),
+            hooks: parse_hooks(&self.hooks),
         })
     }
+
+    /// Rule sections in merge order: selectors with a group first, so a
+    /// section naming an app directly overrides its groups, each in file
+    /// order, so of two groups covering an app the later one wins.
+    fn sections(&self) -> Result<Vec<(&str, Selector<'_>, &ProfileV1App)>, Error> {
+        let mut sections = self
+            .rules
+            .iter()
+            .map(|(selector, app)| {
+                Ok((&**selector, Selector::parse(selector)?, app))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        sections.sort_by_key(|(_, selector, _)| !selector.has_group());
+        Ok(sections)
+    }
+
+    /// The unmerged sections that apply to `bundle_id`, in the order
+    /// [`ProfileV1::parse`] merges them, so later ones win. `common` comes
+    /// first; an app no section reaches gets only `common`.
+    pub fn rule_sources(&self, bundle_id: &str) -> Result<Vec<RuleSource>, Error> {
+        let mut sources = Vec::new();
+        if let Some(common) = self.rules.get(COMMON_BUNDLE_ID) {
+            sources.push(RuleSource {
+                origin: RuleOrigin::Common,
+                rules: parse_app_rules(common.clone(), COMMON_BUNDLE_ID)?,
+            });
+        }
+        if bundle_id.is_empty() || bundle_id == COMMON_BUNDLE_ID {
+            return Ok(sources);
+        }
+        for (selector, parsed_selector, app_actions) in self.sections()? {
+            // Validate groups the same way `parse` does.
+            parsed_selector.materialize(&self.groups)?;
+            let Some(origin) =
+                parsed_selector.origin_of(selector, bundle_id, &self.groups)
+            else {
+                continue;
+            };
+            sources.push(RuleSource {
+                origin,
+                rules: parse_app_rules(app_actions.clone(), selector)?,
+            });
+        }
+        Ok(sources)
+    }
+
+    /// Parse the `tests` section. Groups in `app` are expanded to their
+    /// members.
+    pub fn parse_tests(&self) -> Result<Vec<ProfileTest>, Error> {
+        let mut tests = Vec::with_capacity(self.tests.len());
+        for raw in &self.tests {
+            let apps = match &raw.app {
+                Some(app) => Selector::parse(app)?.materialize(&self.groups)?,
+                None => Vec::new(),
+            };
+            let name = raw.name.clone().unwrap_or_else(|| {
+                let app = raw.app.as_deref().unwrap_or(COMMON_BUNDLE_ID);
+                format!("{app}: {}", raw.input)
+            });
+            tests.push(ProfileTest {
+                name,
+                apps,
+                input: raw.input.clone(),
+                expect: raw.expect.clone(),
+            });
+        }
+        Ok(tests)
+    }
+}
+
+fn parse_hooks(raw: &ProfileV1Hooks) -> Hooks {
+    [
+        (HookEvent::ControllerConnected, &raw.controller_connected),
+        (
+            HookEvent::ControllerDisconnected,
+            &raw.controller_disconnected,
+        ),
+        (HookEvent::ProfileLoaded, &raw.profile_loaded),
+        (HookEvent::ProfileFailed, &raw.profile_failed),
+        (HookEvent::AppChanged, &raw.app_changed),
+        (HookEvent::LayerChanged, &raw.layer_changed),
+        (HookEvent::Paused, &raw.paused),
+        (HookEvent::Resumed, &raw.resumed),
+    ]
+    .into_iter()
+    // An empty command is the same as no hook.
+    .filter_map(|(event, command)| {
+        let command = command.as_ref().filter(|c| !c.trim().is_empty())?;
+        Some((event, command.clone()))
+    })
+    .collect()
 }
 
 fn parse_controller_settings(
@@ -105,7 +206,7 @@ fn parse_device_remap(
 }
 
 /// Parse a button name into a `Button` enum.
-fn parse_button_name(name: &str) -> Result<Button, Error> {
+pub(crate) fn parse_button_name(name: &str) -> Result<Button, Error> {
     Ok(match name {
         "a" => Button::A,
         "b" => Button::B,
@@ -133,6 +234,30 @@ fn parse_button_name(name: &str) -> Result<Button, Error> {
     })
 }
 
+/// The canonical profile name of a button, as accepted by
+/// [`parse_button_name`].
+pub(crate) fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::A => "a",
+        Button::B => "b",
+        Button::X => "x",
+        Button::Y => "y",
+        Button::Back => "back",
+        Button::Guide => "guide",
+        Button::Start => "start",
+        Button::LeftStick => "ls",
+        Button::RightStick => "rs",
+        Button::LeftShoulder => "lb",
+        Button::RightShoulder => "rb",
+        Button::LeftTrigger => "lt",
+        Button::RightTrigger => "rt",
+        Button::DPadUp => "dpad_up",
+        Button::DPadDown => "dpad_down",
+        Button::DPadLeft => "dpad_left",
+        Button::DPadRight => "dpad_right",
+    }
+}
+
 /// Parse a v1 app rules.
 fn parse_app_rules(raw: ProfileV1App, bundle_id: &str) -> Result<AppRules, Error> {
     let mut button_rules: ButtonRules = AHashMap::new();
@@ -153,6 +278,7 @@ fn parse_app_rules(raw: ProfileV1App, bundle_id: &str) -> Result<AppRules, Error
     Ok(AppRules {
         buttons: button_rules,
         sticks: stick_rules,
+        shell: raw.shell,
     })
 }
 
@@ -164,7 +290,7 @@ fn parse_stick_side(raw: &str) -> Result<StickSide, Error> {
     })
 }
 
-fn parse_chord(input: &str) -> Result<ButtonChord, Error> {
+pub(crate) fn parse_chord(input: &str) -> Result<ButtonChord, Error> {
     let mut set = ButtonChord::empty();
     for term in parse_terms_with_delim(input, '+')
         .map_err(|e| Error::InvalidTrigger(format!("{input}: {e:?}")))?
@@ -179,51 +305,106 @@ fn parse_chord(input: &str) -> Result<ButtonChord, Error> {
     }
 }
 
+/// Upper bound for `type_delay_ms`. The output worker sleeps between the
+/// characters, so every other key and release waits for the whole text.
+const MAX_TYPE_DELAY_MS: u64 = 200;
+
 fn parse_button_rule(
     raw: ProfileV1ButtonRule,
     target_name: &str,
 ) -> Result<ButtonRule, Error> {
+    if raw.type_delay_ms.is_some() && raw.type_text.is_none() {
+        return Err(Error::InvalidOption(
+            target_name.to_string(),
+            "type_delay_ms needs type".to_string(),
+        ));
+    }
+    if raw.type_delay_ms.is_some_and(|ms| ms > MAX_TYPE_DELAY_MS) {
+        return Err(Error::InvalidOption(
+            target_name.to_string(),
+            format!("type_delay_ms is at most {MAX_TYPE_DELAY_MS}"),
+        ));
+    }
+    // `shell` and `shell_hold` share a slot: a rule has at most one.
+    let shell = match (raw.shell, raw.shell_hold) {
+        (None, None) => None,
+        (Some(command), None) => Some((command, false)),
+        (None, Some(command)) => Some((command, true)),
+        (Some(_), Some(_)) => {
+            return Err(Error::InvalidActions(target_name.to_string()))
+        }
+    };
+    if shell.is_none() && (raw.timeout_ms.is_some() || raw.single_instance.is_some())
+    {
+        return Err(Error::InvalidOption(
+            target_name.to_string(),
+            "timeout_ms and single_instance need shell or shell_hold".to_string(),
+        ));
+    }
     let action = match (
         raw.keystroke,
         raw.hold,
         raw.tap,
         raw.macros,
-        raw.shell,
+        shell,
         raw.click,
         raw.hold_click,
         raw.rawkey,
+        raw.type_text,
+        raw.toggle_enabled.filter(|enabled| *enabled),
     ) {
-        (Some(keystroke), None, None, None, None, None, None, None) => {
+        (Some(keystroke), None, None, None, None, None, None, None, None, None) => {
             let keystroke = parse_keystroke(&keystroke)?;
             ButtonAction::Keystroke(Arc::new(keystroke))
         }
-        (None, Some(hold), None, None, None, None, None, None) => {
+        (None, Some(hold), None, None, None, None, None, None, None, None) => {
             let keystroke = parse_keystroke(&hold)?;
             ButtonAction::HoldKeystroke(Arc::new(keystroke))
         }
-        (None, None, Some(tap), None, None, None, None, None) => {
+        (None, None, Some(tap), None, None, None, None, None, None, None) => {
             let keystroke = parse_keystroke(&tap)?;
             ButtonAction::TapKeystroke(Arc::new(keystroke))
         }
-        (None, None, None, Some(macros), None, None, None, None) => {
+        (None, None, None, Some(macros), None, None, None, None, None, None) => {
             let macros = parse_macros(&macros)?;
             ButtonAction::Macros(Arc::new(macros))
         }
-        (None, None, None, None, Some(shell), None, None, None) => {
-            ButtonAction::Shell(shell)
+        (None, None, None, None, Some(shell), None, None, None, None, None) => {
+            let (command, hold) = shell;
+            let shell = Arc::new(ShellAction {
+                command,
+                timeout_ms: raw.timeout_ms,
+                single_instance: raw.single_instance.unwrap_or(false),
+            });
+            if hold {
+                ButtonAction::ShellHold(shell)
+            } else {
+                ButtonAction::Shell(shell)
+            }
         }
-        (None, None, None, None, None, Some(click), None, None) => {
+        (None, None, None, None, None, Some(click), None, None, None, None) => {
             let (button, click_type) = parse_click_spec(&click, target_name)?;
             ButtonAction::MouseClick { button, click_type }
         }
-        (None, None, None, None, None, None, Some(hold), None) => {
+        (None, None, None, None, None, None, Some(hold), None, None, None) => {
             let button = parse_mouse_button(&hold, target_name)?;
             ButtonAction::HoldClick(button)
         }
-        (None, None, None, None, None, None, None, Some(rawkey)) => {
+        (None, None, None, None, None, None, None, Some(rawkey), None, None) => {
             let modifier = parse_raw_modifier(&rawkey, target_name)?;
             ButtonAction::RawModifier(modifier)
         }
+        (None, None, None, None, None, None, None, None, Some(text), None) => {
+            ButtonAction::Type {
+                text,
+                delay_ms: raw.type_delay_ms.unwrap_or(0),
+            }
+        }
+        (None, None, None, None, None, None, None, None, None, Some(_)) => {
+            ButtonAction::ToggleEnabled {
+                pause_ms: raw.pause_ms,
+            }
+        }
         _ => return Err(Error::InvalidActions(target_name.to_string())),
     };
 
@@ -283,7 +464,11 @@ fn parse_raw_modifier(
 }
 
 fn parse_keystroke(input: &str) -> Result<KeyCombo, Error> {
-    input.parse::<KeyCombo>().map_err(Error::KeyParse)
+    let combo = input.parse::<KeyCombo>().map_err(Error::KeyParse)?;
+    if let Some(key) = combo.unsupported_keys().next() {
+        return Err(Error::UnsupportedKey(key.name().into(), input.into()));
+    }
+    Ok(combo)
 }
 
 fn parse_macros(input: &[String]) -> Result<Macros, Error> {
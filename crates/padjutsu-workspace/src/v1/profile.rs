@@ -1,4 +1,5 @@
 use ahash::AHashMap;
+use indexmap::IndexMap;
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -12,9 +13,46 @@ pub struct ProfileV1 {
     #[serde(default)]
     pub groups: AHashMap<String, Vec<Box<str>>>,
     #[serde(default)]
-    pub rules: AHashMap<Box<str>, ProfileV1App>, // bundle_id -> app mapping
+    /// Sections in file order, which decides between groups.
+    pub rules: IndexMap<Box<str>, ProfileV1App>, // bundle_id -> app mapping
     #[serde(default)]
     pub shell: Option<Box<str>>,
+    #[serde(default)]
+    pub hooks: ProfileV1Hooks,
+    #[serde(default)]
+    pub tests: Vec<ProfileV1Test>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProfileV1Hooks {
+    #[serde(default)]
+    pub controller_connected: Option<Box<str>>,
+    #[serde(default)]
+    pub controller_disconnected: Option<Box<str>>,
+    #[serde(default)]
+    pub profile_loaded: Option<Box<str>>,
+    #[serde(default)]
+    pub profile_failed: Option<Box<str>>,
+    #[serde(default)]
+    pub app_changed: Option<Box<str>>,
+    #[serde(default)]
+    pub layer_changed: Option<Box<str>>,
+    #[serde(default)]
+    pub paused: Option<Box<str>>,
+    #[serde(default)]
+    pub resumed: Option<Box<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProfileV1Test {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub app: Option<String>, // bundle id or $group
+    pub input: String,
+    pub expect: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -24,6 +62,8 @@ pub(crate) struct ProfileV1App {
     pub buttons: AHashMap<String, ProfileV1ButtonRule>, // chord -> button rule
     #[serde(default)]
     pub sticks: AHashMap<String, ProfileV1Stick>, // side -> stick rules
+    #[serde(default)]
+    pub shell: Option<Box<str>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -42,11 +82,25 @@ pub(crate) struct ProfileV1ButtonRule {
     #[serde(default)]
     pub shell: Option<String>,
     #[serde(default)]
+    pub shell_hold: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub single_instance: Option<bool>,
+    #[serde(default)]
     pub click: Option<String>,
     #[serde(default)]
     pub hold_click: Option<String>,
     #[serde(default)]
     pub rawkey: Option<String>,
+    #[serde(default, rename = "type")]
+    pub type_text: Option<String>,
+    #[serde(default)]
+    pub type_delay_ms: Option<u64>,
+    #[serde(default)]
+    pub toggle_enabled: Option<bool>,
+    #[serde(default)]
+    pub pause_ms: Option<u64>,
     #[serde(default)]
     pub repeat_delay_ms: Option<u64>,
     #[serde(default)]
@@ -1,6 +1,7 @@
 use ahash::AHashMap;
 use thiserror::Error;
 use super::combo::{parse_terms_with_delim, SequenceError, SequenceErrorKind};
+use crate::RuleOrigin;
 
 pub(crate) type SelectorResult<T> = Result<T, SelectorError>;
 
@@ -69,6 +70,42 @@ impl<'a> Selector<'a> {
         Ok(bundle_ids)
     }
 
+    /// Whether the selector names any group.
+    pub(crate) fn has_group(&self) -> bool {
+        self.0.iter().any(|token| matches!(token, Lexem::Group(_)))
+    }
+
+    /// How the selector reaches `bundle_id`, if it does. Naming the bundle
+    /// ID directly wins over a group that contains it.
+    pub(crate) fn origin_of(
+        &self,
+        source: &str,
+        bundle_id: &str,
+        groups: &AHashMap<String, Vec<Box<str>>>,
+    ) -> Option<RuleOrigin> {
+        let mut via_group = None;
+        for token in self.0.iter() {
+            match token {
+                Lexem::BundleId(id) if *id == bundle_id => {
+                    return Some(RuleOrigin::App(source.into()));
+                }
+                Lexem::Group(group) if via_group.is_none() => {
+                    let contains = groups
+                        .get(*group)
+                        .is_some_and(|ids| ids.iter().any(|id| &**id == bundle_id));
+                    if contains {
+                        via_group = Some(RuleOrigin::Group {
+                            selector: source.into(),
+                            group: (*group).into(),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+        via_group
+    }
+
     /// Parses the selector string and validates it. Returns a vector of tokens.
     pub(crate) fn parse(input: &'a str) -> SelectorResult<Self> {
         let terms = match parse_terms_with_delim(input, '|') {
@@ -16,6 +16,8 @@ fn build_profile_simple(button: Button, combo: KeyCombo) -> Profile {
         ButtonRule {
             action: ButtonAction::Keystroke(Arc::new(combo)),
             vibrate: None,
+            repeat_delay_ms: None,
+            repeat_interval_ms: None,
         },
     );
     app.buttons = buttons;
@@ -26,6 +28,7 @@ fn build_profile_simple(button: Button, combo: KeyCombo) -> Profile {
         blacklist: Default::default(),
         rules,
         shell: None,
+        hooks: Default::default(),
     }
 }
 
@@ -54,40 +57,20 @@ pub fn bench_button_path(c: &mut Criterion) {
                 id,
                 button,
                 padjutsud::app::ButtonPhase::Pressed,
-                |a| {
-                    match a {
-                        Effect::KeyPress(_)
-                        | Effect::Rumble { .. }
-                        | Effect::Shell(_)
-                        | Effect::Macros(_)
-                        | Effect::MouseMove { .. }
-                        | Effect::Scroll { .. }
-                        | Effect::KeyTap(_)
-                        | Effect::KeyRelease(_) => {
-                            sink_count += 1;
-                        }
-                    };
-                    black_box(());
+                std::time::Instant::now(),
+                |a: Effect| {
+                    black_box(a);
+                    sink_count += 1;
                 },
             );
             g.on_button_with(
                 id,
                 button,
                 padjutsud::app::ButtonPhase::Released,
-                |a| {
-                    match a {
-                        Effect::KeyPress(_)
-                        | Effect::Rumble { .. }
-                        | Effect::Shell(_)
-                        | Effect::Macros(_)
-                        | Effect::MouseMove { .. }
-                        | Effect::Scroll { .. }
-                        | Effect::KeyTap(_)
-                        | Effect::KeyRelease(_) => {
-                            sink_count += 1;
-                        }
-                    };
-                    black_box(());
+                std::time::Instant::now(),
+                |a: Effect| {
+                    black_box(a);
+                    sink_count += 1;
                 },
             );
             black_box(sink_count)
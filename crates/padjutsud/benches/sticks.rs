@@ -26,6 +26,7 @@ fn build_profile_arrows() -> Profile {
         blacklist: Default::default(),
         rules,
         shell: None,
+        hooks: Default::default(),
     }
 }
 
@@ -54,22 +55,9 @@ pub fn bench_sticks_arrows(c: &mut Criterion) {
                 g.on_axis_motion(id, CtrlAxis::LeftX, x);
                 g.on_axis_motion(id, CtrlAxis::LeftY, y);
                 let mut n = 0usize;
-                g.on_tick_with(|a| {
-                    {
-                        match a {
-                            Effect::KeyTap(_)
-                            | Effect::MouseMove { .. }
-                            | Effect::Scroll { .. }
-                            | Effect::KeyPress(_)
-                            | Effect::KeyRelease(_)
-                            | Effect::Rumble { .. }
-                            | Effect::Shell(_)
-                            | Effect::Macros(_) => {
-                                n += 1;
-                            }
-                        };
-                        black_box(())
-                    };
+                g.on_tick_with(std::time::Instant::now(), |a: Effect| {
+                    black_box(a);
+                    n += 1;
                 });
                 black_box(n);
             }
@@ -2,6 +2,7 @@
 pub use nsworkspace::{Event as ActivityEvent, Monitor, NotificationListener};
 
 #[cfg(not(target_os = "macos"))]
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum ActivityEvent {
     DidActivateApplication(String),
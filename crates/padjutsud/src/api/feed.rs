@@ -0,0 +1,281 @@
+//! Live event feed for observers.
+//!
+//! The event loop publishes what it handles and what it emits into an
+//! [`EventFeed`]; every subscribed connection gets the events its
+//! [`FeedFilter`] lets through. Publishing never blocks the loop: a
+//! subscriber that falls behind loses events, one that hung up is dropped.
+
+use std::fmt;
+
+use bitcode::{Decode, Encode};
+use crossbeam_channel::{Sender, TrySendError};
+use padjutsu_gamepad::{Axis, ControllerEvent, ControllerId};
+use padjutsu_workspace::{button_name, ProfileEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::activity::ActivityEvent;
+use crate::app::Effect;
+use crate::domain::DomainEvent;
+
+/// Events a subscriber may buffer before new ones are dropped.
+pub const FEED_BACKLOG: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedKind {
+    Controller,
+    App,
+    Profile,
+    Effects,
+}
+
+/// Which events a subscriber wants.
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode, Deserialize)]
+pub struct FeedFilter {
+    /// Only events of this controller. Events that belong to no controller,
+    /// like app switches, always pass.
+    #[serde(default)]
+    pub controller: Option<ControllerId>,
+    /// Only these kinds; empty means all.
+    #[serde(default)]
+    pub kinds: Vec<FeedKind>,
+}
+
+impl FeedFilter {
+    pub fn matches(&self, event: &FeedEvent) -> bool {
+        let kind_ok = self.kinds.is_empty() || self.kinds.contains(&event.kind());
+        let controller_ok = match (self.controller, event.controller()) {
+            (Some(wanted), Some(got)) => wanted == got,
+            _ => true,
+        };
+        kind_ok && controller_ok
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FeedEvent {
+    Controller {
+        controller: ControllerId,
+        event: String,
+    },
+    App {
+        app: String,
+    },
+    Profile {
+        event: String,
+    },
+    Effect {
+        /// The controller whose input caused the effect; `None` for
+        /// timer-driven output such as repeats and stick motion.
+        controller: Option<ControllerId>,
+        effect: String,
+    },
+}
+
+const AXES: [Axis; 6] = [
+    Axis::LeftX,
+    Axis::LeftY,
+    Axis::RightX,
+    Axis::RightY,
+    Axis::LeftTrigger,
+    Axis::RightTrigger,
+];
+
+fn axis_name(axis: Axis) -> &'static str {
+    match axis {
+        Axis::LeftX => "left_x",
+        Axis::LeftY => "left_y",
+        Axis::RightX => "right_x",
+        Axis::RightY => "right_y",
+        Axis::LeftTrigger => "left_trigger",
+        Axis::RightTrigger => "right_trigger",
+    }
+}
+
+/// The axis called `name` by [`axis_name`].
+pub(crate) fn parse_axis(name: &str) -> Option<Axis> {
+    AXES.into_iter().find(|axis| axis_name(*axis) == name)
+}
+
+impl FeedEvent {
+    /// The feed event for an incoming domain event, if observers see it.
+    pub fn from_domain(event: &DomainEvent) -> Option<Self> {
+        match event {
+            DomainEvent::Controller(event) => {
+                let (controller, event) = match event {
+                    ControllerEvent::Connected(info) => {
+                        (info.id, format!("connected {:?}", info.name))
+                    }
+                    ControllerEvent::Disconnected(id) => {
+                        (*id, "disconnected".to_string())
+                    }
+                    ControllerEvent::ButtonPressed { id, button } => {
+                        (*id, format!("pressed {}", button_name(*button)))
+                    }
+                    ControllerEvent::ButtonReleased { id, button } => {
+                        (*id, format!("released {}", button_name(*button)))
+                    }
+                    ControllerEvent::AxisMotion { id, axis, value } => {
+                        (*id, format!("axis {} {value:.3}", axis_name(*axis)))
+                    }
+                };
+                Some(Self::Controller { controller, event })
+            }
+            DomainEvent::Activity(ActivityEvent::DidActivateApplication(app)) => {
+                Some(Self::App { app: app.clone() })
+            }
+            DomainEvent::Profile(event) => {
+                let event = match event {
+                    ProfileEvent::Changed(_) => "loaded".to_string(),
+                    ProfileEvent::Removed => "removed".to_string(),
+                    ProfileEvent::Error(e) => format!("error: {e}"),
+                };
+                Some(Self::Profile { event })
+            }
+            DomainEvent::Api(_) | DomainEvent::Timer(_) | DomainEvent::System(_) => {
+                None
+            }
+        }
+    }
+
+    pub fn from_effect(effect: &Effect, controller: Option<ControllerId>) -> Self {
+        Self::Effect {
+            controller,
+            effect: effect.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> FeedKind {
+        match self {
+            Self::Controller { .. } => FeedKind::Controller,
+            Self::App { .. } => FeedKind::App,
+            Self::Profile { .. } => FeedKind::Profile,
+            Self::Effect { .. } => FeedKind::Effects,
+        }
+    }
+
+    pub fn controller(&self) -> Option<ControllerId> {
+        match self {
+            Self::Controller { controller, .. } => Some(*controller),
+            Self::Effect { controller, .. } => *controller,
+            Self::App { .. } | Self::Profile { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for FeedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Controller { controller, event } => {
+                write!(f, "controller {controller}: {event}")
+            }
+            Self::App { app } => write!(f, "app: {app}"),
+            Self::Profile { event } => write!(f, "profile: {event}"),
+            Self::Effect {
+                controller: Some(controller),
+                effect,
+            } => write!(f, "effect: {effect} (controller {controller})"),
+            Self::Effect {
+                controller: None,
+                effect,
+            } => write!(f, "effect: {effect}"),
+        }
+    }
+}
+
+/// A connection waiting for feed events.
+pub struct Subscriber {
+    pub filter: FeedFilter,
+    pub events: Sender<FeedEvent>,
+}
+
+#[derive(Default)]
+pub struct EventFeed {
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventFeed {
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Whether anyone is listening; lets callers skip building events.
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn publish(&mut self, event: FeedEvent) {
+        self.subscribers.retain(|subscriber| {
+            if !subscriber.filter.matches(&event) {
+                return true;
+            }
+            match subscriber.events.try_send(event.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::bounded;
+
+    use super::*;
+
+    fn pressed(controller: ControllerId) -> FeedEvent {
+        FeedEvent::Controller {
+            controller,
+            event: "pressed a".into(),
+        }
+    }
+
+    #[test]
+    fn publish_filters_and_drops_hung_up_subscribers() {
+        let mut feed = EventFeed::default();
+        let (effects_tx, effects) = bounded(8);
+        feed.subscribe(Subscriber {
+            filter: FeedFilter {
+                controller: Some(1),
+                kinds: vec![FeedKind::Effects],
+            },
+            events: effects_tx,
+        });
+        let (all_tx, all) = bounded(8);
+        feed.subscribe(Subscriber {
+            filter: FeedFilter::default(),
+            events: all_tx,
+        });
+
+        let tap = FeedEvent::Effect {
+            controller: Some(1),
+            effect: "tap enter".into(),
+        };
+        feed.publish(pressed(1));
+        feed.publish(FeedEvent::Effect {
+            controller: Some(2),
+            effect: "tap esc".into(),
+        });
+        feed.publish(tap.clone());
+        assert_eq!(effects.try_iter().collect::<Vec<_>>(), [tap]);
+        assert_eq!(all.try_iter().count(), 3);
+
+        drop(all);
+        feed.publish(pressed(1));
+        assert_eq!(feed.subscribers.len(), 1);
+        drop(effects);
+        let rumble = Effect::Rumble { id: 1, ms: 10 };
+        feed.publish(FeedEvent::from_effect(&rumble, None));
+        assert!(feed.is_empty());
+    }
+
+    #[test]
+    fn json_lines_are_tagged_by_kind() {
+        let line = serde_json::to_string(&pressed(3)).unwrap();
+        assert_eq!(
+            line,
+            r#"{"kind":"controller","controller":3,"event":"pressed a"}"#
+        );
+    }
+}
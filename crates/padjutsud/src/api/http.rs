@@ -0,0 +1,704 @@
+//! Opt-in HTTP endpoint on 127.0.0.1 for dashboards and Stream Deck style
+//! plugins.
+//!
+//! - `GET /status` answers like the `status` command.
+//! - `POST /command` takes one JSON command, as in [`super::json_lines`].
+//! - `GET /events` upgrades to a WebSocket carrying `{"event": ...}` text
+//!   messages; `?controller=0&kind=app&kind=profile` narrows the feed.
+//!
+//! Bodies are the same `{"ok": ...}` / `{"error": ...}` objects the JSON
+//! lines protocol writes. Browsers may only call in from pages served on
+//! localhost: requests with any other `Origin` are refused, and so are
+//! requests whose `Host` isn't a loopback name, against DNS rebinding.
+//!
+//! Any local process can reach the port, so every request must also carry
+//! the token the daemon writes to [`TOKEN_FILE_NAME`] next to its control
+//! socket, readable by its owner only: as `Authorization: Bearer <token>`,
+//! or as `?token=<token>` where headers can't be set, as for a browser's
+//! WebSocket.
+//!
+//! Each connection carries one request and is closed after the response,
+//! except for an event stream, which keeps its connection.
+
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use colored::Colorize;
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use super::feed::FEED_BACKLOG;
+use super::json_lines::JsonLine;
+use super::protocol::{dispatch, MAX_FRAME_LEN};
+use super::{
+    ApiReply, ApiRequest, ApiResult, Command, FeedEvent, FeedFilter, FeedKind,
+    RemoteError,
+};
+use crate::{print_debug, print_error, print_info};
+
+/// File holding the token HTTP clients must present.
+pub const TOKEN_FILE_NAME: &str = "http-token";
+
+/// Upper bound for a request line and its headers.
+const MAX_HEAD_LEN: usize = 16 * 1024;
+
+/// How long a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an event stream waits for an event before it looks at what
+/// the client sent.
+const EVENT_POLL: Duration = Duration::from_millis(100);
+
+pub struct HttpServer {
+    listener: TcpListener,
+    token: String,
+}
+
+impl HttpServer {
+    /// Bind `127.0.0.1:port`; port 0 picks a free one. Only requests that
+    /// present `token` are served.
+    pub fn bind(port: u16, token: String) -> ApiResult<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        Ok(Self { listener, token })
+    }
+
+    /// Make a new random token and write it to [`TOKEN_FILE_NAME`] in `dir`,
+    /// readable and writable by the current user only.
+    pub fn write_token(dir: &Path) -> ApiResult<String> {
+        let mut bytes = [0u8; 16];
+        File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+        let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        let path = dir.join(TOKEN_FILE_NAME);
+        // A file left by someone else could keep looser permissions.
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        file.write_all(token.as_bytes())?;
+        Ok(token)
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// Serve requests on a background thread, forwarding commands to `tx`.
+    pub fn listen_events(self, tx: Sender<ApiRequest>) -> ApiResult<JoinHandle<()>> {
+        if let Some(addr) = self.local_addr() {
+            print_info!("http api listening at http://{addr}");
+        }
+        let handle = thread::Builder::new()
+            .name("padjutsud-http-api".into())
+            .spawn(move || {
+                for stream in self.listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            print_error!("http api accept error: {e}");
+                            continue;
+                        }
+                    };
+                    let tx = tx.clone();
+                    let token = self.token.clone();
+                    let spawned = thread::Builder::new()
+                        .name("padjutsud-http-client".into())
+                        .spawn(move || handle_connection(stream, &token, &tx));
+                    if let Err(e) = spawned {
+                        print_error!("failed to spawn http client thread: {e}");
+                    }
+                }
+            })?;
+        Ok(handle)
+    }
+}
+
+/// The parts of an HTTP request the API looks at.
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Response {
+    code: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn empty(code: u16) -> Self {
+        Self {
+            code,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_owned()));
+        self
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let reason = StatusCode::from_u16(self.code)
+            .ok()
+            .and_then(|code| code.canonical_reason())
+            .unwrap_or_default();
+        let mut head = format!("HTTP/1.1 {} {reason}\r\n", self.code);
+        for (name, value) in &self.headers {
+            let _ = write!(head, "{name}: {value}\r\n");
+        }
+        // Interim answers and a switch of protocol keep the connection.
+        if self.code >= 200 {
+            let _ = write!(
+                head,
+                "Content-Length: {}\r\nConnection: close\r\n",
+                self.body.len()
+            );
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes())?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+/// Read one request from `stream`, or the status code to refuse it with.
+fn read_request<S: Read + Write>(stream: &mut S) -> Result<Request, u16> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (mut request, head_len) = loop {
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+        match parsed.parse(&buffer) {
+            Ok(httparse::Status::Complete(head_len)) => {
+                let target = parsed.path.unwrap_or_default();
+                let (path, query) = target.split_once('?').unwrap_or((target, ""));
+                let request = Request {
+                    method: parsed.method.unwrap_or_default().to_owned(),
+                    path: path.to_owned(),
+                    query: query.to_owned(),
+                    headers: parsed
+                        .headers
+                        .iter()
+                        .map(|h| {
+                            let value = String::from_utf8_lossy(h.value);
+                            (h.name.to_owned(), value.into_owned())
+                        })
+                        .collect(),
+                    body: Vec::new(),
+                };
+                break (request, head_len);
+            }
+            Ok(httparse::Status::Partial) => {}
+            Err(httparse::Error::TooManyHeaders) => return Err(431),
+            Err(_) => return Err(400),
+        }
+        if buffer.len() > MAX_HEAD_LEN {
+            return Err(431);
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) => return Err(400),
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => return Err(408),
+        }
+    };
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(411);
+    }
+    let length = match request.header("Content-Length") {
+        Some(length) => length.trim().parse().map_err(|_| 400u16)?,
+        None => 0,
+    };
+    if length > MAX_FRAME_LEN {
+        return Err(413);
+    }
+    let mut body = buffer.split_off(head_len);
+    if body.len() < length {
+        if request
+            .header("Expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+        {
+            Response::empty(100).write_to(stream).map_err(|_| 400u16)?;
+        }
+        stream
+            .take((length - body.len()) as u64)
+            .read_to_end(&mut body)
+            .map_err(|_| 408u16)?;
+    }
+    if body.len() < length {
+        return Err(400);
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(request)
+}
+
+/// Whether `authority` (`host[:port]`) names this machine's loopback.
+fn is_loopback(authority: &str) -> bool {
+    let host = match authority.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => authority,
+    };
+    matches!(host, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+fn allowed_origin(origin: &str) -> bool {
+    origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+        .is_some_and(is_loopback)
+}
+
+/// Compare in constant time, so response times don't leak the token.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Whether `request` carries `token` in its `Authorization` header or in
+/// its query.
+fn authorized(request: &Request, token: &str) -> bool {
+    let bearer = request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let param = request
+        .query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    bearer
+        .or(param)
+        .is_some_and(|given| same_token(given, token))
+}
+
+fn status_code(error: &RemoteError) -> u16 {
+    match error {
+        RemoteError::UnknownController(_) | RemoteError::NoControllers => 404,
+        RemoteError::Unavailable => 503,
+        RemoteError::Timeout => 504,
+        RemoteError::ProfileInvalid(_) => 422,
+        RemoteError::UnsupportedVersion { .. }
+        | RemoteError::NotNegotiated(_)
+        | RemoteError::Malformed(_) => 400,
+    }
+}
+
+fn json_response(result: &ApiReply) -> Response {
+    let code = match result {
+        Ok(_) => 200,
+        Err(error) => status_code(error),
+    };
+    Response {
+        code,
+        headers: vec![("Content-Type", "application/json".into())],
+        body: JsonLine::reply(None, result).encode().into_bytes(),
+    }
+}
+
+fn error_response(error: RemoteError) -> Response {
+    json_response(&Err(error))
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str, tx: &Sender<ApiRequest>) {
+    if let Err(e) = stream.set_read_timeout(Some(REQUEST_TIMEOUT)) {
+        print_error!("failed to set http client timeout: {e}");
+        return;
+    }
+    match read_request(&mut stream) {
+        Ok(request) => handle_request(stream, request, token, tx),
+        Err(code) => {
+            let _ = Response::empty(code).write_to(&mut stream);
+        }
+    }
+}
+
+fn handle_request(
+    mut stream: TcpStream,
+    request: Request,
+    token: &str,
+    tx: &Sender<ApiRequest>,
+) {
+    let host_ok = request.header("Host").is_some_and(is_loopback);
+    let origin = request.header("Origin").map(str::to_owned);
+    if !host_ok || origin.as_deref().is_some_and(|o| !allowed_origin(o)) {
+        let _ = Response::empty(403).write_to(&mut stream);
+        return;
+    }
+    print_debug!("http api request: {} {}", request.method, request.path);
+
+    // Browsers send CORS preflights without credentials.
+    let preflight = request.method == "OPTIONS";
+    let response = if !preflight && !authorized(&request, token) {
+        Response::empty(401).with_header("WWW-Authenticate", "Bearer")
+    } else {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/events") => return serve_events(stream, &request, tx),
+            ("OPTIONS", _) => Response::empty(204)
+                .with_header("Access-Control-Allow-Methods", "GET, POST")
+                .with_header(
+                    "Access-Control-Allow-Headers",
+                    "Authorization, Content-Type",
+                ),
+            ("GET", "/status") => {
+                json_response(&dispatch(tx, Command::Status, None))
+            }
+            ("POST", "/command") => match parse_command(&request.body) {
+                Ok(Command::Subscribe(_)) => error_response(RemoteError::Malformed(
+                    "subscribe over GET /events".into(),
+                )),
+                Ok(command) => json_response(&dispatch(tx, command, None)),
+                Err(error) => error_response(error),
+            },
+            _ => Response::empty(404),
+        }
+    };
+    let response = match origin {
+        Some(origin) => response.with_header("Access-Control-Allow-Origin", &origin),
+        None => response,
+    };
+    let _ = response.write_to(&mut stream);
+}
+
+fn parse_command(body: &[u8]) -> Result<Command, RemoteError> {
+    serde_json::from_slice(body).map_err(|e| RemoteError::Malformed(e.to_string()))
+}
+
+fn parse_filter(query: &str) -> Result<FeedFilter, RemoteError> {
+    let mut filter = FeedFilter::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "controller" => {
+                let id = value.parse().map_err(|_| {
+                    RemoteError::Malformed(format!("bad controller `{value}`"))
+                })?;
+                filter.controller = Some(id);
+            }
+            "kind" => filter.kinds.push(match value {
+                "controller" => FeedKind::Controller,
+                "app" => FeedKind::App,
+                "profile" => FeedKind::Profile,
+                "effects" => FeedKind::Effects,
+                _ => {
+                    return Err(RemoteError::Malformed(format!(
+                        "unknown kind `{value}`"
+                    )))
+                }
+            }),
+            "token" => {}
+            _ => {
+                return Err(RemoteError::Malformed(format!(
+                    "unknown parameter `{key}`"
+                )))
+            }
+        }
+    }
+    Ok(filter)
+}
+
+/// Upgrade `stream` to a WebSocket and forward feed events until either
+/// side goes away.
+fn serve_events(mut stream: TcpStream, request: &Request, tx: &Sender<ApiRequest>) {
+    let Some(key) = request.header("Sec-WebSocket-Key") else {
+        let response = error_response(RemoteError::Malformed(
+            "expected a websocket upgrade".into(),
+        ));
+        let _ = response.write_to(&mut stream);
+        return;
+    };
+    let filter = match parse_filter(&request.query) {
+        Ok(filter) => filter,
+        Err(error) => {
+            let _ = error_response(error).write_to(&mut stream);
+            return;
+        }
+    };
+    let (events_tx, events) = bounded(FEED_BACKLOG);
+    if let Err(error) = dispatch(tx, Command::Subscribe(filter), Some(events_tx)) {
+        let _ = error_response(error).write_to(&mut stream);
+        return;
+    }
+    let response = Response::empty(101)
+        .with_header("Upgrade", "websocket")
+        .with_header("Connection", "Upgrade")
+        .with_header("Sec-WebSocket-Accept", &derive_accept_key(key.as_bytes()));
+    // Reads give up almost at once, so one thread can both forward events
+    // and take the client's pings and close frame.
+    let ready = response
+        .write_to(&mut stream)
+        .and_then(|()| stream.set_read_timeout(Some(Duration::from_millis(1))));
+    if let Err(e) = ready {
+        print_debug!("http event stream not started: {e}");
+        return;
+    }
+    let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    forward_events(socket, events);
+}
+
+/// Send events until the client closes the stream or the event loop goes
+/// away.
+fn forward_events(mut socket: WebSocket<TcpStream>, events: Receiver<FeedEvent>) {
+    let error = loop {
+        match events.recv_timeout(EVENT_POLL) {
+            Ok(event) => {
+                let line = JsonLine::Event { event: &event }.encode();
+                if let Err(e) = socket.send(Message::text(line)) {
+                    break e;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = socket.close(None);
+                return;
+            }
+        }
+        // Answers to pings and to a close frame are queued by the read and
+        // sent by the flush; after a close the next read ends the stream.
+        match socket.read() {
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => break e,
+        }
+        if let Err(e) = socket.flush() {
+            break e;
+        }
+    };
+    if !matches!(error, tungstenite::Error::ConnectionClosed) {
+        print_debug!("http event stream closed: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+
+    use crossbeam_channel::unbounded;
+    use serde_json::{json, Value};
+    use tungstenite::stream::MaybeTlsStream;
+
+    use super::*;
+    use crate::api::Response as ApiResponse;
+
+    /// Answer rumble and subscribe from a fake event loop; subscriptions
+    /// get one app switch.
+    fn fake_loop() -> Sender<ApiRequest> {
+        let (tx, rx) = unbounded::<ApiRequest>();
+        thread::spawn(move || {
+            for request in rx {
+                let reply = match request.command {
+                    Command::Rumble { id, .. } => Ok(ApiResponse::Rumbled {
+                        controllers: vec![id.unwrap_or(0)],
+                    }),
+                    Command::Subscribe(filter) => {
+                        assert_eq!(filter.kinds, [FeedKind::App]);
+                        Ok(ApiResponse::Subscribed)
+                    }
+                    _ => Err(RemoteError::NoControllers),
+                };
+                let _ = request.reply.send(reply);
+                if let Some(events) = &request.events {
+                    let _ = events.send(FeedEvent::App { app: "one".into() });
+                }
+            }
+        });
+        tx
+    }
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn start() -> SocketAddr {
+        let server = HttpServer::bind(0, TOKEN.into()).unwrap();
+        let addr = server.local_addr().unwrap();
+        server.listen_events(fake_loop()).unwrap();
+        addr
+    }
+
+    /// Send a raw HTTP/1.1 request with the token; returns the status code
+    /// and body.
+    fn call(addr: SocketAddr, head: &str, body: &str) -> (u16, String) {
+        let head = format!("{head}\r\nAuthorization: Bearer {TOKEN}");
+        call_unauthorized(addr, &head, body)
+    }
+
+    fn call_unauthorized(addr: SocketAddr, head: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{head}\r\nHost: {addr}\r\nConnection: close\r\n\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    fn json(body: &str) -> Value {
+        serde_json::from_str(body).unwrap()
+    }
+
+    /// Open an event stream for app switches.
+    fn subscribe(addr: SocketAddr) -> WebSocket<MaybeTlsStream<TcpStream>> {
+        let url = format!("ws://{addr}/events?kind=app&token={TOKEN}");
+        let (socket, _) = tungstenite::connect(url).unwrap();
+        if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+        }
+        socket
+    }
+
+    #[test]
+    fn answers_status_and_commands() {
+        let addr = start();
+        let (status, body) = call(addr, "GET /status HTTP/1.1", "");
+        assert_eq!(status, 404);
+        assert_eq!(json(&body)["error"], json!("no_controllers"));
+
+        let (status, body) = call(
+            addr,
+            "POST /command HTTP/1.1",
+            r#"{"rumble": {"id": 2, "ms": 100}}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            json(&body),
+            json!({"ok": {"rumbled": {"controllers": [2]}}})
+        );
+
+        let (status, body) = call(addr, "POST /command HTTP/1.1", "rumble");
+        assert_eq!(status, 400);
+        assert!(json(&body)["error"]["malformed"].is_string());
+        assert_eq!(call(addr, "GET /nowhere HTTP/1.1", "").0, 404);
+    }
+
+    #[test]
+    fn refuses_foreign_origins() {
+        let addr = start();
+        let head = "POST /command HTTP/1.1\r\nOrigin: https://example.com";
+        let (status, _) = call(addr, head, r#"{"rumble": {"ms": 100}}"#);
+        assert_eq!(status, 403);
+        let head = "POST /command HTTP/1.1\r\nOrigin: http://localhost:8080";
+        let (status, _) = call(addr, head, r#"{"rumble": {"ms": 100}}"#);
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn refuses_requests_without_the_token() {
+        let addr = start();
+        let body = r#"{"rumble": {"ms": 100}}"#;
+        let (status, _) = call_unauthorized(addr, "POST /command HTTP/1.1", body);
+        assert_eq!(status, 401);
+        let head = "POST /command HTTP/1.1\r\nAuthorization: Bearer 0123";
+        assert_eq!(call_unauthorized(addr, head, body).0, 401);
+        let head = format!("POST /command?token={TOKEN} HTTP/1.1");
+        assert_eq!(call_unauthorized(addr, &head, body).0, 200);
+        let (status, _) = call_unauthorized(addr, "OPTIONS /command HTTP/1.1", "");
+        assert_eq!(status, 204);
+    }
+
+    #[test]
+    fn refuses_oversized_and_chunked_bodies() {
+        let addr = start();
+        let head = format!(
+            "POST /command HTTP/1.1\r\nAuthorization: Bearer {TOKEN}\r\n\
+             Host: {addr}\r\nContent-Length: {}\r\n\r\n",
+            MAX_FRAME_LEN + 1
+        );
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(head.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+
+        let head = "POST /command HTTP/1.1\r\nTransfer-Encoding: chunked";
+        assert_eq!(call(addr, head, "").0, 411);
+    }
+
+    #[test]
+    fn writes_the_token_for_the_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir()
+            .join(format!("padjutsud-http-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let token = HttpServer::write_token(&dir).unwrap();
+        let path = dir.join(TOKEN_FILE_NAME);
+        assert_eq!(fs::read_to_string(&path).unwrap(), token);
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_ne!(HttpServer::write_token(&dir).unwrap(), token);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn streams_events_over_a_websocket() {
+        let mut socket = subscribe(start());
+        let message = socket.read().unwrap();
+        assert_eq!(
+            json(message.to_text().unwrap()),
+            json!({"event": {"kind": "app", "app": "one"}})
+        );
+    }
+
+    #[test]
+    fn answers_pings_and_close_frames() {
+        let mut socket = subscribe(start());
+        socket.send(Message::Ping(vec![7].into())).unwrap();
+        assert!(socket.read().unwrap().is_text());
+        assert_eq!(socket.read().unwrap(), Message::Pong(vec![7].into()));
+
+        socket.close(None).unwrap();
+        loop {
+            match socket.read() {
+                Ok(message) => assert!(message.is_close(), "{message:?}"),
+                Err(tungstenite::Error::ConnectionClosed) => break,
+                Err(e) => panic!("close not answered: {e}"),
+            }
+        }
+    }
+}
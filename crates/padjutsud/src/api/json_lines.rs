@@ -0,0 +1,341 @@
+//! Newline-delimited JSON flavour of the control protocol, for scripts.
+//!
+//! Every line a client sends is one request, e.g.
+//! `{"id": 1, "command": {"rumble": {"ms": 200}}}` or `{"command": "status"}`;
+//! the `id` is optional and echoed back. Every request gets one line back,
+//! either `{"id": 1, "ok": ...}` or `{"id": 1, "error": ..., "message": ...}`,
+//! and a successful `subscribe` is followed by `{"event": ...}` lines until
+//! the client hangs up.
+//!
+//! The handshake is optional so that `socat - UNIX-CONNECT:api.sock` is a
+//! client: a connection that starts straight with a request is granted all
+//! of the daemon's capabilities. One whose first line is
+//! `{"hello": {"version": 1, "capabilities": ["status"]}}` is held to the
+//! capabilities it names and gets `{"welcome": ...}` back, like the bitcode
+//! [`Hello`](super::protocol::Hello).
+
+use std::io::{self, Read, Write};
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+
+use super::feed::FEED_BACKLOG;
+use super::protocol::{
+    dispatch, negotiate, Hello, CAPABILITIES, MAX_FRAME_LEN, PROTOCOL_VERSION,
+};
+use super::{
+    ApiError, ApiReply, ApiRequest, ApiResult, Command, FeedEvent, RemoteError,
+    Response,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonRequest {
+    #[serde(default)]
+    id: Option<u64>,
+    command: Command,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonHello {
+    hello: Hello,
+}
+
+/// One line the daemon writes; the HTTP endpoint sends the same objects.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(super) enum JsonLine<'a> {
+    Ok {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        ok: &'a Response,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        error: &'a RemoteError,
+        message: String,
+    },
+    Event {
+        event: &'a FeedEvent,
+    },
+    Welcome {
+        welcome: Welcome<'a>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct Welcome<'a> {
+    version: u16,
+    capabilities: &'a [String],
+}
+
+impl<'a> JsonLine<'a> {
+    pub(super) fn reply(id: Option<u64>, result: &'a ApiReply) -> Self {
+        match result {
+            Ok(ok) => Self::Ok { id, ok },
+            Err(error) => Self::Error {
+                id,
+                error,
+                message: error.to_string(),
+            },
+        }
+    }
+
+    pub(super) fn encode(&self) -> String {
+        // Only plain data and string keys; serializing can't fail.
+        serde_json::to_string(self).expect("serializable json line")
+    }
+}
+
+fn write_line<W: Write>(writer: &mut W, line: &JsonLine<'_>) -> io::Result<()> {
+    let mut encoded = line.encode();
+    encoded.push('\n');
+    writer.write_all(encoded.as_bytes())?;
+    writer.flush()
+}
+
+/// Splits a stream into lines, reading no further than it has to.
+struct LineReader {
+    buffer: Vec<u8>,
+}
+
+impl LineReader {
+    /// The next line without its newline; `Ok(None)` once the peer closed
+    /// the connection. A last line needs no newline.
+    fn next_line<R: Read>(&mut self, reader: &mut R) -> ApiResult<Option<Vec<u8>>> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+                let rest = self.buffer.split_off(end + 1);
+                let mut line = std::mem::replace(&mut self.buffer, rest);
+                line.pop();
+                return Ok(Some(line));
+            }
+            if self.buffer.len() > MAX_FRAME_LEN {
+                return Err(ApiError::Protocol("line too long".into()));
+            }
+            let read = match reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if read == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buffer)));
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+/// Serve a JSON lines connection whose first byte, `first`, was already
+/// read, until it closes.
+pub(crate) fn serve<S: Read + Write>(
+    stream: &mut S,
+    tx: &Sender<ApiRequest>,
+    first: u8,
+) -> ApiResult<()> {
+    let mut lines = LineReader {
+        buffer: vec![first],
+    };
+    let mut granted: Vec<String> =
+        CAPABILITIES.iter().map(|c| c.to_string()).collect();
+    let mut first_line = true;
+    while let Some(line) = lines.next_line(stream)? {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        if std::mem::take(&mut first_line) {
+            if let Ok(JsonHello { hello }) = serde_json::from_slice(&line) {
+                granted = match negotiate(hello) {
+                    Ok(granted) => granted,
+                    Err(error) => {
+                        write_line(stream, &JsonLine::reply(None, &Err(error)))?;
+                        return Ok(());
+                    }
+                };
+                let welcome = Welcome {
+                    version: PROTOCOL_VERSION,
+                    capabilities: &granted,
+                };
+                write_line(stream, &JsonLine::Welcome { welcome })?;
+                continue;
+            }
+        }
+        let JsonRequest { id, command } = match serde_json::from_slice(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let result = Err(RemoteError::Malformed(e.to_string()));
+                write_line(stream, &JsonLine::reply(None, &result))?;
+                continue;
+            }
+        };
+        let capability = command.capability();
+        if !granted.iter().any(|c| c == capability) {
+            let result = Err(RemoteError::NotNegotiated(capability.into()));
+            write_line(stream, &JsonLine::reply(id, &result))?;
+            continue;
+        }
+        if let Command::Subscribe(_) = command {
+            let (events_tx, events) = bounded(FEED_BACKLOG);
+            let result = dispatch(tx, command, Some(events_tx));
+            write_line(stream, &JsonLine::reply(id, &result))?;
+            if result.is_ok() {
+                return stream_events(stream, events);
+            }
+            continue;
+        }
+        let result = dispatch(tx, command, None);
+        write_line(stream, &JsonLine::reply(id, &result))?;
+    }
+    Ok(())
+}
+
+fn stream_events<S: Write>(
+    stream: &mut S,
+    events: Receiver<FeedEvent>,
+) -> ApiResult<()> {
+    for event in events {
+        match write_line(stream, &JsonLine::Event { event: &event }) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    use crossbeam_channel::unbounded;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Answer every request from a fake event loop; subscriptions get one
+    /// app switch.
+    fn fake_loop() -> Sender<ApiRequest> {
+        let (tx, rx) = unbounded::<ApiRequest>();
+        thread::spawn(move || {
+            for request in rx {
+                let reply = match request.command {
+                    Command::Rumble { id: Some(7), .. } => {
+                        Err(RemoteError::UnknownController(7))
+                    }
+                    Command::Rumble { id, .. } => Ok(Response::Rumbled {
+                        controllers: vec![id.unwrap_or(0)],
+                    }),
+                    Command::Subscribe(_) => Ok(Response::Subscribed),
+                    _ => Err(RemoteError::Unavailable),
+                };
+                let _ = request.reply.send(reply);
+                if let Some(events) = &request.events {
+                    let _ = events.send(FeedEvent::App { app: "one".into() });
+                }
+            }
+        });
+        tx
+    }
+
+    /// Connect through the negotiating entry point, like `socat` would.
+    fn connect() -> (UnixStream, BufReader<UnixStream>) {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let tx = fake_loop();
+        thread::spawn(move || crate::api::protocol::serve(&mut server, &tx));
+        let reader = BufReader::new(client.try_clone().unwrap());
+        (client, reader)
+    }
+
+    fn read_json(reader: &mut BufReader<UnixStream>) -> Value {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn answers_json_lines_with_ids() {
+        let (mut client, mut reader) = connect();
+        client
+            .write_all(
+                b"{\"id\": 1, \"command\": {\"rumble\": {\"ms\": 200}}}\n\n\
+                  {\"command\": {\"rumble\": {\"id\": 7, \"ms\": 200}}}\n\
+                  not json\n",
+            )
+            .unwrap();
+        assert_eq!(
+            read_json(&mut reader),
+            json!({"id": 1, "ok": {"rumbled": {"controllers": [0]}}})
+        );
+        assert_eq!(
+            read_json(&mut reader),
+            json!({
+                "error": {"unknown_controller": 7},
+                "message": "no controller with id 7",
+            })
+        );
+        let error = read_json(&mut reader);
+        assert!(error["error"]["malformed"].is_string(), "{error}");
+
+        // A last line without a newline still counts.
+        client
+            .write_all(br#"{"id": 2, "command": "status"}"#)
+            .unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        assert_eq!(read_json(&mut reader)["error"], json!("unavailable"));
+    }
+
+    #[test]
+    fn subscription_streams_event_lines() {
+        let (mut client, mut reader) = connect();
+        client
+            .write_all(b"{\"command\": {\"subscribe\": {\"kinds\": [\"app\"]}}}\n")
+            .unwrap();
+        assert_eq!(read_json(&mut reader), json!({"ok": "subscribed"}));
+        assert_eq!(
+            read_json(&mut reader),
+            json!({"event": {"kind": "app", "app": "one"}})
+        );
+    }
+
+    #[test]
+    fn hello_line_narrows_capabilities() {
+        let (mut client, mut reader) = connect();
+        client
+            .write_all(
+                b"{\"hello\": {\"version\": 1, \"capabilities\": [\"rumble\", \"x\"]}}\n\
+                  {\"id\": 3, \"command\": \"status\"}\n",
+            )
+            .unwrap();
+        assert_eq!(
+            read_json(&mut reader),
+            json!({"welcome": {"version": 1, "capabilities": ["rumble"]}})
+        );
+        let refused = read_json(&mut reader);
+        assert_eq!(refused["id"], json!(3));
+        assert_eq!(refused["error"], json!({"not_negotiated": "status"}));
+    }
+
+    #[test]
+    fn hello_line_with_other_version_is_refused() {
+        let (mut client, mut reader) = connect();
+        client
+            .write_all(b"{\"hello\": {\"version\": 9, \"capabilities\": []}}\n")
+            .unwrap();
+        let refused = read_json(&mut reader);
+        assert_eq!(
+            refused["error"],
+            json!({"unsupported_version": {"requested": 9, "supported": 1}})
+        );
+        let mut rest = String::new();
+        reader.read_line(&mut rest).unwrap();
+        assert_eq!(rest, "");
+    }
+}
@@ -1,34 +1,175 @@
+mod feed;
+mod http;
+mod json_lines;
+mod protocol;
+mod status;
 mod unix_sock;
 
+use std::io::{Read, Write};
 use std::thread::JoinHandle;
 
-pub use unix_sock::{UnixSocket};
+pub(crate) use feed::parse_axis;
+pub use feed::{EventFeed, FeedEvent, FeedFilter, FeedKind, Subscriber};
+pub use http::HttpServer;
+pub use protocol::Client;
+pub use status::{
+    ControllerStatus, HeldStatus, OutputStatus, RepeatStatus, StatusReport,
+    StickStatus,
+};
+pub use unix_sock::UnixSocket;
 
 use bitcode::{Decode, Encode};
 use crossbeam_channel::Sender;
 use padjutsu_gamepad::ControllerId;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Error type for api operations.
 #[derive(Error, Debug)]
 pub enum ApiError {
-    #[error("failed to send event")]
+    #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    #[error("{0}")]
+    Remote(RemoteError),
 }
 
 /// Convenient result alias for api operations.
 pub type ApiResult<T> = std::result::Result<T, ApiError>;
 
 /// padjutsud api control command.
-#[derive(Encode, Decode)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Command {
-    Rumble { id: Option<ControllerId>, ms: u32 },
+    Rumble {
+        id: Option<ControllerId>,
+        ms: u32,
+    },
+    Status,
+    /// Stream [`FeedEvent`]s on this connection until it closes.
+    Subscribe(FeedFilter),
+    /// Feed `input` to the event loop as if `controller` sent it; without
+    /// a controller it goes to the daemon's virtual one.
+    Inject {
+        controller: Option<ControllerId>,
+        input: InjectedInput,
+    },
+    /// Stop mapping input, for `for_ms` if given. Held outputs are released.
+    Pause {
+        for_ms: Option<u64>,
+    },
+    Resume,
+    /// Pause if enabled, resume if paused.
+    Toggle,
+    /// Re-read the workspace profile now instead of waiting for the watcher.
+    Reload,
+}
+
+/// Controller input for [`Command::Inject`], with buttons and axes named as
+/// in profiles.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectedInput {
+    Press(String),
+    Release(String),
+    /// `value` in [-1.0, 1.0]. Trigger axes don't press their buttons; send
+    /// a [`InjectedInput::Press`] for that.
+    Axis {
+        axis: String,
+        value: f32,
+    },
+}
+
+impl Command {
+    /// The capability a client must negotiate to send this command.
+    pub fn capability(&self) -> &'static str {
+        match self {
+            Command::Rumble { .. } => "rumble",
+            Command::Status => "status",
+            Command::Subscribe(_) => "observe",
+            Command::Inject { .. } => "inject",
+            Command::Pause { .. } | Command::Resume | Command::Toggle => "pause",
+            Command::Reload => "reload",
+        }
+    }
+}
+
+/// Successful outcome of a [`Command`].
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Rumbled {
+        controllers: Vec<ControllerId>,
+    },
+    Status(StatusReport),
+    /// Events follow as [`FeedEvent`]s.
+    Subscribed,
+    /// The input was queued for `controller`.
+    Injected {
+        controller: ControllerId,
+    },
+    /// Mapping state after a pause, resume or toggle.
+    Enabled {
+        enabled: bool,
+        /// Time left of a timed pause.
+        resumes_in_ms: Option<u64>,
+    },
+    /// The profile was read and parsed and is now in use.
+    Reloaded,
+}
+
+/// Why the daemon refused or failed a request.
+#[derive(Error, Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteError {
+    #[error(
+        "protocol version {requested} is not supported (daemon speaks {supported})"
+    )]
+    UnsupportedVersion { requested: u16, supported: u16 },
+    #[error("capability \"{0}\" was not negotiated")]
+    NotNegotiated(String),
+    #[error("malformed message: {0}")]
+    Malformed(String),
+    #[error("no controller with id {0}")]
+    UnknownController(ControllerId),
+    #[error("no controllers connected")]
+    NoControllers,
+    #[error("daemon is not accepting commands")]
+    Unavailable,
+    #[error("daemon did not answer in time")]
+    Timeout,
+    /// The profile could not be read or parsed; the previous one stays.
+    #[error("profile not reloaded: {0}")]
+    ProfileInvalid(String),
+}
+
+/// What the event loop answers to a [`Command`].
+pub type ApiReply = Result<Response, RemoteError>;
+
+/// A command on its way to the event loop, with the channel its reply goes
+/// back on.
+pub struct ApiRequest {
+    pub command: Command,
+    pub reply: Sender<ApiReply>,
+    /// Where the events of a [`Command::Subscribe`] go.
+    pub events: Option<Sender<FeedEvent>>,
 }
 
 /// padjutsud api events transport.
 /// listener that can receive api commands from the outer world,
-/// and sender that can send api commands from the outer world to the padjutsud.
+/// and client connections that send commands to the padjutsud and read
+/// its responses.
 pub trait ApiTransport {
-    fn listen_events(&self, tx: Sender<Command>) -> ApiResult<JoinHandle<()>>;
-    fn send_event(&self, event: Command) -> ApiResult<()>;
+    type Stream: Read + Write;
+
+    fn listen_events(&self, tx: Sender<ApiRequest>) -> ApiResult<JoinHandle<()>>;
+
+    /// Connect and negotiate `capabilities`.
+    fn connect(&self, capabilities: &[&str]) -> ApiResult<Client<Self::Stream>>;
+
+    /// Send one command on a fresh connection and wait for its response.
+    fn send_event(&self, event: Command) -> ApiResult<Response> {
+        self.connect(&[event.capability()])?.request(event)
+    }
 }
@@ -0,0 +1,484 @@
+//! Framed request/response protocol spoken over stream transports.
+//!
+//! Every message is a big-endian `u32` length followed by that many bytes
+//! of bitcode. A connection opens with the client's [`Hello`] naming the
+//! protocol version and the capabilities it wants; the server answers with
+//! [`ServerMessage::Welcome`] listing the ones it grants, or
+//! [`ServerMessage::Rejected`] and closes. After that the client may send
+//! any number of [`ClientMessage::Request`]s; each gets exactly one
+//! [`ServerMessage::Response`] carrying the same id. A successful
+//! [`Command::Subscribe`] turns the connection into a one-way stream of
+//! [`ServerMessage::Event`]s that lasts until the client hangs up.
+//!
+//! Frames never reach 2^24 bytes, so such a connection always starts with a
+//! zero byte. One that starts with anything else speaks the JSON lines
+//! flavour in [`super::json_lines`] instead.
+
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use bitcode::{Decode, Encode};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use serde::Deserialize;
+
+use super::feed::FEED_BACKLOG;
+use super::json_lines;
+use super::{
+    ApiError, ApiReply, ApiRequest, ApiResult, Command, FeedEvent, FeedFilter,
+    RemoteError, Response,
+};
+
+/// Version of the framing and message types. Bumped on any incompatible
+/// change; the server rejects other versions.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Everything this build of the daemon can do, by capability name.
+pub const CAPABILITIES: &[&str] =
+    &["rumble", "status", "observe", "inject", "pause", "reload"];
+
+/// Upper bound for one frame; anything larger is a corrupt or hostile peer.
+pub(crate) const MAX_FRAME_LEN: usize = 1 << 20;
+
+/// How long a request may wait for the event loop before it fails.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize)]
+pub struct Hello {
+    pub version: u16,
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub enum ClientMessage {
+    Hello(Hello),
+    Request { id: u32, command: Command },
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub enum ServerMessage {
+    /// Handshake accepted with the granted capabilities.
+    Welcome {
+        version: u16,
+        capabilities: Vec<String>,
+    },
+    /// The connection is unusable and will be closed.
+    Rejected(RemoteError),
+    Response {
+        id: u32,
+        result: ApiReply,
+    },
+    /// One event of a subscription.
+    Event(FeedEvent),
+}
+
+pub(crate) fn write_frame<W: Write, T: Encode>(
+    writer: &mut W,
+    message: &T,
+) -> io::Result<()> {
+    let encoded = bitcode::encode(message);
+    let length = u32::try_from(encoded.len()).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "frame too large")
+    })?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(&encoded)?;
+    writer.flush()
+}
+
+/// Read one frame. `Ok(None)` means the peer closed the connection
+/// between frames.
+pub(crate) fn read_frame<R: Read, T: for<'a> Decode<'a>>(
+    reader: &mut R,
+) -> ApiResult<Option<T>> {
+    let mut length_buffer = [0u8; 4];
+    match reader.read_exact(&mut length_buffer) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_be_bytes(length_buffer) as usize;
+    if length == 0 || length > MAX_FRAME_LEN {
+        return Err(ApiError::Protocol(format!("bad frame length {length}")));
+    }
+    let mut data = vec![0u8; length];
+    reader.read_exact(&mut data)?;
+    bitcode::decode(&data)
+        .map(Some)
+        .map_err(|e| ApiError::Protocol(format!("undecodable frame: {e}")))
+}
+
+/// Hand `command` to the event loop and wait for its reply.
+pub(crate) fn dispatch(
+    tx: &Sender<ApiRequest>,
+    command: Command,
+    events: Option<Sender<FeedEvent>>,
+) -> ApiReply {
+    let (reply, replies) = bounded(1);
+    tx.send(ApiRequest {
+        command,
+        reply,
+        events,
+    })
+    .map_err(|_| RemoteError::Unavailable)?;
+    match replies.recv_timeout(REPLY_TIMEOUT) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(RemoteError::Timeout),
+        // The loop dropped the request without answering, e.g. while
+        // shutting down.
+        Err(RecvTimeoutError::Disconnected) => Err(RemoteError::Unavailable),
+    }
+}
+
+/// The capabilities granted to `hello`, or why the connection is refused.
+pub(super) fn negotiate(hello: Hello) -> Result<Vec<String>, RemoteError> {
+    if hello.version != PROTOCOL_VERSION {
+        return Err(RemoteError::UnsupportedVersion {
+            requested: hello.version,
+            supported: PROTOCOL_VERSION,
+        });
+    }
+    Ok(hello
+        .capabilities
+        .into_iter()
+        .filter(|c| CAPABILITIES.contains(&c.as_str()))
+        .collect())
+}
+
+/// Serve one client connection until it closes.
+pub(crate) fn serve<S: Read + Write>(
+    stream: &mut S,
+    tx: &Sender<ApiRequest>,
+) -> ApiResult<()> {
+    let mut first = [0u8; 1];
+    match stream.read_exact(&mut first) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+        Err(e) => return Err(e.into()),
+    }
+    if first[0] != 0 {
+        return json_lines::serve(stream, tx, first[0]);
+    }
+    let granted = match read_frame(&mut (&first[..]).chain(&mut *stream))? {
+        None => return Ok(()),
+        Some(ClientMessage::Hello(hello)) => match negotiate(hello) {
+            Ok(granted) => {
+                write_frame(
+                    stream,
+                    &ServerMessage::Welcome {
+                        version: PROTOCOL_VERSION,
+                        capabilities: granted.clone(),
+                    },
+                )?;
+                granted
+            }
+            Err(error) => {
+                write_frame(stream, &ServerMessage::Rejected(error))?;
+                return Ok(());
+            }
+        },
+        Some(ClientMessage::Request { .. }) => {
+            let error = RemoteError::Malformed("expected hello".into());
+            write_frame(stream, &ServerMessage::Rejected(error))?;
+            return Ok(());
+        }
+    };
+
+    loop {
+        let message = match read_frame(stream) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(ApiError::Protocol(reason)) => {
+                let error = RemoteError::Malformed(reason.clone());
+                let _ = write_frame(stream, &ServerMessage::Rejected(error));
+                return Err(ApiError::Protocol(reason));
+            }
+            Err(e) => return Err(e),
+        };
+        let (id, command) = match message {
+            ClientMessage::Request { id, command } => (id, command),
+            ClientMessage::Hello(_) => {
+                let error = RemoteError::Malformed("repeated hello".into());
+                write_frame(stream, &ServerMessage::Rejected(error))?;
+                return Ok(());
+            }
+        };
+        let capability = command.capability();
+        if !granted.iter().any(|c| c == capability) {
+            let result = Err(RemoteError::NotNegotiated(capability.into()));
+            write_frame(stream, &ServerMessage::Response { id, result })?;
+            continue;
+        }
+        if let Command::Subscribe(_) = command {
+            let (events_tx, events) = bounded(FEED_BACKLOG);
+            let result = dispatch(tx, command, Some(events_tx));
+            let subscribed = result.is_ok();
+            write_frame(stream, &ServerMessage::Response { id, result })?;
+            if subscribed {
+                return stream_events(stream, events);
+            }
+            continue;
+        }
+        let result = dispatch(tx, command, None);
+        write_frame(stream, &ServerMessage::Response { id, result })?;
+    }
+}
+
+/// Forward subscription events until the client or the event loop goes away.
+fn stream_events<S: Write>(
+    stream: &mut S,
+    events: Receiver<FeedEvent>,
+) -> ApiResult<()> {
+    for event in events {
+        match write_frame(stream, &ServerMessage::Event(event)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// The client end of a connection, after the handshake.
+pub struct Client<S: Read + Write> {
+    stream: S,
+    capabilities: Vec<String>,
+    next_id: u32,
+}
+
+impl<S: Read + Write> Client<S> {
+    /// Perform the handshake, asking for `capabilities`.
+    pub fn handshake(mut stream: S, capabilities: &[&str]) -> ApiResult<Self> {
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        };
+        write_frame(&mut stream, &ClientMessage::Hello(hello))?;
+        match read_frame(&mut stream)? {
+            Some(ServerMessage::Welcome { capabilities, .. }) => Ok(Self {
+                stream,
+                capabilities,
+                next_id: 1,
+            }),
+            Some(ServerMessage::Rejected(error)) => Err(ApiError::Remote(error)),
+            Some(ServerMessage::Response { .. } | ServerMessage::Event(_)) => {
+                Err(ApiError::Protocol("response before welcome".into()))
+            }
+            None => Err(ApiError::Protocol(
+                "connection closed during handshake".into(),
+            )),
+        }
+    }
+
+    /// Send `command` and wait for its response.
+    pub fn request(&mut self, command: Command) -> ApiResult<Response> {
+        let capability = command.capability();
+        if !self.capabilities.iter().any(|c| c == capability) {
+            return Err(ApiError::Remote(RemoteError::NotNegotiated(
+                capability.into(),
+            )));
+        }
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        write_frame(&mut self.stream, &ClientMessage::Request { id, command })?;
+        match read_frame(&mut self.stream)? {
+            Some(ServerMessage::Response { id: got, result }) if got == id => {
+                result.map_err(ApiError::Remote)
+            }
+            Some(ServerMessage::Response { id: got, .. }) => {
+                Err(ApiError::Protocol(format!(
+                    "response for request {got}, expected {id}"
+                )))
+            }
+            Some(ServerMessage::Rejected(error)) => Err(ApiError::Remote(error)),
+            Some(ServerMessage::Welcome { .. }) => {
+                Err(ApiError::Protocol("unexpected welcome".into()))
+            }
+            Some(ServerMessage::Event(_)) => {
+                Err(ApiError::Protocol("event before subscribing".into()))
+            }
+            None => Err(ApiError::Protocol("connection closed".into())),
+        }
+    }
+
+    /// Subscribe to the daemon's event feed. Afterwards the connection only
+    /// carries events; read them with [`Client::next_event`].
+    pub fn subscribe(&mut self, filter: FeedFilter) -> ApiResult<()> {
+        match self.request(Command::Subscribe(filter))? {
+            Response::Subscribed => Ok(()),
+            other => {
+                Err(ApiError::Protocol(format!("unexpected response {other:?}")))
+            }
+        }
+    }
+
+    /// The next subscription event; `Ok(None)` once the daemon hangs up.
+    pub fn next_event(&mut self) -> ApiResult<Option<FeedEvent>> {
+        match read_frame(&mut self.stream)? {
+            Some(ServerMessage::Event(event)) => Ok(Some(event)),
+            Some(ServerMessage::Rejected(error)) => Err(ApiError::Remote(error)),
+            Some(_) => Err(ApiError::Protocol("expected an event".into())),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    use crossbeam_channel::unbounded;
+
+    use super::*;
+
+    /// Answer rumble requests and subscriptions from a fake event loop.
+    fn fake_loop() -> Sender<ApiRequest> {
+        let (tx, rx) = unbounded::<ApiRequest>();
+        thread::spawn(move || {
+            for request in rx {
+                let reply = match request.command {
+                    Command::Rumble { id: Some(7), .. } => {
+                        Err(RemoteError::UnknownController(7))
+                    }
+                    Command::Rumble { id, .. } => Ok(Response::Rumbled {
+                        controllers: vec![id.unwrap_or(0)],
+                    }),
+                    Command::Status => Err(RemoteError::Unavailable),
+                    Command::Subscribe(_) => Ok(Response::Subscribed),
+                    Command::Inject { .. }
+                    | Command::Pause { .. }
+                    | Command::Resume
+                    | Command::Toggle
+                    | Command::Reload => Err(RemoteError::Unavailable),
+                };
+                let _ = request.reply.send(reply);
+                for app in ["one", "two"] {
+                    if let Some(events) = &request.events {
+                        let _ = events.send(FeedEvent::App { app: app.into() });
+                    }
+                }
+            }
+        });
+        tx
+    }
+
+    fn connect(capabilities: &[&str]) -> ApiResult<Client<UnixStream>> {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let tx = fake_loop();
+        thread::spawn(move || {
+            let _ = serve(&mut server, &tx);
+        });
+        Client::handshake(client, capabilities)
+    }
+
+    #[test]
+    fn answers_several_requests_per_connection() {
+        let mut client = connect(&["rumble", "teleport"]).unwrap();
+        assert_eq!(client.capabilities, ["rumble"]);
+        assert_eq!(
+            client
+                .request(Command::Rumble {
+                    id: Some(1),
+                    ms: 10
+                })
+                .unwrap(),
+            Response::Rumbled {
+                controllers: vec![1]
+            }
+        );
+        match client.request(Command::Rumble {
+            id: Some(7),
+            ms: 10,
+        }) {
+            Err(ApiError::Remote(RemoteError::UnknownController(7))) => {}
+            other => panic!("expected unknown controller, got {other:?}"),
+        }
+        assert!(client.request(Command::Rumble { id: None, ms: 10 }).is_ok());
+    }
+
+    #[test]
+    fn refuses_commands_that_were_not_negotiated() {
+        let mut client = connect(&[]).unwrap();
+        match client.request(Command::Rumble { id: None, ms: 10 }) {
+            Err(ApiError::Remote(RemoteError::NotNegotiated(c))) => {
+                assert_eq!(c, "rumble")
+            }
+            other => panic!("expected not negotiated, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn server_enforces_negotiated_capabilities() {
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let tx = fake_loop();
+        thread::spawn(move || serve(&mut server, &tx));
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: Vec::new(),
+        };
+        write_frame(&mut client, &ClientMessage::Hello(hello)).unwrap();
+        let _: Option<ServerMessage> = read_frame(&mut client).unwrap();
+        let request = ClientMessage::Request {
+            id: 9,
+            command: Command::Rumble { id: None, ms: 1 },
+        };
+        write_frame(&mut client, &request).unwrap();
+        let reply: Option<ServerMessage> = read_frame(&mut client).unwrap();
+        assert_eq!(
+            reply,
+            Some(ServerMessage::Response {
+                id: 9,
+                result: Err(RemoteError::NotNegotiated("rumble".into())),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_other_versions_and_missing_hello() {
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let tx = fake_loop();
+        let handle = thread::spawn(move || serve(&mut server, &tx));
+        let hello = Hello {
+            version: PROTOCOL_VERSION + 1,
+            capabilities: Vec::new(),
+        };
+        write_frame(&mut client, &ClientMessage::Hello(hello)).unwrap();
+        let reply: Option<ServerMessage> = read_frame(&mut client).unwrap();
+        assert_eq!(
+            reply,
+            Some(ServerMessage::Rejected(RemoteError::UnsupportedVersion {
+                requested: PROTOCOL_VERSION + 1,
+                supported: PROTOCOL_VERSION,
+            }))
+        );
+        handle.join().unwrap().unwrap();
+
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let tx = fake_loop();
+        thread::spawn(move || serve(&mut server, &tx));
+        let request = ClientMessage::Request {
+            id: 1,
+            command: Command::Rumble { id: None, ms: 1 },
+        };
+        write_frame(&mut client, &request).unwrap();
+        let reply: Option<ServerMessage> = read_frame(&mut client).unwrap();
+        assert!(matches!(
+            reply,
+            Some(ServerMessage::Rejected(RemoteError::Malformed(_)))
+        ));
+    }
+
+    #[test]
+    fn subscription_streams_events_until_the_loop_hangs_up() {
+        let mut client = connect(&["observe"]).unwrap();
+        client.subscribe(FeedFilter::default()).unwrap();
+        assert_eq!(
+            client.next_event().unwrap(),
+            Some(FeedEvent::App { app: "one".into() })
+        );
+        assert_eq!(
+            client.next_event().unwrap(),
+            Some(FeedEvent::App { app: "two".into() })
+        );
+        assert_eq!(client.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+
+use bitcode::{Decode, Encode};
+use padjutsu_control::WorkerStats;
+use padjutsu_gamepad::ControllerId;
+use serde::Serialize;
+
+use crate::app::binding::BindingSource;
+use crate::domain::{ControllerMode, RuntimeMode, StickActivity, StickModeKind};
+
+/// Snapshot of the daemon's live state, answered to [`super::Command::Status`].
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+pub struct StatusReport {
+    pub mode: RuntimeMode,
+    /// Whether mappings are paused.
+    pub paused: bool,
+    /// Time left of a timed pause.
+    pub resumes_in_ms: Option<u64>,
+    pub active_app: String,
+    pub binding: BindingSource,
+    /// File the workspace profile is loaded from, if the daemon has one.
+    pub profile_path: Option<String>,
+    /// Time since the current profile was loaded; `None` without a profile.
+    pub profile_age_ms: Option<u64>,
+    pub controllers: Vec<ControllerStatus>,
+    pub held: Vec<HeldStatus>,
+    pub repeats: Vec<RepeatStatus>,
+    /// Queues of the thread that posts output; `None` until the event loop
+    /// has sampled them.
+    pub output: Option<OutputStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+pub struct ControllerStatus {
+    pub id: ControllerId,
+    pub name: String,
+    /// `None` until the event loop has seen the controller connect.
+    pub mode: Option<ControllerMode>,
+    pub left_stick: Option<StickStatus>,
+    pub right_stick: Option<StickStatus>,
+    /// Profile names of the buttons held, after the controller's mapping.
+    pub pressed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize)]
+pub struct StickStatus {
+    pub mode: Option<StickModeKind>,
+    pub activity: StickActivity,
+}
+
+/// An output a hold action keeps pressed.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+pub struct HeldStatus {
+    pub controller: ControllerId,
+    /// e.g. `key cmd+c` or `mouse Left`.
+    pub output: String,
+}
+
+/// Queue depths and drop counts of the performer worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+pub struct OutputStatus {
+    /// Key, button and text commands waiting to be posted.
+    pub queued: u64,
+    /// Most commands ever waiting at once.
+    pub queued_peak: u64,
+    /// Mouse moves and scrolls waiting to be posted.
+    pub motion_queued: u64,
+    /// Mouse moves and scrolls dropped because output fell behind.
+    pub motion_dropped: u64,
+    /// Commands lost because the worker thread stopped.
+    pub lost: u64,
+}
+
+impl From<WorkerStats> for OutputStatus {
+    fn from(stats: WorkerStats) -> Self {
+        Self {
+            queued: stats.lossless_depth as u64,
+            queued_peak: stats.lossless_peak as u64,
+            motion_queued: stats.motion_depth as u64,
+            motion_dropped: stats.motion_dropped,
+            lost: stats.disconnected_dropped,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+pub struct RepeatStatus {
+    pub controller: ControllerId,
+    /// What drives the repeat, e.g. `button a` or `left stick arrow`.
+    pub source: String,
+    pub key: String,
+    pub next_in_ms: u64,
+}
+
+impl fmt::Display for StickStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            Some(mode) => write!(f, "{mode:?}, {:?}", self.activity),
+            None => write!(f, "none, {:?}", self.activity),
+        }
+    }
+}
+
+impl fmt::Display for StatusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "mode:        {:?}", self.mode)?;
+        match (self.paused, self.resumes_in_ms) {
+            (false, _) => writeln!(f, "mappings:    enabled")?,
+            (true, None) => writeln!(f, "mappings:    paused")?,
+            (true, Some(ms)) => {
+                writeln!(f, "mappings:    paused, resuming in {}s", ms / 1000)?
+            }
+        }
+        let app = if self.active_app.is_empty() {
+            "(none)"
+        } else {
+            &self.active_app
+        };
+        writeln!(f, "app:         {app} ({:?} rules)", self.binding)?;
+        let path = self.profile_path.as_deref().unwrap_or("(no workspace)");
+        match self.profile_age_ms {
+            Some(ms) => {
+                writeln!(f, "profile:     {path}, loaded {}s ago", ms / 1000)?
+            }
+            None => writeln!(f, "profile:     {path}, not loaded")?,
+        }
+        if self.controllers.is_empty() {
+            writeln!(f, "controllers: none")?;
+        }
+        for controller in &self.controllers {
+            let mode = controller
+                .mode
+                .map(|mode| format!("{mode:?}"))
+                .unwrap_or_else(|| "unknown".to_string());
+            writeln!(
+                f,
+                "controller:  {} {:?} {mode}, pressed [{}]",
+                controller.id,
+                controller.name,
+                controller.pressed.join(", ")
+            )?;
+            if let Some(stick) = controller.left_stick {
+                writeln!(f, "  left:      {stick}")?;
+            }
+            if let Some(stick) = controller.right_stick {
+                writeln!(f, "  right:     {stick}")?;
+            }
+        }
+        for held in &self.held {
+            writeln!(f, "held:        {} on {}", held.output, held.controller)?;
+        }
+        for repeat in &self.repeats {
+            writeln!(
+                f,
+                "repeat:      {} -> {} on {}, next in {}ms",
+                repeat.source, repeat.key, repeat.controller, repeat.next_in_ms
+            )?;
+        }
+        if let Some(output) = self.output {
+            writeln!(
+                f,
+                "output:      {} queued (peak {}), {} motion queued, \
+                 {} motion dropped, {} lost",
+                output.queued,
+                output.queued_peak,
+                output.motion_queued,
+                output.motion_dropped,
+                output.lost
+            )?;
+        }
+        Ok(())
+    }
+}
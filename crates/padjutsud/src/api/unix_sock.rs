@@ -1,23 +1,17 @@
 use std::fs;
-use std::io::{BufWriter, Read, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::thread::{self, JoinHandle};
 
 use crossbeam_channel::Sender;
 use colored::Colorize;
-use bitcode::{Encode, Decode};
 
-use crate::{print_error, print_info};
-use super::{Command, ApiTransport, ApiResult};
+use crate::{print_debug, print_error, print_info};
+use super::protocol::{serve, Client};
+use super::{ApiRequest, ApiTransport, ApiResult};
 
 const SOCKET_FILE_NAME: &str = "api.sock";
 
-#[derive(Encode, Decode)]
-pub struct SocketCommand {
-    command: Command,
-}
-
 pub struct UnixSocket {
     socket_path: PathBuf,
 }
@@ -31,43 +25,25 @@ impl UnixSocket {
 }
 
 impl UnixSocket {
-    fn handle_connection(mut stream: UnixStream, tx: &Sender<Command>) {
-        let mut length_buffer = [0u8; 4];
-        let _ = stream.read_exact(&mut length_buffer);
-        if length_buffer == [0u8; 4] {
-            let _ = stream.write_all(b"ERR empty\n");
-            return;
-        }
-
-        let length = u32::from_be_bytes(length_buffer) as usize;
-        if length == 0 {
-            let _ = stream.write_all(b"ERR empty\n");
-            return;
+    fn handle_connection(mut stream: UnixStream, tx: Sender<ApiRequest>) {
+        let spawned = thread::Builder::new()
+            .name("padjutsud-socket-client".into())
+            .spawn(move || {
+                if let Err(e) = serve(&mut stream, &tx) {
+                    print_error!("control socket client error: {e}");
+                }
+                print_debug!("control socket client disconnected");
+            });
+        if let Err(e) = spawned {
+            print_error!("failed to spawn control socket client thread: {e}");
         }
-
-        // Читаем данные
-        let mut data_buffer = vec![0u8; length];
-        let Ok(_) = stream.read_exact(&mut data_buffer) else {
-            let _ = stream.write_all(b"ERR read failed\n");
-            return;
-        };
-
-        // Десериализуем
-        let command = match bitcode::decode(&data_buffer) {
-            Ok(cmd) => cmd,
-            Err(err) => {
-                print_error!("failed to decode command: {err}");
-                let _ = stream.write_all(format!("ERR {err}\n").as_bytes());
-                return;
-            }
-        };
-
-        tx.send(command).unwrap();
     }
 }
 
 impl ApiTransport for UnixSocket {
-    fn listen_events(&self, tx: Sender<Command>) -> ApiResult<JoinHandle<()>> {
+    type Stream = UnixStream;
+
+    fn listen_events(&self, tx: Sender<ApiRequest>) -> ApiResult<JoinHandle<()>> {
         let socket_path = self.socket_path.clone();
         if socket_path.exists() {
             fs::remove_file(&socket_path)?;
@@ -81,7 +57,7 @@ impl ApiTransport for UnixSocket {
                 for stream in listener.incoming() {
                     match stream {
                         Ok(stream) => {
-                            Self::handle_connection(stream, &tx);
+                            Self::handle_connection(stream, tx.clone());
                         }
                         Err(e) => {
                             print_error!("control socket accept error: {}", e);
@@ -93,16 +69,8 @@ impl ApiTransport for UnixSocket {
         Ok(handle)
     }
 
-    fn send_event(&self, event: Command) -> ApiResult<()> {
-        let socket_path = self.socket_path.clone();
-        let stream = UnixStream::connect(&socket_path)?;
-        let mut writer = BufWriter::new(stream);
-        let cmd = SocketCommand { command: event };
-        let encoded = bitcode::encode(&cmd);
-        let length = encoded.len() as u32;
-        writer.write_all(&length.to_be_bytes())?;
-        writer.write_all(&encoded)?;
-
-        Ok(())
+    fn connect(&self, capabilities: &[&str]) -> ApiResult<Client<UnixStream>> {
+        let stream = UnixStream::connect(&self.socket_path)?;
+        Client::handshake(stream, capabilities)
     }
 }
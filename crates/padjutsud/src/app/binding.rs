@@ -1,10 +1,14 @@
 use std::sync::Arc;
 
+use bitcode::{Decode, Encode};
+use serde::Serialize;
+
 use padjutsu_workspace::{ButtonRules, Profile, StickRules};
 
 use super::stick::CompiledStickRules;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BindingSource {
     #[default]
     None,
@@ -54,6 +58,10 @@ impl BindingContext {
         let stick_rules = rules.map(|rules| Arc::new(rules.sticks.clone()));
         let compiled_stick_rules =
             stick_rules.as_deref().map(CompiledStickRules::from_rules);
+        // An app's own `shell` wins over the profile's.
+        let shell = rules
+            .and_then(|rules| rules.shell.clone())
+            .or_else(|| profile.shell.clone());
 
         Self {
             active_app: active_app.into(),
@@ -61,7 +69,7 @@ impl BindingContext {
             button_rules,
             stick_rules,
             compiled_stick_rules,
-            shell: profile.shell.clone(),
+            shell,
         }
     }
 
@@ -0,0 +1,71 @@
+//! Short, profile-like descriptions of bindings, for the cheat sheet,
+//! `explain` and the state dump.
+
+use std::fmt::Write as _;
+
+use padjutsu_workspace::{button_name, Axis, ButtonAction, ShellAction, StickMode};
+
+/// A short, profile-like description of a button action.
+pub fn describe_action(action: &ButtonAction) -> String {
+    match action {
+        ButtonAction::Keystroke(combo) => format!("keystroke {combo}"),
+        ButtonAction::HoldKeystroke(combo) => format!("hold {combo}"),
+        ButtonAction::TapKeystroke(combo) => format!("tap {combo}"),
+        ButtonAction::Macros(macros) => {
+            let combos: Vec<String> = macros.iter().map(|c| c.to_string()).collect();
+            format!("macros {}", combos.join(", "))
+        }
+        ButtonAction::Shell(shell) => describe_shell("shell", shell),
+        ButtonAction::ShellHold(shell) => describe_shell("shell_hold", shell),
+        ButtonAction::MouseClick { button, click_type } => {
+            format!("click {button:?} {click_type:?}")
+        }
+        ButtonAction::HoldClick(button) => format!("hold_click {button:?}"),
+        ButtonAction::RawModifier(key) => format!("raw_modifier {key:?}"),
+        ButtonAction::Type { text, delay_ms } => {
+            format!("type {text:?} delay_ms={delay_ms}")
+        }
+        ButtonAction::ToggleEnabled { pause_ms: None } => {
+            "toggle_enabled".to_string()
+        }
+        ButtonAction::ToggleEnabled { pause_ms: Some(ms) } => {
+            format!("toggle_enabled pause_ms={ms}")
+        }
+    }
+}
+
+fn describe_shell(kind: &str, shell: &ShellAction) -> String {
+    let mut out = format!("{kind} {}", shell.command);
+    if let Some(ms) = shell.timeout_ms {
+        let _ = write!(out, " timeout_ms={ms}");
+    }
+    if shell.single_instance {
+        out.push_str(" single_instance");
+    }
+    out
+}
+
+/// A short description of a stick mode.
+pub fn summarize_stick(mode: &StickMode) -> String {
+    let axis = |axis: &Axis| match axis {
+        Axis::X => "x",
+        Axis::Y => "y",
+    };
+    match mode {
+        StickMode::Arrows(_) => "arrows".to_string(),
+        StickMode::Volume(params) => format!("volume ({} axis)", axis(&params.axis)),
+        StickMode::Brightness(params) => {
+            format!("brightness ({} axis)", axis(&params.axis))
+        }
+        StickMode::MouseMove(params) => match params.precision_button {
+            Some(button) => {
+                format!("mouse_move, hold {} for precision", button_name(button))
+            }
+            None => "mouse_move".to_string(),
+        },
+        StickMode::Scroll(params) if params.horizontal => {
+            "scroll, horizontal too".to_string()
+        }
+        StickMode::Scroll(_) => "scroll".to_string(),
+    }
+}
@@ -1,8 +1,27 @@
+use std::fmt;
 use std::sync::Arc;
 
 use padjutsu_control::KeyCombo;
 use padjutsu_gamepad::ControllerId;
-use padjutsu_workspace::{Macros, MouseButton, MouseClickType, RawModifierKey};
+use padjutsu_workspace::{
+    chord_name, ButtonChord, Macros, MouseButton, MouseClickType, RawModifierKey,
+    ShellAction,
+};
+
+/// A shell command to start and what triggered it.
+#[derive(Debug, Clone)]
+pub struct ShellRun {
+    pub action: Arc<ShellAction>,
+    /// `PADJUTSU_*` variables describing the trigger.
+    pub env: Vec<(&'static str, String)>,
+}
+
+/// The chord on a controller that keeps a `shell_hold` command running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeldShell {
+    pub controller: ControllerId,
+    pub chord: ButtonChord,
+}
 
 #[derive(Debug, Clone)]
 pub enum Effect {
@@ -12,7 +31,18 @@ pub enum Effect {
     KeyRelease(KeyCombo),
     KeyTap(KeyCombo),
     Macros(Arc<Macros>),
-    Shell(String),
+    Shell(Box<ShellRun>),
+    /// Start a `shell_hold` command; it runs until the [`Effect::ShellStop`]
+    /// for the same chord.
+    ShellStart {
+        held: HeldShell,
+        run: Box<ShellRun>,
+    },
+    ShellStop(HeldShell),
+    Type {
+        text: String,
+        delay_ms: u64,
+    },
     MouseClick {
         button: MouseButton,
         click_type: MouseClickType,
@@ -35,6 +65,55 @@ pub enum Effect {
         id: ControllerId,
         ms: u32,
     },
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
     RawModifierPress(RawModifierKey),
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
     RawModifierRelease(RawModifierKey),
+    /// Lift every key the output backend still holds, whichever action
+    /// pressed it.
+    ReleaseAll,
+}
+
+/// One line per effect, stable enough to diff; `simulate`, profile tests
+/// and the event feed all print effects this way.
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Effect::KeyPress(combo) => write!(f, "key_press {combo}"),
+            Effect::KeyRelease(combo) => write!(f, "key_release {combo}"),
+            Effect::KeyTap(combo) => write!(f, "tap {combo}"),
+            Effect::Macros(macros) => {
+                f.write_str("macros")?;
+                for (i, combo) in macros.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{sep}{combo}")?;
+                }
+                Ok(())
+            }
+            Effect::Shell(run) => write!(f, "shell {}", run.action.command),
+            Effect::ShellStart { held, run } => write!(
+                f,
+                "shell_start {} {}",
+                chord_name(&held.chord),
+                run.action.command
+            ),
+            Effect::ShellStop(held) => {
+                write!(f, "shell_stop {}", chord_name(&held.chord))
+            }
+            Effect::Type { text, delay_ms } => {
+                write!(f, "type {text:?} delay_ms={delay_ms}")
+            }
+            Effect::MouseClick { button, click_type } => {
+                write!(f, "click {button:?} {click_type:?}")
+            }
+            Effect::MousePress { button } => write!(f, "mouse_press {button:?}"),
+            Effect::MouseRelease { button } => write!(f, "mouse_release {button:?}"),
+            Effect::MouseMove { dx, dy } => write!(f, "mouse_move {dx} {dy}"),
+            Effect::Scroll { h, v } => write!(f, "scroll {h:.3} {v:.3}"),
+            Effect::Rumble { ms, .. } => write!(f, "rumble {ms}ms"),
+            Effect::RawModifierPress(key) => write!(f, "rawkey_press {key:?}"),
+            Effect::RawModifierRelease(key) => write!(f, "rawkey_release {key:?}"),
+            Effect::ReleaseAll => f.write_str("release_all"),
+        }
+    }
 }
@@ -1,9 +1,10 @@
 pub mod binding;
+pub mod describe;
 pub mod effect;
 pub mod padjutsu;
 pub mod stick;
 
-pub use effect::Effect;
+pub use effect::{Effect, HeldShell, ShellRun};
 pub use padjutsu::Padjutsu;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
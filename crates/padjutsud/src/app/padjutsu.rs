@@ -1,4 +1,6 @@
 use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 use ahash::AHashMap;
 
@@ -8,12 +10,14 @@ use padjutsu_control::KeyCombo;
 use padjutsu_bit_mask::Bitmask;
 use padjutsu_gamepad::{Button, ControllerId, ControllerInfo, Axis as CtrlAxis};
 use padjutsu_workspace::{
-    ButtonAction, ControllerSettings, Profile, StickMode, StickSide,
+    chord_name, ButtonAction, ButtonChord, ButtonRules, ControllerSettings,
+    MouseButton, Profile, RawModifierKey, ShellAction, StickMode, StickRules,
+    StickSide,
 };
 
 use crate::{app::ButtonPhase, print_debug, print_info};
 use super::binding::{BindingContext, BindingSource};
-use super::effect::Effect;
+use super::effect::{Effect, HeldShell, ShellRun};
 use super::stick::{StickProcessor, CompiledStickRules};
 use super::stick::util::axis_index as stick_axis_index;
 
@@ -25,8 +29,8 @@ struct ControllerState {
     axes: [f32; 6],
 }
 
-const DEFAULT_REPEAT_DELAY_MS: u64 = 400;
-const DEFAULT_REPEAT_INTERVAL_MS: u64 = 50;
+pub(crate) const DEFAULT_REPEAT_DELAY_MS: u64 = 400;
+pub(crate) const DEFAULT_REPEAT_INTERVAL_MS: u64 = 50;
 
 struct ButtonRepeatTask {
     key: KeyCombo,
@@ -46,6 +50,13 @@ struct ButtonTransition {
     target_button: Button,
     effects: Vec<Effect>,
     repeat: ButtonRepeatDirective,
+    /// Set by `toggle_enabled` rules: flip the paused state.
+    toggle: Option<PauseToggle>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PauseToggle {
+    pause_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -59,14 +70,71 @@ enum ButtonRepeatDirective {
     Stop,
 }
 
+/// An output a hold action keeps pressed until its chord is released.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeldOutput {
+    Key(KeyCombo),
+    Mouse(MouseButton),
+    RawModifier(RawModifierKey),
+    /// A running `shell_hold` command, by its chord.
+    Shell(ButtonChord),
+}
+
+impl HeldOutput {
+    fn pressed_by(effect: &Effect) -> Option<Self> {
+        match effect {
+            Effect::KeyPress(combo) => Some(Self::Key(combo.clone())),
+            Effect::MousePress { button } => Some(Self::Mouse(*button)),
+            Effect::RawModifierPress(key) => Some(Self::RawModifier(*key)),
+            Effect::ShellStart { held, .. } => Some(Self::Shell(held.chord)),
+            _ => None,
+        }
+    }
+
+    fn released_by(effect: &Effect) -> Option<Self> {
+        match effect {
+            Effect::KeyRelease(combo) => Some(Self::Key(combo.clone())),
+            Effect::MouseRelease { button } => Some(Self::Mouse(*button)),
+            Effect::RawModifierRelease(key) => Some(Self::RawModifier(*key)),
+            Effect::ShellStop(held) => Some(Self::Shell(held.chord)),
+            _ => None,
+        }
+    }
+
+    /// The effect that undoes this output, held by `controller`.
+    fn release(self, controller: ControllerId) -> Effect {
+        match self {
+            Self::Key(combo) => Effect::KeyRelease(combo),
+            Self::Mouse(button) => Effect::MouseRelease { button },
+            Self::RawModifier(key) => Effect::RawModifierRelease(key),
+            Self::Shell(chord) => Effect::ShellStop(HeldShell { controller, chord }),
+        }
+    }
+}
+
+/// A key the daemon is currently repeating.
+#[derive(Debug, Clone)]
+pub struct ActiveRepeat {
+    pub controller: ControllerId,
+    /// What drives the repeat, e.g. `button a` or `left stick arrow up`.
+    pub source: String,
+    pub key: KeyCombo,
+    pub next_fire: Instant,
+}
+
 pub struct Padjutsu {
     pub workspace: Option<Profile>,
+    profile_path: Option<PathBuf>,
+    profile_loaded_at: Option<Instant>,
     active_app: Box<str>,
     binding: BindingContext,
     controllers: AHashMap<ControllerId, ControllerState>,
     sticks: RefCell<StickProcessor>,
     axes_scratch: Vec<(ControllerId, [f32; 6])>,
     button_repeats: AHashMap<(ControllerId, Button), ButtonRepeatTask>,
+    held: Vec<(ControllerId, HeldOutput)>,
+    paused: bool,
+    paused_until: Option<Instant>,
 }
 
 impl Default for Padjutsu {
@@ -79,12 +147,17 @@ impl Padjutsu {
     pub fn new() -> Self {
         Self {
             workspace: None,
+            profile_path: None,
+            profile_loaded_at: None,
             active_app: "".into(),
             binding: BindingContext::default(),
             controllers: AHashMap::new(),
             sticks: RefCell::new(StickProcessor::new()),
             axes_scratch: Vec::new(),
             button_repeats: AHashMap::new(),
+            held: Vec::new(),
+            paused: false,
+            paused_until: None,
         }
     }
 
@@ -94,6 +167,7 @@ impl Padjutsu {
 
     pub fn remove_workspace(&mut self) {
         self.workspace = None;
+        self.profile_loaded_at = None;
         self.binding = BindingContext::empty(self.active_app.as_ref());
         self.button_repeats.clear();
     }
@@ -104,6 +178,128 @@ impl Padjutsu {
         self.rebuild_binding_context();
     }
 
+    /// Record the file the workspace profile is loaded from.
+    pub fn set_profile_path(&mut self, path: PathBuf) {
+        self.profile_path = Some(path);
+    }
+
+    pub fn profile_path(&self) -> Option<&Path> {
+        self.profile_path.as_deref()
+    }
+
+    /// Record when the current workspace profile was loaded.
+    pub fn set_profile_loaded_at(&mut self, at: Instant) {
+        self.profile_loaded_at = Some(at);
+    }
+
+    pub fn profile_loaded_at(&self) -> Option<Instant> {
+        self.profile_loaded_at
+    }
+
+    pub fn active_app(&self) -> &str {
+        self.active_app.as_ref()
+    }
+
+    pub fn binding_source(&self) -> BindingSource {
+        self.binding.source()
+    }
+
+    /// Button rules in effect for the active app.
+    pub fn button_rules(&self) -> Option<&ButtonRules> {
+        self.binding.button_rules()
+    }
+
+    /// Stick rules in effect for the active app.
+    pub fn stick_rules(&self) -> Option<&StickRules> {
+        self.binding.stick_rules()
+    }
+
+    /// Buttons held on controller `id`, after its button mapping.
+    pub fn pressed_buttons(&self, id: ControllerId) -> Bitmask<Button> {
+        self.controllers
+            .get(&id)
+            .map(|state| state.pressed)
+            .unwrap_or_else(Bitmask::empty)
+    }
+
+    /// Outputs pressed by hold actions and not yet released.
+    pub fn held_outputs(&self) -> &[(ControllerId, HeldOutput)] {
+        &self.held
+    }
+
+    /// Button and stick repeats, soonest first.
+    pub fn active_repeats(&self) -> Vec<ActiveRepeat> {
+        let mut repeats: Vec<ActiveRepeat> = self
+            .button_repeats
+            .iter()
+            .map(|((controller, button), task)| ActiveRepeat {
+                controller: *controller,
+                source: format!(
+                    "button {}",
+                    padjutsu_workspace::button_name(*button)
+                ),
+                key: task.key.clone(),
+                next_fire: task.next_fire,
+            })
+            .collect();
+        repeats.extend(self.sticks.borrow().active_repeats());
+        repeats.sort_by_key(|repeat| repeat.next_fire);
+        repeats
+    }
+
+    /// Whether mappings are paused; only `toggle_enabled` rules fire then.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// When a timed pause ends by itself.
+    pub fn paused_until(&self) -> Option<Instant> {
+        self.paused_until
+    }
+
+    /// Pause all mappings, until `until` if given. Returns the releases of
+    /// every output hold actions keep pressed; repeats and stick motion stop.
+    pub fn pause(&mut self, until: Option<Instant>) -> Vec<Effect> {
+        print_info!("mappings paused");
+        self.paused = true;
+        self.paused_until = until;
+        self.button_repeats.clear();
+        {
+            let mut sticks = self.sticks.borrow_mut();
+            for id in self.controllers.keys() {
+                sticks.release_all_for(*id);
+            }
+        }
+        self.release_held()
+    }
+
+    /// Undo every output hold actions keep pressed, then release whatever
+    /// the backend still holds so no key outlives a pause or shutdown.
+    pub fn release_held(&mut self) -> Vec<Effect> {
+        let mut effects: Vec<Effect> = self
+            .held
+            .drain(..)
+            .map(|(controller, output)| output.release(controller))
+            .collect();
+        effects.push(Effect::ReleaseAll);
+        effects
+    }
+
+    pub fn resume(&mut self) {
+        if self.paused {
+            print_info!("mappings resumed");
+        }
+        self.paused = false;
+        self.paused_until = None;
+    }
+
+    /// Resume if a timed pause has run out by `now`.
+    pub fn resume_if_due(&mut self, now: Instant) {
+        if self.paused_until.is_some_and(|until| until <= now) {
+            self.resume();
+        }
+    }
+
     pub fn add_controller(&mut self, info: ControllerInfo) {
         print_info!(
             "add controller - {0} id={1} vid=0x{2:x} pid=0x{3:x}",
@@ -232,10 +428,20 @@ impl Padjutsu {
         }
     }
 
-    pub fn on_controller_disconnected(&mut self, id: ControllerId) {
+    /// Stop what controller `id` drives. Returns the releases of the
+    /// outputs its hold actions keep pressed, `shell_hold` commands included.
+    pub fn on_controller_disconnected(&mut self, id: ControllerId) -> Vec<Effect> {
         self.sticks.borrow_mut().release_all_for(id);
         // Clear any active button repeat tasks for this controller
         self.button_repeats.retain(|(cid, _), _| *cid != id);
+        let (released, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.held)
+            .into_iter()
+            .partition(|(cid, _)| *cid == id);
+        self.held = kept;
+        released
+            .into_iter()
+            .map(|(controller, output)| output.release(controller))
+            .collect()
     }
 
     fn is_precision_active(&self, bindings: Option<&CompiledStickRules>) -> bool {
@@ -259,7 +465,10 @@ impl Padjutsu {
         })
     }
 
-    pub fn on_tick_with<F: FnMut(Effect)>(&mut self, sink: F) {
+    pub fn on_tick_with<F: FnMut(Effect)>(&mut self, now: Instant, sink: F) {
+        if self.paused {
+            return;
+        }
         let started_at = Instant::now();
         let bindings_owned = self.get_compiled_stick_rules().cloned();
         let precision = self.is_precision_active(bindings_owned.as_ref());
@@ -278,6 +487,7 @@ impl Padjutsu {
             precision
         );
         self.sticks.borrow_mut().on_tick_with(
+            now,
             bindings_owned.as_ref(),
             &self.axes_scratch,
             precision,
@@ -289,9 +499,9 @@ impl Padjutsu {
         );
     }
 
-    pub fn on_tick_effects(&mut self) -> Vec<Effect> {
+    pub fn on_tick_effects(&mut self, now: Instant) -> Vec<Effect> {
         let mut effects = Vec::new();
-        self.on_tick_with(|effect| effects.push(effect));
+        self.on_tick_with(now, |effect| effects.push(effect));
         effects
     }
 
@@ -363,6 +573,9 @@ impl Padjutsu {
     /// True when there are tick-requiring stick modes and some axis deviates from neutral,
     /// or when repeat tasks are active (to drain their timers).
     pub fn needs_tick(&self) -> bool {
+        if self.paused {
+            return false;
+        }
         let has_tick_modes = self.has_tick_modes();
         let has_axis_activity = self.has_axis_activity(0.05);
         let has_stick_repeats = self.sticks.borrow().has_active_repeats();
@@ -381,6 +594,9 @@ impl Padjutsu {
     /// Hint whether a faster tick would improve responsiveness.
     /// True when there is recent/ongoing axis activity or repeat tasks are active.
     pub fn wants_fast_tick(&self) -> bool {
+        if self.paused {
+            return false;
+        }
         let axis_activity = self.has_axis_activity(0.05);
         let stick_repeats = self.sticks.borrow().has_active_repeats();
         let button_repeats = self.has_active_button_repeats();
@@ -479,6 +695,7 @@ impl Padjutsu {
         id: ControllerId,
         button: Button,
         phase: ButtonPhase,
+        now: Instant,
         mut sink: F,
     ) {
         print_debug!("handle button - {id} {button:?} {phase:?}");
@@ -524,6 +741,8 @@ impl Padjutsu {
             id,
             button,
             rumble,
+            self.paused,
+            &self.active_app,
         );
 
         if transitions.is_empty() {
@@ -535,7 +754,7 @@ impl Padjutsu {
         }
 
         for transition in transitions {
-            self.apply_button_transition(id, transition, &mut sink);
+            self.apply_button_transition(id, transition, now, &mut sink);
         }
     }
 
@@ -544,12 +763,14 @@ impl Padjutsu {
         id: ControllerId,
         button: Button,
         phase: ButtonPhase,
+        now: Instant,
     ) -> Vec<Effect> {
         let mut effects = Vec::new();
-        self.on_button_with(id, button, phase, |effect| effects.push(effect));
+        self.on_button_with(id, button, phase, now, |effect| effects.push(effect));
         effects
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn resolve_button_transitions(
         button_rules: &padjutsu_workspace::ButtonRules,
         prev_pressed: Bitmask<Button>,
@@ -558,14 +779,20 @@ impl Padjutsu {
         id: ControllerId,
         button: Button,
         rumble: bool,
+        paused: bool,
+        app: &str,
     ) -> Vec<ButtonTransition> {
         let mut transitions = Vec::new();
+        // While paused only the rules that can resume stay live.
+        let live = |rule: &padjutsu_workspace::ButtonRule| {
+            !paused || matches!(rule.action, ButtonAction::ToggleEnabled { .. })
+        };
 
         if phase == ButtonPhase::Released {
             for (target, rule) in button_rules.iter() {
                 let was = prev_pressed.is_superset(target);
                 let is_now = now_pressed.is_superset(target);
-                if !was || is_now {
+                if !was || is_now || !live(rule) {
                     continue;
                 }
 
@@ -584,12 +811,20 @@ impl Padjutsu {
                         effects.push(Effect::RawModifierRelease(key));
                         ButtonRepeatDirective::None
                     }
+                    ButtonAction::ShellHold(_) => {
+                        effects.push(Effect::ShellStop(HeldShell {
+                            controller: id,
+                            chord: *target,
+                        }));
+                        ButtonRepeatDirective::None
+                    }
                     _ => ButtonRepeatDirective::None,
                 };
                 transitions.push(ButtonTransition {
                     target_button: button,
                     effects,
                     repeat,
+                    toggle: None,
                 });
             }
 
@@ -598,10 +833,10 @@ impl Padjutsu {
 
         // First pass: find max_bits among rules that should fire
         let mut max_bits: u32 = 0;
-        for (target, _rule) in button_rules.iter() {
+        for (target, rule) in button_rules.iter() {
             let was = prev_pressed.is_superset(target);
             let is_now = now_pressed.is_superset(target);
-            let fire = was != is_now;
+            let fire = was != is_now && live(rule);
             if fire {
                 let bits: u32 = target.count();
                 if bits > max_bits {
@@ -618,13 +853,14 @@ impl Padjutsu {
         for (target, rule) in button_rules.iter() {
             let was = prev_pressed.is_superset(target);
             let is_now = now_pressed.is_superset(target);
-            let fire = was != is_now;
+            let fire = was != is_now && live(rule);
             if !fire || target.count() != max_bits {
                 continue;
             }
             match phase {
                 ButtonPhase::Pressed => {
                     let mut effects = Vec::new();
+                    let mut toggle = None;
                     if let Some(ms) = rule.vibrate {
                         if rumble {
                             effects.push(Effect::Rumble { id, ms: ms as u32 });
@@ -655,9 +891,21 @@ impl Padjutsu {
                             effects.push(Effect::Macros(m));
                             ButtonRepeatDirective::None
                         }
-                        ButtonAction::Shell(s) => {
-                            print_debug!("shell command: {}", s);
-                            effects.push(Effect::Shell(s));
+                        ButtonAction::Shell(action) => {
+                            print_debug!("shell command: {}", action.command);
+                            let run = shell_run(action, app, target, id);
+                            effects.push(Effect::Shell(run));
+                            ButtonRepeatDirective::None
+                        }
+                        ButtonAction::ShellHold(action) => {
+                            print_debug!("shell_hold command: {}", action.command);
+                            effects.push(Effect::ShellStart {
+                                held: HeldShell {
+                                    controller: id,
+                                    chord: *target,
+                                },
+                                run: shell_run(action, app, target, id),
+                            });
                             ButtonRepeatDirective::None
                         }
                         ButtonAction::MouseClick { button, click_type } => {
@@ -672,11 +920,20 @@ impl Padjutsu {
                             effects.push(Effect::RawModifierPress(key));
                             ButtonRepeatDirective::None
                         }
+                        ButtonAction::Type { text, delay_ms } => {
+                            effects.push(Effect::Type { text, delay_ms });
+                            ButtonRepeatDirective::None
+                        }
+                        ButtonAction::ToggleEnabled { pause_ms } => {
+                            toggle = Some(PauseToggle { pause_ms });
+                            ButtonRepeatDirective::None
+                        }
                     };
                     transitions.push(ButtonTransition {
                         target_button: button,
                         effects,
                         repeat,
+                        toggle,
                     });
                 }
                 ButtonPhase::Released => unreachable!(),
@@ -690,11 +947,24 @@ impl Padjutsu {
         &mut self,
         id: ControllerId,
         transition: ButtonTransition,
+        now: Instant,
         sink: &mut F,
     ) {
         for effect in transition.effects {
+            self.track_held(id, &effect);
             sink(effect);
         }
+        if let Some(PauseToggle { pause_ms }) = transition.toggle {
+            if self.paused {
+                self.resume();
+            } else {
+                let until =
+                    pause_ms.map(|ms| now + std::time::Duration::from_millis(ms));
+                for effect in self.pause(until) {
+                    sink(effect);
+                }
+            }
+        }
 
         match transition.repeat {
             ButtonRepeatDirective::None => {}
@@ -708,8 +978,7 @@ impl Padjutsu {
                     ButtonRepeatTask {
                         key,
                         interval_ms,
-                        next_fire: Instant::now()
-                            + std::time::Duration::from_millis(delay_ms),
+                        next_fire: now + std::time::Duration::from_millis(delay_ms),
                         delay_done: false,
                     },
                 );
@@ -720,6 +989,20 @@ impl Padjutsu {
         }
     }
 
+    fn track_held(&mut self, id: ControllerId, effect: &Effect) {
+        if let Some(output) = HeldOutput::pressed_by(effect) {
+            self.held.push((id, output));
+        } else if let Some(output) = HeldOutput::released_by(effect) {
+            if let Some(pos) = self
+                .held
+                .iter()
+                .position(|(cid, held)| *cid == id && *held == output)
+            {
+                self.held.remove(pos);
+            }
+        }
+    }
+
     fn poll_button_repeats(&mut self, now: Instant) -> ButtonRepeatPoll {
         let mut poll = ButtonRepeatPoll::default();
 
@@ -759,3 +1042,20 @@ impl Padjutsu {
         }
     }
 }
+
+/// A shell action with the variables that tell it what triggered it.
+fn shell_run(
+    action: Arc<ShellAction>,
+    app: &str,
+    chord: &ButtonChord,
+    controller: ControllerId,
+) -> Box<ShellRun> {
+    Box::new(ShellRun {
+        action,
+        env: vec![
+            ("PADJUTSU_APP", app.to_string()),
+            ("PADJUTSU_CHORD", chord_name(chord)),
+            ("PADJUTSU_CONTROLLER", controller.to_string()),
+        ],
+    })
+}
@@ -22,7 +22,6 @@ impl StepperMode {
                     padjutsu_control::Key::VolumeDown
                 }
             }
-            #[cfg(target_os = "macos")]
             StepperMode::Brightness => {
                 if positive {
                     padjutsu_control::Key::BrightnessUp
@@ -30,10 +29,6 @@ impl StepperMode {
                     padjutsu_control::Key::BrightnessDown
                 }
             }
-            #[cfg(not(target_os = "macos"))]
-            StepperMode::Brightness => {
-                unimplemented!()
-            }
         }
     }
     pub(super) fn kind_for(
@@ -2,6 +2,7 @@ use colored::Colorize;
 use padjutsu_gamepad::ControllerId;
 use padjutsu_workspace::{Axis as ProfileAxis, StickMode, StickSide};
 
+use crate::app::padjutsu::ActiveRepeat;
 use crate::app::Effect;
 use crate::{print_debug, print_info};
 
@@ -97,6 +98,7 @@ impl StickProcessor {
 
     pub fn on_tick_with<F: FnMut(Effect)>(
         &mut self,
+        now: std::time::Instant,
         bindings: Option<&CompiledStickRules>,
         axes_list: &[(ControllerId, [f32; 6])],
         precision: bool,
@@ -109,8 +111,7 @@ impl StickProcessor {
             return;
         };
 
-        let now = std::time::Instant::now();
-        let started_at = now;
+        let started_at = std::time::Instant::now();
         let previous_tick_at = self.last_tick_at;
         let dt_s = self.tick_dt_s(now);
         let dt_us = previous_tick_at
@@ -172,8 +173,8 @@ impl StickProcessor {
             }).collect();
             print_info!(
                 "stick modes: left={:?} right={:?} has_scroll={} axes=[{}]",
-                bindings.left().map(|m| std::mem::discriminant(m)),
-                bindings.right().map(|m| std::mem::discriminant(m)),
+                bindings.left().map(std::mem::discriminant),
+                bindings.right().map(std::mem::discriminant),
                 has_scroll,
                 axes_dbg.join("; ")
             );
@@ -272,6 +273,39 @@ impl StickProcessor {
         false
     }
 
+    /// Every active stick repeat with its next due time.
+    pub(crate) fn active_repeats(&self) -> Vec<ActiveRepeat> {
+        let mut repeats = Vec::new();
+        for (cid, ctrl) in self.controllers.iter() {
+            for (side, state) in ["left", "right"].iter().zip(ctrl.sides.iter()) {
+                let slots = state
+                    .arrows
+                    .iter()
+                    .map(|slot| ("arrow", slot))
+                    .chain(state.volume.iter().map(|slot| ("volume", slot)))
+                    .chain(state.brightness.iter().map(|slot| ("brightness", slot)));
+                for (kind, slot) in slots {
+                    let Some(task) = slot else {
+                        continue;
+                    };
+                    let due_ms = if task.delay_done {
+                        task.interval_ms
+                    } else {
+                        task.initial_delay_ms
+                    };
+                    repeats.push(ActiveRepeat {
+                        controller: *cid,
+                        source: format!("{side} stick {kind}"),
+                        key: padjutsu_control::KeyCombo::from_key(task.key),
+                        next_fire: task.last_fire
+                            + std::time::Duration::from_millis(due_ms),
+                    });
+                }
+            }
+        }
+        repeats
+    }
+
     pub fn has_active_repeats_for(&self, id: ControllerId) -> bool {
         let Some(ctrl) = self.controllers.get(&id) else {
             return false;
@@ -514,6 +548,7 @@ impl StickProcessor {
         perf
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn tick_mouse_side(
         dt_s: f32,
         params: &padjutsu_workspace::MouseParams,
@@ -824,215 +859,3 @@ impl StickProcessor {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use padjutsu_workspace::{ScrollParams, ScrollRuntimeParams};
-
-    fn scroll_params(horizontal: bool, axis_lock: bool) -> ScrollParams {
-        ScrollParams {
-            deadzone: 0.15,
-            speed_lines_s: 120.0,
-            horizontal,
-            axis_lock,
-            invert_x: false,
-            invert_y: false,
-            runtime: ScrollRuntimeParams {
-                tick_ms: 4,
-                smoothing_window_ms: 25,
-                gamma: 1.5,
-                trigger_boost_max: 0.0,
-                trigger_boost_gamma: 1.5,
-            },
-        }
-    }
-
-    /// Create axes array with right stick values (indices 2=RightX, 3=RightY).
-    fn right_stick_axes(x: f32, y: f32) -> [f32; 6] {
-        [0.0, 0.0, x, y, 0.0, 0.0]
-    }
-
-    fn collect_scroll_effects(effects: &[Effect]) -> Vec<(f64, f64)> {
-        effects
-            .iter()
-            .filter_map(|e| match e {
-                Effect::Scroll { h, v } => Some((*h, *v)),
-                _ => None,
-            })
-            .collect()
-    }
-
-    /// Tick scroll multiple times with configurable dt.
-    fn tick_scroll_n_dt(
-        proc: &mut StickProcessor,
-        params: &ScrollParams,
-        axes: [f32; 6],
-        n: usize,
-        dt_s: f32,
-    ) -> Vec<Effect> {
-        let mut effects = Vec::new();
-        for _ in 0..n {
-            proc.tick_scroll_side(
-                1,
-                axes,
-                StickSide::Right,
-                params,
-                dt_s,
-                &mut |e| effects.push(e),
-            );
-        }
-        effects
-    }
-
-    /// Tick scroll multiple times with default dt (10ms).
-    fn tick_scroll_n(
-        proc: &mut StickProcessor,
-        params: &ScrollParams,
-        axes: [f32; 6],
-        n: usize,
-    ) -> Vec<Effect> {
-        tick_scroll_n_dt(proc, params, axes, n, 0.010)
-    }
-
-    // --- axis_lock tests ---
-
-    #[test]
-    fn axis_lock_locks_to_vertical_when_y_dominant() {
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(true, true);
-
-        // Push stick mostly down (y=0.8) with slight horizontal drift (x=0.1)
-        let effects = tick_scroll_n(&mut proc, &params, right_stick_axes(0.1, 0.8), 20);
-        let scrolls = collect_scroll_effects(&effects);
-
-        // Should have vertical scroll but NO horizontal scroll
-        assert!(!scrolls.is_empty(), "should produce scroll effects");
-        for (h, _v) in &scrolls {
-            assert_eq!(*h, 0.0, "horizontal scroll should be locked out");
-        }
-        assert!(
-            scrolls.iter().any(|(_, v)| *v != 0.0),
-            "should have vertical scroll"
-        );
-    }
-
-    #[test]
-    fn axis_lock_locks_to_horizontal_when_x_dominant() {
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(true, true);
-
-        // Push stick mostly right (x=0.8) with slight vertical drift (y=0.1)
-        let effects = tick_scroll_n(&mut proc, &params, right_stick_axes(0.8, 0.1), 20);
-        let scrolls = collect_scroll_effects(&effects);
-
-        assert!(!scrolls.is_empty(), "should produce scroll effects");
-        for (_h, v) in &scrolls {
-            assert_eq!(*v, 0.0, "vertical scroll should be locked out");
-        }
-        assert!(
-            scrolls.iter().any(|(h, _)| *h != 0.0),
-            "should have horizontal scroll"
-        );
-    }
-
-    #[test]
-    fn axis_lock_resets_when_stick_returns_to_deadzone() {
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(true, true);
-
-        // First: scroll vertically to lock axis
-        tick_scroll_n(&mut proc, &params, right_stick_axes(0.1, 0.8), 10);
-
-        // Return to deadzone for 200ms+ (each tick is 10ms, so 25 ticks = 250ms)
-        tick_scroll_n(&mut proc, &params, right_stick_axes(0.0, 0.0), 25);
-
-        // Now scroll horizontally — should lock to horizontal
-        let effects = tick_scroll_n(&mut proc, &params, right_stick_axes(0.8, 0.1), 20);
-        let scrolls = collect_scroll_effects(&effects);
-
-        assert!(!scrolls.is_empty(), "should produce scroll effects");
-        for (_h, v) in &scrolls {
-            assert_eq!(*v, 0.0, "vertical scroll should be locked out after reset");
-        }
-    }
-
-    #[test]
-    fn no_axis_lock_allows_both_axes() {
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(true, false); // horizontal=true, axis_lock=false
-
-        // Push stick diagonally
-        let effects = tick_scroll_n(&mut proc, &params, right_stick_axes(0.5, 0.5), 20);
-        let scrolls = collect_scroll_effects(&effects);
-
-        assert!(!scrolls.is_empty(), "should produce scroll effects");
-        let has_h = scrolls.iter().any(|(h, _)| *h != 0.0);
-        let has_v = scrolls.iter().any(|(_, v)| *v != 0.0);
-        assert!(has_h, "should have horizontal scroll without axis_lock");
-        assert!(has_v, "should have vertical scroll without axis_lock");
-    }
-
-    #[test]
-    fn scroll_works_with_small_dt() {
-        // Regression: with 2ms ticks (dt=0.002), the smoothing filter alpha is very small.
-        // If scroll_filtered is reset to zero in the deadzone branch, the filter can never
-        // ramp past the deadzone threshold in a single tick, causing scroll to never start.
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(false, false);
-
-        // Simulate 2ms ticks — 100 ticks = 200ms, plenty of time to ramp up
-        let effects = tick_scroll_n_dt(
-            &mut proc, &params, right_stick_axes(0.0, 0.8), 100, 0.002,
-        );
-        let scrolls = collect_scroll_effects(&effects);
-
-        assert!(
-            !scrolls.is_empty(),
-            "scroll must work with 2ms tick interval"
-        );
-        assert!(
-            scrolls.iter().any(|(_, v)| *v != 0.0),
-            "should have vertical scroll with small dt"
-        );
-    }
-
-    #[test]
-    fn scroll_survives_direction_change() {
-        // When changing direction (up→down), the stick briefly crosses through
-        // the deadzone. Scroll should resume without interruption.
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(false, false);
-
-        // Scroll down
-        let effects1 = tick_scroll_n(&mut proc, &params, right_stick_axes(0.0, 0.8), 20);
-        let scrolls1 = collect_scroll_effects(&effects1);
-        assert!(!scrolls1.is_empty(), "initial scroll should work");
-
-        // Brief pass through center (2 ticks = 20ms, simulates direction change)
-        tick_scroll_n(&mut proc, &params, right_stick_axes(0.0, 0.0), 2);
-
-        // Scroll up — should start quickly
-        let effects2 = tick_scroll_n(&mut proc, &params, right_stick_axes(0.0, -0.8), 20);
-        let scrolls2 = collect_scroll_effects(&effects2);
-        assert!(
-            !scrolls2.is_empty(),
-            "scroll must resume after direction change"
-        );
-    }
-
-    #[test]
-    fn horizontal_false_zeroes_x_axis() {
-        let mut proc = StickProcessor::new();
-        let params = scroll_params(false, false); // horizontal=false
-
-        // Push stick diagonally
-        let effects = tick_scroll_n(&mut proc, &params, right_stick_axes(0.5, 0.8), 20);
-        let scrolls = collect_scroll_effects(&effects);
-
-        assert!(!scrolls.is_empty(), "should produce scroll effects");
-        for (h, _v) in &scrolls {
-            assert_eq!(*h, 0.0, "horizontal scroll should be zero when horizontal=false");
-        }
-    }
-}
@@ -0,0 +1,563 @@
+//! Binding cheat sheets.
+//!
+//! Renders the bindings of a parsed [`Profile`] as a reference for people
+//! rather than the daemon: `common` first, then one section per set of
+//! apps that share the same additions and overrides on top of it. Apps
+//! whose rules came from one group end up in one section, so the sheet
+//! follows the profile's structure without reading its YAML.
+
+use std::fmt::Write as _;
+
+use padjutsu_gamepad::Button;
+use padjutsu_workspace::{
+    button_name, chord_name, AppRules, ButtonAction, ButtonChord, ButtonRule,
+    Profile, StickSide,
+};
+
+use crate::app::describe::{describe_action, summarize_stick};
+use crate::app::padjutsu::{DEFAULT_REPEAT_DELAY_MS, DEFAULT_REPEAT_INTERVAL_MS};
+use crate::cli::CheatsheetFormat;
+use crate::load_profile;
+
+const COMMON: &str = "common";
+
+/// One binding in a section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    /// A chord (`lt+a`) or a stick (`left stick`).
+    input: String,
+    action: String,
+    /// What `common` binds the same input to, when this row overrides it.
+    common: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    apps: Vec<String>,
+    buttons: Vec<Row>,
+    sticks: Vec<Row>,
+}
+
+impl Section {
+    fn title(&self) -> String {
+        if self.apps.is_empty() {
+            "Common".to_string()
+        } else {
+            self.apps.join(", ")
+        }
+    }
+
+    /// The action bound to `button` alone, if any.
+    fn single(&self, button: Button) -> Option<&str> {
+        let name = button_name(button);
+        self.buttons
+            .iter()
+            .find(|row| row.input == name)
+            .map(|row| row.action.as_str())
+    }
+}
+
+/// A button rule with the parameters that differ from the defaults.
+fn summarize_rule(rule: &ButtonRule) -> String {
+    let mut out = describe_action(&rule.action);
+    if let ButtonAction::Keystroke(_) = rule.action {
+        let delay = rule.repeat_delay_ms.unwrap_or(DEFAULT_REPEAT_DELAY_MS);
+        let interval = rule
+            .repeat_interval_ms
+            .unwrap_or(DEFAULT_REPEAT_INTERVAL_MS);
+        if (delay, interval) != (DEFAULT_REPEAT_DELAY_MS, DEFAULT_REPEAT_INTERVAL_MS)
+        {
+            let _ = write!(out, ", repeat after {delay}ms every {interval}ms");
+        }
+    }
+    if let Some(ms) = rule.vibrate {
+        let _ = write!(out, ", vibrate {ms}ms");
+    }
+    out
+}
+
+fn stick_input(side: StickSide) -> &'static str {
+    match side {
+        StickSide::Left => "left stick",
+        StickSide::Right => "right stick",
+    }
+}
+
+/// Buttons of a chord in declaration order, for sorting.
+fn chord_key(chord: &ButtonChord) -> (u32, Vec<usize>) {
+    let buttons = Button::ALL
+        .iter()
+        .enumerate()
+        .filter(|(_, button)| chord.contains(**button))
+        .map(|(i, _)| i)
+        .collect();
+    (chord.count(), buttons)
+}
+
+/// Rows of `rules`, leaving out those identical to `base`.
+fn rows(rules: &AppRules, base: Option<&AppRules>) -> (Vec<Row>, Vec<Row>) {
+    let mut chords: Vec<&ButtonChord> = rules.buttons.keys().collect();
+    chords.sort_by_key(|chord| chord_key(chord));
+    let buttons = chords
+        .into_iter()
+        .filter_map(|chord| {
+            let action = summarize_rule(&rules.buttons[chord]);
+            let common = base
+                .and_then(|base| base.buttons.get(chord))
+                .map(summarize_rule);
+            (common.as_ref() != Some(&action)).then(|| Row {
+                input: chord_name(chord),
+                action,
+                common,
+            })
+        })
+        .collect();
+
+    let sticks = [StickSide::Left, StickSide::Right]
+        .into_iter()
+        .filter_map(|side| {
+            let action = summarize_stick(rules.sticks.get(&side)?);
+            let common = base
+                .and_then(|base| base.sticks.get(&side))
+                .map(summarize_stick);
+            (common.as_ref() != Some(&action)).then(|| Row {
+                input: stick_input(side).to_string(),
+                action,
+                common,
+            })
+        })
+        .collect();
+    (buttons, sticks)
+}
+
+/// `common` followed by the apps, grouped by identical overrides.
+fn sections(profile: &Profile) -> Vec<Section> {
+    let common = profile.rules.get(COMMON);
+    let mut sections = Vec::new();
+    if let Some(common) = common {
+        let (buttons, sticks) = rows(common, None);
+        sections.push(Section {
+            apps: Vec::new(),
+            buttons,
+            sticks,
+        });
+    }
+
+    let mut apps: Vec<&str> = profile
+        .rules
+        .keys()
+        .map(|app| &**app)
+        .filter(|app| *app != COMMON && !profile.blacklist.contains(*app))
+        .collect();
+    apps.sort_unstable();
+    let mut by_app: Vec<Section> = Vec::new();
+    for app in apps {
+        let (buttons, sticks) = rows(&profile.rules[app], common);
+        if buttons.is_empty() && sticks.is_empty() {
+            continue;
+        }
+        match by_app
+            .iter_mut()
+            .find(|section| section.buttons == buttons && section.sticks == sticks)
+        {
+            Some(section) => section.apps.push(app.to_string()),
+            None => by_app.push(Section {
+                apps: vec![app.to_string()],
+                buttons,
+                sticks,
+            }),
+        }
+    }
+    sections.extend(by_app);
+    sections
+}
+
+fn blacklist(profile: &Profile) -> Vec<&str> {
+    let mut apps: Vec<&str> = profile.blacklist.iter().map(String::as_str).collect();
+    apps.sort_unstable();
+    apps
+}
+
+/// `text` inside a table cell: pipes would end the cell and newlines the row.
+fn markdown_cell(text: &str) -> String {
+    text.trim_end().replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// The sheet as Markdown: a table of bindings per section.
+pub(crate) fn markdown(profile: &Profile) -> String {
+    let mut out = String::from("# Controller bindings\n");
+    for section in sections(profile) {
+        let _ = writeln!(out, "\n## {}\n", section.title());
+        let overrides = !section.apps.is_empty();
+        if overrides {
+            out.push_str("In addition to or instead of common:\n\n");
+            out.push_str("| Input | Action | Common |\n|---|---|---|\n");
+        } else {
+            out.push_str("| Input | Action |\n|---|---|\n");
+        }
+        for row in section.buttons.iter().chain(&section.sticks) {
+            let _ =
+                write!(out, "| `{}` | {} |", row.input, markdown_cell(&row.action));
+            if overrides {
+                let common = row.common.as_deref().unwrap_or("");
+                let _ = write!(out, " {} |", markdown_cell(common));
+            }
+            out.push('\n');
+        }
+    }
+    let blacklist = blacklist(profile);
+    if !blacklist.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Disabled\n\nNo bindings in {}.",
+            blacklist.join(", ")
+        );
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Where a button sits on the diagram, and which side its label goes.
+struct Spot {
+    button: Button,
+    x: u32,
+    y: u32,
+    label_y: u32,
+}
+
+const LEFT_SPOTS: [Spot; 8] = [
+    Spot {
+        button: Button::LeftTrigger,
+        x: 350,
+        y: 48,
+        label_y: 40,
+    },
+    Spot {
+        button: Button::LeftShoulder,
+        x: 350,
+        y: 82,
+        label_y: 80,
+    },
+    Spot {
+        button: Button::Back,
+        x: 420,
+        y: 150,
+        label_y: 120,
+    },
+    Spot {
+        button: Button::LeftStick,
+        x: 360,
+        y: 160,
+        label_y: 160,
+    },
+    Spot {
+        button: Button::DPadUp,
+        x: 405,
+        y: 212,
+        label_y: 200,
+    },
+    Spot {
+        button: Button::DPadLeft,
+        x: 385,
+        y: 232,
+        label_y: 240,
+    },
+    Spot {
+        button: Button::DPadRight,
+        x: 425,
+        y: 232,
+        label_y: 280,
+    },
+    Spot {
+        button: Button::DPadDown,
+        x: 405,
+        y: 252,
+        label_y: 320,
+    },
+];
+
+const RIGHT_SPOTS: [Spot; 8] = [
+    Spot {
+        button: Button::RightTrigger,
+        x: 550,
+        y: 48,
+        label_y: 40,
+    },
+    Spot {
+        button: Button::RightShoulder,
+        x: 550,
+        y: 82,
+        label_y: 80,
+    },
+    Spot {
+        button: Button::Start,
+        x: 480,
+        y: 150,
+        label_y: 120,
+    },
+    Spot {
+        button: Button::Y,
+        x: 545,
+        y: 135,
+        label_y: 160,
+    },
+    Spot {
+        button: Button::X,
+        x: 525,
+        y: 157,
+        label_y: 200,
+    },
+    Spot {
+        button: Button::B,
+        x: 565,
+        y: 157,
+        label_y: 240,
+    },
+    Spot {
+        button: Button::A,
+        x: 545,
+        y: 179,
+        label_y: 280,
+    },
+    Spot {
+        button: Button::RightStick,
+        x: 495,
+        y: 232,
+        label_y: 320,
+    },
+];
+
+/// Between Back and Start, labelled above the controller.
+const GUIDE_SPOT: Spot = Spot {
+    button: Button::Guide,
+    x: 450,
+    y: 125,
+    label_y: 20,
+};
+
+/// Longest diagram label that stays clear of the controller; the table
+/// has the full text.
+const LABEL_CHARS: usize = 40;
+
+fn clip(text: &str) -> String {
+    if text.chars().count() <= LABEL_CHARS {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(LABEL_CHARS - 1).collect();
+    clipped.push('…');
+    clipped
+}
+
+/// A controller diagram labelled with the section's single-button bindings.
+fn svg(section: &Section) -> String {
+    let mut out = String::from(
+        "<svg viewBox=\"0 0 900 350\" xmlns=\"http://www.w3.org/2000/svg\">\n\
+         <rect class=\"body\" x=\"320\" y=\"95\" width=\"260\" height=\"190\" rx=\"80\"/>\n",
+    );
+    let sides = [
+        (&LEFT_SPOTS[..], 20, "start"),
+        (&RIGHT_SPOTS[..], 880, "end"),
+    ];
+    let center = [(std::slice::from_ref(&GUIDE_SPOT), GUIDE_SPOT.x, "middle")];
+    for (spots, label_x, anchor) in sides.into_iter().chain(center) {
+        for spot in spots {
+            let name = button_name(spot.button);
+            let bound = section.single(spot.button);
+            let class = if bound.is_some() { "bound" } else { "free" };
+            let _ = writeln!(
+                out,
+                "<circle class=\"{class}\" cx=\"{}\" cy=\"{}\" r=\"9\"><title>{name}</title></circle>",
+                spot.x, spot.y
+            );
+            let Some(action) = bound else { continue };
+            let _ = writeln!(
+                out,
+                "<line x1=\"{label_x}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n\
+                 <text x=\"{label_x}\" y=\"{}\" text-anchor=\"{anchor}\"><tspan class=\"name\">{name}</tspan> {}</text>",
+                spot.label_y + 4,
+                spot.x,
+                spot.y,
+                spot.label_y,
+                escape_html(&clip(action)),
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+const STYLE: &str = "\
+body { font: 14px system-ui, sans-serif; margin: 2em auto; max-width: 960px; color: #222; }
+table { border-collapse: collapse; margin: 1em 0 2em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+code { font-size: 13px; }
+svg { width: 100%; }
+svg .body { fill: #eee; stroke: #999; }
+svg circle.free { fill: #fff; stroke: #999; }
+svg circle.bound { fill: #4a7bd0; }
+svg line { stroke: #bbb; }
+svg text { font-size: 12px; }
+svg .name { font-weight: bold; }
+";
+
+/// The sheet as a standalone HTML page with a controller diagram per
+/// section.
+pub(crate) fn html(profile: &Profile) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Controller bindings</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Controller bindings</h1>\n"
+    );
+    for section in sections(profile) {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(&section.title()));
+        out.push_str(&svg(&section));
+        let overrides = !section.apps.is_empty();
+        out.push_str("<table>\n<tr><th>Input</th><th>Action</th>");
+        if overrides {
+            out.push_str("<th>Common</th>");
+        }
+        out.push_str("</tr>\n");
+        for row in section.buttons.iter().chain(&section.sticks) {
+            let _ = write!(
+                out,
+                "<tr><td><code>{}</code></td><td>{}</td>",
+                escape_html(&row.input),
+                escape_html(&row.action)
+            );
+            if overrides {
+                let common = row.common.as_deref().unwrap_or("");
+                let _ = write!(out, "<td>{}</td>", escape_html(common));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+    let blacklist = blacklist(profile);
+    if !blacklist.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Disabled</h2>\n<p>No bindings in {}.</p>",
+            escape_html(&blacklist.join(", "))
+        );
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Render the workspace profile's cheat sheet.
+pub(crate) fn run(
+    workspace: Option<&str>,
+    format: CheatsheetFormat,
+) -> Result<String, String> {
+    let (_, profile) = load_profile(workspace)?;
+    Ok(match format {
+        CheatsheetFormat::Markdown => markdown(&profile),
+        CheatsheetFormat::Html => html(&profile),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use padjutsu_workspace::parse_profile;
+
+    use super::*;
+
+    const PROFILE: &str = r#"
+version: 1
+groups:
+  messaging: [com.tinyspeck.slackmacgap, ru.keepcoder.Telegram]
+blacklist: [com.apple.Terminal]
+rules:
+  common:
+    buttons:
+      a:
+        tap: enter
+      lt+dpad_left:
+        keystroke: cmd+shift+[
+        repeat_delay_ms: 300
+        repeat_interval_ms: 120
+      rt+dpad_left:
+        shell: aerospace focus left | cat
+    sticks:
+      left:
+        mode: mouse_move
+        precision_button: lt
+  $messaging:
+    buttons:
+      lt+dpad_left:
+        keystroke: ctrl+shift+tab
+      b:
+        tap: escape
+        vibrate: 20
+      x:
+        shell: |
+          open -a Slack
+          echo done
+  com.figma.Desktop:
+    buttons:
+      a:
+        tap: enter
+"#;
+
+    #[test]
+    fn markdown_groups_apps_with_the_same_overrides() {
+        let profile = parse_profile(PROFILE).unwrap();
+        assert_eq!(
+            markdown(&profile),
+            "\
+# Controller bindings
+
+## Common
+
+| Input | Action |
+|---|---|
+| `a` | tap enter |
+| `lt+dpad_left` | keystroke shift+cmd+bracket_left, repeat after 300ms every 120ms |
+| `rt+dpad_left` | shell aerospace focus left \\| cat |
+| `left stick` | mouse_move, hold lt for precision |
+
+## com.tinyspeck.slackmacgap, ru.keepcoder.Telegram
+
+In addition to or instead of common:
+
+| Input | Action | Common |
+|---|---|---|
+| `b` | tap escape, vibrate 20ms |  |
+| `x` | shell open -a Slack<br>echo done |  |
+| `lt+dpad_left` | keystroke ctrl+shift+tab | keystroke shift+cmd+bracket_left, repeat after 300ms every 120ms |
+
+## Disabled
+
+No bindings in com.apple.Terminal.
+"
+        );
+    }
+
+    #[test]
+    fn html_labels_single_buttons_on_the_diagram() {
+        let profile = parse_profile(PROFILE).unwrap();
+        let page = html(&profile);
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert_eq!(page.matches("<svg ").count(), 2);
+        assert!(page.contains("<tspan class=\"name\">a</tspan> tap enter</text>"));
+        // Every button has a spot, bound or not.
+        assert_eq!(page.matches("<circle ").count(), 2 * Button::ALL.len());
+        assert!(page.contains("<td>shell aerospace focus left | cat</td>"));
+        assert!(page
+            .contains("<h2>com.tinyspeck.slackmacgap, ru.keepcoder.Telegram</h2>"));
+        assert!(!page.contains("com.figma.Desktop"));
+    }
+}
@@ -1,5 +1,18 @@
 use clap::Parser;
 use clap::Subcommand;
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+/// Where synthesized keyboard and mouse input goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub(crate) enum OutputBackendKind {
+    /// The platform input API via enigo (Quartz, X11/libei, ...).
+    #[default]
+    Enigo,
+    /// Virtual kernel devices via /dev/uinput; works on X11 and Wayland.
+    #[cfg(target_os = "linux")]
+    Uinput,
+}
 
 #[derive(Debug, Subcommand, PartialEq)]
 pub(crate) enum ControlCommand {
@@ -12,6 +25,105 @@ pub(crate) enum ControlCommand {
         #[clap(short, long)]
         ms: u32,
     },
+    /// Feed controller input to the daemon as if a controller sent it
+    Inject {
+        /// The controller to inject into; the daemon's virtual one if omitted
+        #[clap(short, long)]
+        id: Option<u32>,
+        /// The input to inject
+        #[clap(subcommand)]
+        input: InjectArg,
+    },
+    /// Stop mapping controller input until resumed
+    Pause {
+        /// Resume by itself after this many milliseconds
+        #[clap(long)]
+        for_ms: Option<u64>,
+    },
+    /// Resume mapping controller input
+    Resume,
+    /// Pause if mapping is enabled, resume if paused
+    Toggle,
+    /// Re-read the profile now; fails if it doesn't parse
+    Reload,
+}
+
+/// Controller input to inject, named as in profiles.
+#[derive(Debug, Subcommand, PartialEq)]
+pub(crate) enum InjectArg {
+    /// Press a button, e.g. `a` or `lt`
+    Press { button: String },
+    /// Release a button
+    Release { button: String },
+    /// Move an axis, e.g. `left_x 0.5`
+    Axis {
+        /// One of left_x, left_y, right_x, right_y, left_trigger, right_trigger
+        axis: String,
+        /// The position in [-1, 1]
+        #[clap(allow_negative_numbers = true)]
+        value: f32,
+    },
+}
+
+/// A stick side, as in profiles.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum StickArg {
+    Left,
+    Right,
+}
+
+/// A kind of event `observe` can show.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum ObserveKind {
+    /// Buttons, axes, connects and disconnects.
+    Controller,
+    /// Frontmost application changes.
+    App,
+    /// Profile loads, removals and errors.
+    Profile,
+    /// Keys, clicks, mouse motion and other output the daemon emits.
+    Effects,
+}
+
+/// The format of a binding cheat sheet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub(crate) enum CheatsheetFormat {
+    #[default]
+    Markdown,
+    /// A standalone page with a controller diagram per section.
+    Html,
+}
+
+/// Where controller input comes from.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum InputBackendKind {
+    /// SDL2 game controller API.
+    #[cfg(feature = "sdl2-backend")]
+    Sdl2,
+    /// Linux /dev/input/event* devices, read directly.
+    #[cfg(target_os = "linux")]
+    Evdev,
+}
+
+impl Default for InputBackendKind {
+    /// SDL2 when built in, evdev otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "sdl2-backend")]
+        return Self::Sdl2;
+        #[cfg(not(feature = "sdl2-backend"))]
+        return Self::Evdev;
+    }
+}
+
+impl From<InputBackendKind> for padjutsu_gamepad::Backend {
+    fn from(kind: InputBackendKind) -> Self {
+        match kind {
+            #[cfg(feature = "sdl2-backend")]
+            InputBackendKind::Sdl2 => Self::Sdl2,
+            #[cfg(target_os = "linux")]
+            InputBackendKind::Evdev => Self::Evdev,
+        }
+    }
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -22,19 +134,118 @@ pub(crate) enum Command {
         /// The profile to run
         #[clap(short, long)]
         workspace: Option<String>,
+        /// The output backend for synthesized input
+        #[clap(long, value_enum, default_value_t)]
+        output: OutputBackendKind,
+        /// The backend controllers are read from
+        #[clap(long, value_enum, default_value_t)]
+        input: InputBackendKind,
+        /// Also serve the control API over HTTP on 127.0.0.1:PORT
+        #[clap(long, value_name = "PORT")]
+        http_port: Option<u16>,
     },
     /// Start daemon in the background.
     Start {
         /// The directory containing the profile
         #[clap(short, long)]
         workspace: Option<String>,
+        /// Also serve the control API over HTTP on 127.0.0.1:PORT
+        #[clap(long, value_name = "PORT")]
+        http_port: Option<u16>,
     },
     /// Stop the daemon.
     Stop,
-    /// Show the status of the daemon.
-    Status,
-    /// Observe the daemon's events.
-    Observe,
+    /// Show the status of the daemon, with its live state when it answers
+    /// on the control socket.
+    Status {
+        /// The directory containing the profile
+        #[clap(short, long)]
+        workspace: Option<String>,
+    },
+    /// Stream the running daemon's events.
+    Observe {
+        /// The workspace of the daemon to observe
+        #[clap(short, long)]
+        workspace: Option<String>,
+        /// Only events of this controller
+        #[clap(short, long)]
+        controller: Option<u32>,
+        /// Only these kinds of events; may be repeated
+        #[clap(short, long, value_enum)]
+        kind: Vec<ObserveKind>,
+        /// Print one JSON object per line
+        #[clap(long)]
+        json: bool,
+    },
+    /// Record controller input and app switches to a session file.
+    Record {
+        /// The session file to write (JSON lines)
+        #[clap(short, long)]
+        output: PathBuf,
+        /// The backend controllers are read from
+        #[clap(long, value_enum, default_value_t)]
+        input: InputBackendKind,
+    },
+    /// Replay a recorded session against a profile.
+    Replay {
+        /// The session file to replay
+        session: PathBuf,
+        /// The workspace directory or profile file to replay against
+        #[clap(short, long)]
+        workspace: Option<String>,
+        /// Playback speed relative to the recording, with `--output`
+        #[clap(long, default_value_t = 1.0)]
+        speed: f64,
+        /// Post the effects as real input through this backend instead of
+        /// printing them
+        #[clap(long, value_enum)]
+        output: Option<OutputBackendKind>,
+    },
+    /// Print the effects a profile produces for a script of timed inputs.
+    Simulate {
+        /// The input script, or `-` for stdin
+        script: PathBuf,
+        /// The workspace directory or profile file to simulate
+        #[clap(short, long)]
+        workspace: Option<String>,
+        /// The bundle ID of the frontmost application
+        #[clap(short, long)]
+        app: Option<String>,
+    },
+    /// Run the binding tests in the profile's `tests` section.
+    TestProfile {
+        /// The workspace directory or profile file to test
+        #[clap(short, long)]
+        workspace: Option<String>,
+    },
+    /// Show which rule fires for a chord or stick, where it comes from and
+    /// what it shadows.
+    Explain {
+        /// The workspace directory or profile file to inspect
+        #[clap(short, long)]
+        workspace: Option<String>,
+        /// The bundle ID of the frontmost application
+        #[clap(short, long)]
+        app: Option<String>,
+        /// The chord to explain, e.g. `lt+a`
+        #[clap(short, long, required_unless_present = "stick")]
+        buttons: Option<String>,
+        /// The stick to explain
+        #[clap(short, long, value_enum, conflicts_with = "buttons")]
+        stick: Option<StickArg>,
+    },
+    /// Write a reference of the profile's bindings per app.
+    Cheatsheet {
+        /// The workspace directory or profile file to describe
+        #[clap(short, long)]
+        workspace: Option<String>,
+        /// The output format
+        #[clap(short, long, value_enum, default_value_t)]
+        format: CheatsheetFormat,
+        /// The file to write; stdout if omitted
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
     /// Send a command to the daemon.
     Command {
         /// The workspace to send the command to
@@ -1,7 +1,10 @@
+use padjutsu_workspace::HookEvent;
+
 use crate::activity::ActivityEvent;
 use crate::app::Padjutsu;
-use crate::domain::{DomainStep, WakeTransition};
+use crate::domain::{push_hook, DomainStep, WakeTransition};
 
+#[cfg_attr(not(target_os = "macos"), allow(irrefutable_let_patterns))]
 pub fn reduce_activity_event(
     activity_event: ActivityEvent,
     step: &mut DomainStep,
@@ -10,7 +13,15 @@ pub fn reduce_activity_event(
     let ActivityEvent::DidActivateApplication(bundle_id) = activity_event else {
         return;
     };
+    let previous = padjutsu.active_app().to_string();
     padjutsu.set_active_app(&bundle_id);
+    if previous != bundle_id {
+        let env = vec![
+            ("PADJUTSU_APP", bundle_id),
+            ("PADJUTSU_PREVIOUS_APP", previous),
+        ];
+        push_hook(step, padjutsu, HookEvent::AppChanged, env);
+    }
     step.transition.shell = Some(crate::domain::ShellTransition::Set(
         padjutsu.current_shell(),
     ));
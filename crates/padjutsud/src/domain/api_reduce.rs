@@ -1,29 +1,689 @@
-use padjutsu_gamepad::ControllerManager;
+use std::time::{Duration, Instant};
 
-use crate::api::Command as ApiCommand;
-use crate::app::Effect;
-use crate::domain::DomainStep;
+use padjutsu_gamepad::{
+    Button, ControllerEvent, ControllerId, ControllerInfo, ControllerManager,
+};
+use padjutsu_workspace::{button_name, chord_name, parse_button};
+
+use crate::api::{
+    parse_axis, ApiReply, ApiRequest, Command as ApiCommand, ControllerStatus,
+    HeldStatus, InjectedInput, OutputStatus, RemoteError, RepeatStatus, Response,
+    StatusReport, StickStatus, Subscriber,
+};
+use crate::app::padjutsu::HeldOutput;
+use crate::app::{Effect, Padjutsu};
+use crate::domain::stick_state::StickState;
+use crate::domain::{
+    reload_profile, DomainStep, ReplyTransition, RuntimeState, WakeTransition,
+};
+
+/// The daemon's own controller, fed only by [`ApiCommand::Inject`]. Backends
+/// count their ids up from zero, so the top id stays free.
+pub const INJECTED_CONTROLLER_ID: ControllerId = ControllerId::MAX;
+const INJECTED_CONTROLLER_NAME: &str = "Injected";
 
 pub fn reduce_api_command(
-    command: ApiCommand,
+    request: ApiRequest,
     step: &mut DomainStep,
+    padjutsu: &mut Padjutsu,
     manager: &ControllerManager,
+    runtime_state: &RuntimeState,
+    now: Instant,
 ) {
-    match command {
-        ApiCommand::Rumble { id, ms } => match id {
-            Some(controller_id) => {
-                step.transition.effects.push(Effect::Rumble {
-                    id: controller_id,
-                    ms,
-                });
+    let ApiRequest {
+        command,
+        reply,
+        events,
+    } = request;
+    let result = match command {
+        ApiCommand::Rumble { id, ms } => rumble(id, ms, step, manager),
+        ApiCommand::Status => Ok(Response::Status(status(
+            padjutsu,
+            manager,
+            runtime_state,
+            now,
+        ))),
+        ApiCommand::Subscribe(filter) => match events {
+            Some(events) => {
+                step.transition
+                    .subscriptions
+                    .push(Subscriber { filter, events });
+                Ok(Response::Subscribed)
             }
-            None => {
-                for info in manager.controllers() {
-                    step.transition
-                        .effects
-                        .push(Effect::Rumble { id: info.id, ms });
-                }
+            None => Err(RemoteError::Malformed(
+                "subscription without an event channel".into(),
+            )),
+        },
+        ApiCommand::Inject { controller, input } => {
+            inject(controller, input, step, padjutsu)
+        }
+        ApiCommand::Pause { for_ms } => {
+            pause(for_ms, step, padjutsu, now);
+            Ok(enabled(padjutsu, now))
+        }
+        ApiCommand::Resume => {
+            padjutsu.resume();
+            step.transition.wake.push(WakeTransition::Reschedule);
+            Ok(enabled(padjutsu, now))
+        }
+        ApiCommand::Toggle => {
+            if padjutsu.is_paused() {
+                padjutsu.resume();
+                step.transition.wake.push(WakeTransition::Reschedule);
+            } else {
+                pause(None, step, padjutsu, now);
             }
+            Ok(enabled(padjutsu, now))
+        }
+        ApiCommand::Reload => reload_profile(step, padjutsu, now)
+            .map(|()| Response::Reloaded)
+            .map_err(RemoteError::ProfileInvalid),
+    };
+    step.transition.replies.push(ReplyTransition {
+        to: reply,
+        reply: result,
+    });
+}
+
+fn rumble(
+    id: Option<padjutsu_gamepad::ControllerId>,
+    ms: u32,
+    step: &mut DomainStep,
+    manager: &ControllerManager,
+) -> ApiReply {
+    let connected: Vec<_> =
+        manager.controllers().iter().map(|info| info.id).collect();
+    let controllers = match id {
+        Some(controller_id) if connected.contains(&controller_id) => {
+            vec![controller_id]
+        }
+        Some(controller_id) => {
+            return Err(RemoteError::UnknownController(controller_id))
+        }
+        None if connected.is_empty() => return Err(RemoteError::NoControllers),
+        None => connected,
+    };
+    for &id in &controllers {
+        step.transition.effects.push(Effect::Rumble { id, ms });
+    }
+    Ok(Response::Rumbled { controllers })
+}
+
+fn pause(
+    for_ms: Option<u64>,
+    step: &mut DomainStep,
+    padjutsu: &mut Padjutsu,
+    now: Instant,
+) {
+    let until = for_ms.map(|ms| now + Duration::from_millis(ms));
+    step.transition.effects.extend(padjutsu.pause(until));
+    step.transition.wake.push(WakeTransition::Reschedule);
+}
+
+fn resumes_in_ms(padjutsu: &Padjutsu, now: Instant) -> Option<u64> {
+    padjutsu
+        .paused_until()
+        .map(|until| until.saturating_duration_since(now).as_millis() as u64)
+}
+
+fn enabled(padjutsu: &Padjutsu, now: Instant) -> Response {
+    Response::Enabled {
+        enabled: !padjutsu.is_paused(),
+        resumes_in_ms: resumes_in_ms(padjutsu, now),
+    }
+}
+
+fn inject(
+    controller: Option<ControllerId>,
+    input: InjectedInput,
+    step: &mut DomainStep,
+    padjutsu: &Padjutsu,
+) -> ApiReply {
+    let id = controller.unwrap_or(INJECTED_CONTROLLER_ID);
+    let button = |name: &str| {
+        parse_button(name).ok_or_else(|| {
+            RemoteError::Malformed(format!("unknown button `{name}`"))
+        })
+    };
+    let event = match input {
+        InjectedInput::Press(name) => ControllerEvent::ButtonPressed {
+            id,
+            button: button(&name)?,
         },
+        InjectedInput::Release(name) => ControllerEvent::ButtonReleased {
+            id,
+            button: button(&name)?,
+        },
+        InjectedInput::Axis { axis, value } => {
+            let axis = parse_axis(&axis).ok_or_else(|| {
+                RemoteError::Malformed(format!("unknown axis `{axis}`"))
+            })?;
+            if !(-1.0..=1.0).contains(&value) {
+                return Err(RemoteError::Malformed(format!(
+                    "axis value {value} is outside [-1, 1]"
+                )));
+            }
+            ControllerEvent::AxisMotion { id, axis, value }
+        }
+    };
+    if !padjutsu.is_known(id) {
+        if controller.is_some() {
+            return Err(RemoteError::UnknownController(id));
+        }
+        step.transition
+            .injected
+            .push(ControllerEvent::Connected(ControllerInfo {
+                id,
+                name: INJECTED_CONTROLLER_NAME.into(),
+                supports_rumble: false,
+                vendor_id: 0,
+                product_id: 0,
+            }));
+    }
+    step.transition.injected.push(event);
+    Ok(Response::Injected { controller: id })
+}
+
+fn stick_status(state: StickState) -> StickStatus {
+    StickStatus {
+        mode: state.mode,
+        activity: state.activity,
+    }
+}
+
+fn describe_held(output: &HeldOutput) -> String {
+    match output {
+        HeldOutput::Key(combo) => format!("key {combo}"),
+        HeldOutput::Mouse(button) => format!("mouse {button:?}"),
+        HeldOutput::RawModifier(key) => format!("raw_modifier {key:?}"),
+        HeldOutput::Shell(chord) => format!("shell_hold {}", chord_name(chord)),
+    }
+}
+
+pub(crate) fn status(
+    padjutsu: &Padjutsu,
+    manager: &ControllerManager,
+    runtime_state: &RuntimeState,
+    now: Instant,
+) -> StatusReport {
+    let mut named: Vec<(ControllerId, String)> = manager
+        .controllers()
+        .into_iter()
+        .map(|info| (info.id, info.name))
+        .collect();
+    if padjutsu.is_known(INJECTED_CONTROLLER_ID) {
+        named.push((INJECTED_CONTROLLER_ID, INJECTED_CONTROLLER_NAME.into()));
+    }
+    let controllers = named
+        .into_iter()
+        .map(|(id, name)| {
+            let state = runtime_state.controller_state(id);
+            let pressed = padjutsu.pressed_buttons(id);
+            ControllerStatus {
+                id,
+                name,
+                mode: state.map(|state| state.mode()),
+                left_stick: state.map(|state| stick_status(state.left_stick())),
+                right_stick: state.map(|state| stick_status(state.right_stick())),
+                pressed: Button::ALL
+                    .into_iter()
+                    .filter(|button| pressed.contains(*button))
+                    .map(|button| button_name(button).to_string())
+                    .collect(),
+            }
+        })
+        .collect();
+    StatusReport {
+        mode: runtime_state.mode(),
+        paused: padjutsu.is_paused(),
+        resumes_in_ms: resumes_in_ms(padjutsu, now),
+        active_app: padjutsu.active_app().to_string(),
+        binding: padjutsu.binding_source(),
+        profile_path: padjutsu
+            .profile_path()
+            .map(|path| path.display().to_string()),
+        profile_age_ms: padjutsu
+            .profile_loaded_at()
+            .map(|at| now.saturating_duration_since(at).as_millis() as u64),
+        controllers,
+        held: padjutsu
+            .held_outputs()
+            .iter()
+            .map(|(controller, output)| HeldStatus {
+                controller: *controller,
+                output: describe_held(output),
+            })
+            .collect(),
+        repeats: padjutsu
+            .active_repeats()
+            .into_iter()
+            .map(|repeat| RepeatStatus {
+                controller: repeat.controller,
+                source: repeat.source,
+                key: repeat.key.to_string(),
+                next_in_ms: repeat
+                    .next_fire
+                    .saturating_duration_since(now)
+                    .as_millis() as u64,
+            })
+            .collect(),
+        output: runtime_state.output_stats().map(OutputStatus::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::bounded;
+    use padjutsu_gamepad::Backend;
+
+    use crate::app::ButtonPhase;
+    use crate::domain::{ControllerMode, RuntimeMode};
+
+    use super::*;
+
+    fn request(command: ApiCommand) -> ApiRequest {
+        let (reply, _) = bounded(1);
+        ApiRequest {
+            command,
+            reply,
+            events: None,
+        }
+    }
+
+    #[test]
+    fn rumble_replies_with_the_controllers_it_reached() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let mut padjutsu = Padjutsu::new();
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Rumble { id: None, ms: 30 }),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            Instant::now(),
+        );
+        assert!(step.transition.effects.is_empty());
+        assert_eq!(
+            step.transition.replies[0].reply,
+            Err(RemoteError::NoControllers)
+        );
+
+        let pad = manager.connect_virtual("Virtual").unwrap();
+        let id = pad.id();
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Rumble {
+                id: Some(id + 1),
+                ms: 30,
+            }),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            Instant::now(),
+        );
+        assert_eq!(
+            step.transition.replies[0].reply,
+            Err(RemoteError::UnknownController(id + 1))
+        );
+
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Rumble { id: None, ms: 30 }),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            Instant::now(),
+        );
+        assert!(matches!(
+            step.transition.effects[..],
+            [Effect::Rumble { ms: 30, .. }]
+        ));
+        assert_eq!(
+            step.transition.replies[0].reply,
+            Ok(Response::Rumbled {
+                controllers: vec![id]
+            })
+        );
+    }
+
+    #[test]
+    fn status_reports_live_state() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let pad = manager.connect_virtual("Virtual").unwrap();
+        let id = pad.id();
+        let info = manager.controllers().remove(0);
+        let mut padjutsu = Padjutsu::new();
+        padjutsu.set_workspace(
+            padjutsu_workspace::parse_profile(
+                r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: enter
+      b:
+        hold: shift
+"#,
+            )
+            .unwrap(),
+        );
+        let loaded_at = Instant::now();
+        padjutsu.set_profile_loaded_at(loaded_at);
+        padjutsu.add_controller(info);
+        padjutsu.on_button_effects(id, Button::A, ButtonPhase::Pressed, loaded_at);
+        padjutsu.on_button_effects(id, Button::B, ButtonPhase::Pressed, loaded_at);
+        let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let state = crate::domain::resolve_controller_state(&padjutsu, id);
+        runtime_state.set_controller_state(id, state);
+        runtime_state.set_output_stats(padjutsu_control::WorkerStats {
+            lossless_depth: 2,
+            lossless_peak: 5,
+            motion_dropped: 1,
+            ..Default::default()
+        });
+
+        let mut step = DomainStep::continue_();
+        let now = loaded_at + std::time::Duration::from_millis(100);
+        reduce_api_command(
+            request(ApiCommand::Status),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            now,
+        );
+        let Ok(Response::Status(report)) = &step.transition.replies[0].reply else {
+            panic!("unexpected reply {:?}", step.transition.replies[0].reply);
+        };
+        assert_eq!(report.mode, RuntimeMode::Active);
+        assert_eq!(report.binding, crate::app::binding::BindingSource::Common);
+        assert_eq!(report.profile_age_ms, Some(100));
+        assert_eq!(report.controllers.len(), 1);
+        assert_eq!(report.controllers[0].pressed, ["a", "b"]);
+        assert_eq!(
+            report.controllers[0].mode,
+            Some(ControllerMode::RepeatingWithInput)
+        );
+        assert_eq!(
+            report.held,
+            [HeldStatus {
+                controller: id,
+                output: "key shift".into(),
+            }]
+        );
+        assert_eq!(report.repeats.len(), 1);
+        assert_eq!(report.repeats[0].source, "button a");
+        assert_eq!(report.repeats[0].key, "enter");
+        assert_eq!(report.repeats[0].next_in_ms, 300);
+        assert_eq!(
+            report.output,
+            Some(OutputStatus {
+                queued: 2,
+                queued_peak: 5,
+                motion_queued: 0,
+                motion_dropped: 1,
+                lost: 0,
+            })
+        );
+
+        padjutsu.on_button_effects(id, Button::B, ButtonPhase::Released, now);
+        assert!(padjutsu.held_outputs().is_empty());
+    }
+
+    #[test]
+    fn subscribe_registers_the_connection_event_channel() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let mut padjutsu = Padjutsu::new();
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let filter = crate::api::FeedFilter::default();
+
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Subscribe(filter.clone())),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            Instant::now(),
+        );
+        assert!(step.transition.subscriptions.is_empty());
+        assert!(matches!(
+            step.transition.replies[0].reply,
+            Err(RemoteError::Malformed(_))
+        ));
+
+        let (events, _) = bounded(1);
+        let (reply, _) = bounded(1);
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            ApiRequest {
+                command: ApiCommand::Subscribe(filter),
+                reply,
+                events: Some(events),
+            },
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            Instant::now(),
+        );
+        assert_eq!(step.transition.subscriptions.len(), 1);
+        assert_eq!(step.transition.replies[0].reply, Ok(Response::Subscribed));
+    }
+
+    #[test]
+    fn inject_connects_the_virtual_controller_once() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let mut padjutsu = Padjutsu::new();
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let inject = |padjutsu: &mut Padjutsu, controller, input| {
+            let mut step = DomainStep::continue_();
+            reduce_api_command(
+                request(ApiCommand::Inject { controller, input }),
+                &mut step,
+                padjutsu,
+                &manager,
+                &runtime_state,
+                Instant::now(),
+            );
+            step.transition
+        };
+
+        let transition =
+            inject(&mut padjutsu, None, InjectedInput::Press("a".into()));
+        assert_eq!(
+            transition.replies[0].reply,
+            Ok(Response::Injected {
+                controller: INJECTED_CONTROLLER_ID
+            })
+        );
+        assert!(matches!(
+            &transition.injected[..],
+            [
+                ControllerEvent::Connected(ControllerInfo {
+                    id: INJECTED_CONTROLLER_ID,
+                    ..
+                }),
+                ControllerEvent::ButtonPressed {
+                    id: INJECTED_CONTROLLER_ID,
+                    button: Button::A
+                },
+            ]
+        ));
+
+        let ControllerEvent::Connected(info) = &transition.injected[0] else {
+            unreachable!()
+        };
+        padjutsu.add_controller(info.clone());
+        let transition = inject(
+            &mut padjutsu,
+            None,
+            InjectedInput::Axis {
+                axis: "left_x".into(),
+                value: -0.5,
+            },
+        );
+        assert!(matches!(
+            &transition.injected[..],
+            [ControllerEvent::AxisMotion { value, .. }] if *value == -0.5
+        ));
+
+        let transition =
+            inject(&mut padjutsu, Some(3), InjectedInput::Release("a".into()));
+        assert_eq!(
+            transition.replies[0].reply,
+            Err(RemoteError::UnknownController(3))
+        );
+        let transition =
+            inject(&mut padjutsu, None, InjectedInput::Press("z".into()));
+        assert!(matches!(
+            transition.replies[0].reply,
+            Err(RemoteError::Malformed(_))
+        ));
+        assert!(transition.injected.is_empty());
+    }
+
+    #[test]
+    fn pause_releases_held_outputs_and_leaves_only_toggles_live() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let pad = manager.connect_virtual("Virtual").unwrap();
+        let id = pad.id();
+        let mut padjutsu = Padjutsu::new();
+        padjutsu.set_workspace(
+            padjutsu_workspace::parse_profile(
+                r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: enter
+      b:
+        hold: shift
+      start+back:
+        toggle_enabled: true
+"#,
+            )
+            .unwrap(),
+        );
+        padjutsu.add_controller(manager.controllers().remove(0));
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let now = Instant::now();
+        let press = |padjutsu: &mut Padjutsu, button, phase| {
+            padjutsu.on_button_effects(id, button, phase, now)
+        };
+        press(&mut padjutsu, Button::A, ButtonPhase::Pressed);
+        press(&mut padjutsu, Button::B, ButtonPhase::Pressed);
+
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Pause { for_ms: Some(1000) }),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            now,
+        );
+        assert_eq!(
+            step.transition.replies[0].reply,
+            Ok(Response::Enabled {
+                enabled: false,
+                resumes_in_ms: Some(1000),
+            })
+        );
+        assert!(matches!(
+            &step.transition.effects[..],
+            [Effect::KeyRelease(combo), Effect::ReleaseAll]
+                if combo.to_string() == "shift"
+        ));
+        assert!(padjutsu.held_outputs().is_empty());
+        assert!(padjutsu.active_repeats().is_empty());
+        assert!(!padjutsu.needs_tick());
+
+        press(&mut padjutsu, Button::B, ButtonPhase::Released);
+        press(&mut padjutsu, Button::A, ButtonPhase::Released);
+        assert!(press(&mut padjutsu, Button::A, ButtonPhase::Pressed).is_empty());
+        press(&mut padjutsu, Button::Start, ButtonPhase::Pressed);
+        press(&mut padjutsu, Button::Back, ButtonPhase::Pressed);
+        assert!(!padjutsu.is_paused());
+        assert_eq!(padjutsu.paused_until(), None);
+
+        press(&mut padjutsu, Button::A, ButtonPhase::Released);
+        assert!(matches!(
+            press(&mut padjutsu, Button::A, ButtonPhase::Pressed)[..],
+            [Effect::KeyTap(_)]
+        ));
+
+        let mut step = DomainStep::continue_();
+        reduce_api_command(
+            request(ApiCommand::Toggle),
+            &mut step,
+            &mut padjutsu,
+            &manager,
+            &runtime_state,
+            now,
+        );
+        assert!(padjutsu.is_paused());
+        assert_eq!(
+            step.transition.replies[0].reply,
+            Ok(Response::Enabled {
+                enabled: false,
+                resumes_in_ms: None,
+            })
+        );
+    }
+
+    #[test]
+    fn reload_reports_parse_errors_and_keeps_the_old_profile() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let mut padjutsu = Padjutsu::new();
+        let now = Instant::now();
+        let reload = |padjutsu: &mut Padjutsu| {
+            let mut step = DomainStep::continue_();
+            reduce_api_command(
+                request(ApiCommand::Reload),
+                &mut step,
+                padjutsu,
+                &manager,
+                &runtime_state,
+                now,
+            );
+            step.transition.replies.remove(0).reply
+        };
+
+        assert!(matches!(
+            reload(&mut padjutsu),
+            Err(RemoteError::ProfileInvalid(_))
+        ));
+
+        let path = std::env::temp_dir()
+            .join(format!("padjutsu-reload-{}.yaml", std::process::id()));
+        padjutsu.set_profile_path(path.clone());
+        std::fs::write(
+            &path,
+            "version: 1\nrules:\n  common:\n    buttons:\n      a: {tap: enter}\n",
+        )
+        .unwrap();
+        assert_eq!(reload(&mut padjutsu), Ok(Response::Reloaded));
+        assert!(padjutsu
+            .button_rules()
+            .is_some_and(|rules| rules.len() == 1));
+
+        std::fs::write(&path, "version: 1\nrules: [").unwrap();
+        let reply = reload(&mut padjutsu);
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(reply, Err(RemoteError::ProfileInvalid(_))),
+            "{reply:?}"
+        );
+        assert!(padjutsu
+            .button_rules()
+            .is_some_and(|rules| rules.len() == 1));
     }
 }
@@ -0,0 +1,57 @@
+use std::cell::Cell;
+use std::time::Instant;
+
+/// Source of the current time for the reducers. Everything below
+/// `reduce_event` takes `now` explicitly; this is where it comes from.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to. Used by tests and by replay, so
+/// repeats, smoothing and wake deadlines see exactly the scheduled times.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            now: Cell::new(start),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn advance(&self, by: std::time::Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    /// Move the clock to `at`. Never moves backwards.
+    pub fn set(&self, at: Instant) {
+        if at > self.now.get() {
+            self.now.set(at);
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
@@ -1,11 +1,14 @@
+use std::time::Instant;
+
 use colored::Colorize;
 use padjutsu_gamepad::ControllerEvent;
+use padjutsu_workspace::HookEvent;
 
 use crate::app::{ButtonPhase, Padjutsu};
 use crate::domain::reduce::DomainStep;
 use crate::domain::{
-    push_controller_state_update, resolve_controller_state, ControllerTransition,
-    RuntimeState, WakeState, WakeTransition,
+    push_controller_state_update, push_hook, resolve_controller_state,
+    ControllerTransition, RuntimeState, WakeState, WakeTransition,
 };
 use crate::print_debug;
 
@@ -15,6 +18,7 @@ pub fn reduce_controller_event(
     padjutsu: &mut Padjutsu,
     runtime_state: &RuntimeState,
     wake_state: &WakeState,
+    now: Instant,
     on_ignored: impl FnOnce(&str) -> DomainStep,
 ) -> Option<DomainStep> {
     match controller_event {
@@ -24,7 +28,12 @@ pub fn reduce_controller_event(
                 return None;
             }
 
+            let env = vec![
+                ("PADJUTSU_CONTROLLER_ID", id.to_string()),
+                ("PADJUTSU_CONTROLLER_NAME", info.name.clone()),
+            ];
             padjutsu.add_controller(info);
+            push_hook(step, padjutsu, HookEvent::ControllerConnected, env);
             push_controller_state_update(
                 step,
                 runtime_state,
@@ -35,8 +44,10 @@ pub fn reduce_controller_event(
         }
         ControllerEvent::Disconnected(id) => {
             padjutsu.remove_controller(id);
-            padjutsu.on_controller_disconnected(id);
+            step.transition.effects = padjutsu.on_controller_disconnected(id);
             print_debug!("controller state transition: id={id} -> Disconnected");
+            let env = vec![("PADJUTSU_CONTROLLER_ID", id.to_string())];
+            push_hook(step, padjutsu, HookEvent::ControllerDisconnected, env);
             step.transition
                 .controller_updates
                 .push(ControllerTransition {
@@ -50,7 +61,7 @@ pub fn reduce_controller_event(
                 return Some(on_ignored("button press"));
             }
             step.transition.effects =
-                padjutsu.on_button_effects(id, button, ButtonPhase::Pressed);
+                padjutsu.on_button_effects(id, button, ButtonPhase::Pressed, now);
             let next_state = resolve_controller_state(padjutsu, id);
             push_controller_state_update(step, runtime_state, id, next_state);
             step.transition.wake.push(WakeTransition::Reschedule);
@@ -60,7 +71,7 @@ pub fn reduce_controller_event(
                 return Some(on_ignored("button release"));
             }
             step.transition.effects =
-                padjutsu.on_button_effects(id, button, ButtonPhase::Released);
+                padjutsu.on_button_effects(id, button, ButtonPhase::Released, now);
             let next_state = resolve_controller_state(padjutsu, id);
             push_controller_state_update(step, runtime_state, id, next_state);
             step.transition.wake.push(WakeTransition::Reschedule);
@@ -87,7 +98,7 @@ pub fn reduce_controller_event(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{ControllerMode, RuntimeMode};
+    use crate::domain::{Clock, ControllerMode, ManualClock, RuntimeMode};
     use padjutsu_gamepad::{Button, ControllerInfo};
 
     fn controller_info(id: u32) -> ControllerInfo {
@@ -106,7 +117,8 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.add_controller(controller_info(1));
         let runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let ignored = reduce_controller_event(
             ControllerEvent::ButtonPressed {
@@ -117,6 +129,7 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
             |_| DomainStep::continue_(),
         );
 
@@ -129,7 +142,8 @@ mod tests {
         let mut step = DomainStep::continue_();
         let mut padjutsu = Padjutsu::new();
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let ignored = reduce_controller_event(
             ControllerEvent::Connected(controller_info(1)),
@@ -137,6 +151,7 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
             |_| DomainStep::continue_(),
         );
 
@@ -154,7 +169,8 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.add_controller(controller_info(1));
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let ignored = reduce_controller_event(
             ControllerEvent::Disconnected(1),
@@ -162,6 +178,7 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
             |_| DomainStep::continue_(),
         );
 
@@ -174,6 +191,63 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn disconnect_releases_what_that_controller_holds() {
+        let mut padjutsu = Padjutsu::new();
+        padjutsu.set_workspace(
+            padjutsu_workspace::parse_profile(
+                r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        hold: shift
+      lt+b:
+        shell_hold: record
+"#,
+            )
+            .unwrap(),
+        );
+        padjutsu.add_controller(controller_info(1));
+        padjutsu.add_controller(controller_info(2));
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
+        for (id, button) in
+            [(1, Button::A), (1, Button::LeftTrigger), (1, Button::B)]
+        {
+            padjutsu.on_button_effects(
+                id,
+                button,
+                ButtonPhase::Pressed,
+                clock.now(),
+            );
+        }
+        padjutsu.on_button_effects(2, Button::A, ButtonPhase::Pressed, clock.now());
+
+        let mut step = DomainStep::continue_();
+        reduce_controller_event(
+            ControllerEvent::Disconnected(1),
+            &mut step,
+            &mut padjutsu,
+            &runtime_state,
+            &wake_state,
+            clock.now(),
+            |_| DomainStep::continue_(),
+        );
+
+        let effects: Vec<String> = step
+            .transition
+            .effects
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(effects, ["key_release shift", "shell_stop b+lt"]);
+        assert_eq!(padjutsu.held_outputs().len(), 1);
+        assert_eq!(padjutsu.held_outputs()[0].0, 2);
+    }
+
     #[test]
     fn axis_motion_enqueues_axis_active_state_and_reschedule() {
         let mut step = DomainStep::continue_();
@@ -196,7 +270,8 @@ mod tests {
                 ),
             ),
         );
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let ignored = reduce_controller_event(
             ControllerEvent::AxisMotion {
@@ -208,6 +283,7 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
             |_| DomainStep::continue_(),
         );
 
@@ -1,5 +1,5 @@
 use crate::activity::ActivityEvent;
-use crate::api::Command as ApiCommand;
+use crate::api::ApiRequest;
 use padjutsu_gamepad::ControllerEvent;
 use padjutsu_workspace::ProfileEvent;
 
@@ -7,7 +7,7 @@ pub enum DomainEvent {
     Controller(ControllerEvent),
     Activity(ActivityEvent),
     Profile(ProfileEvent),
-    Api(ApiCommand),
+    Api(ApiRequest),
     Timer(TimerEvent),
     System(SystemEvent),
 }
@@ -18,4 +18,8 @@ pub enum TimerEvent {
 
 pub enum SystemEvent {
     ShutdownRequested,
+    /// SIGHUP: re-read the profile.
+    Reload,
+    /// SIGUSR1: log the whole runtime state.
+    DumpState,
 }
@@ -0,0 +1,98 @@
+use std::time::Instant;
+
+use padjutsu_workspace::HookEvent;
+
+use crate::app::binding::BindingSource;
+use crate::app::Padjutsu;
+use crate::domain::{DomainStep, HookRun};
+
+/// Queue the profile's hook for `event`, if it has one. `env` describes the
+/// event; `PADJUTSU_EVENT` is added here.
+pub fn push_hook(
+    step: &mut DomainStep,
+    padjutsu: &Padjutsu,
+    event: HookEvent,
+    env: Vec<(&'static str, String)>,
+) {
+    let Some(command) = padjutsu
+        .workspace
+        .as_ref()
+        .and_then(|profile| profile.hooks.get(&event))
+    else {
+        return;
+    };
+    let mut run = HookRun {
+        command: command.clone(),
+        env: vec![("PADJUTSU_EVENT", event.name().to_string())],
+    };
+    run.env.extend(env);
+    step.transition.hooks.push(run);
+}
+
+fn layer_name(source: BindingSource) -> &'static str {
+    match source {
+        BindingSource::None => "none",
+        BindingSource::App => "app",
+        BindingSource::Common => "common",
+        BindingSource::Blacklisted => "blacklisted",
+    }
+}
+
+/// The rule set in effect: apps with their own rules each have one, all
+/// other apps share `common`'s.
+fn layer(padjutsu: &Padjutsu) -> (BindingSource, Option<Box<str>>) {
+    let source = padjutsu.binding_source();
+    let app = (source == BindingSource::App).then(|| padjutsu.active_app().into());
+    (source, app)
+}
+
+/// State whose hooks fire on change, whichever event changed it: pausing
+/// comes from buttons, the api and timers, the layer from app switches and
+/// profile loads.
+pub struct HookWatch {
+    paused: bool,
+    layer: (BindingSource, Option<Box<str>>),
+}
+
+impl HookWatch {
+    pub fn new(padjutsu: &Padjutsu) -> Self {
+        Self {
+            paused: padjutsu.is_paused(),
+            layer: layer(padjutsu),
+        }
+    }
+
+    /// Queue hooks for what changed since [`HookWatch::new`].
+    pub fn push_changes(
+        self,
+        step: &mut DomainStep,
+        padjutsu: &Padjutsu,
+        now: Instant,
+    ) {
+        let layer = layer(padjutsu);
+        if layer != self.layer {
+            let env = vec![
+                ("PADJUTSU_LAYER", layer_name(layer.0).to_string()),
+                ("PADJUTSU_APP", padjutsu.active_app().to_string()),
+            ];
+            push_hook(step, padjutsu, HookEvent::LayerChanged, env);
+        }
+        match (self.paused, padjutsu.is_paused()) {
+            (false, true) => {
+                let env = padjutsu
+                    .paused_until()
+                    .map(|until| {
+                        let ms = until.saturating_duration_since(now).as_millis();
+                        ("PADJUTSU_RESUMES_IN_MS", ms.to_string())
+                    })
+                    .into_iter()
+                    .collect();
+                push_hook(step, padjutsu, HookEvent::Paused, env);
+            }
+            (true, false) => {
+                push_hook(step, padjutsu, HookEvent::Resumed, Vec::new())
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,9 +1,12 @@
 mod activity_reduce;
 mod api_reduce;
+mod clock;
 mod controller_reduce;
 mod event;
+mod hooks;
 mod profile_reduce;
 mod reduce;
+mod state_dump;
 mod stick_reduce;
 mod stick_state;
 mod state;
@@ -13,16 +16,21 @@ mod wake;
 
 pub use activity_reduce::reduce_activity_event;
 pub use api_reduce::reduce_api_command;
+pub use clock::{Clock, ManualClock, SystemClock};
 pub use controller_reduce::reduce_controller_event;
 pub use event::{DomainEvent, SystemEvent, TimerEvent};
-pub use profile_reduce::reduce_profile_event;
+pub use hooks::{push_hook, HookWatch};
+pub use profile_reduce::{reduce_profile_event, reload_profile};
 pub use reduce::{reduce_event, DomainControl, DomainStep};
+pub use state_dump::dump_state;
 pub use stick_reduce::{push_controller_state_update, resolve_controller_state};
-pub use stick_state::{resolve_stick_state, stick_transition, StickActivity};
+pub use stick_state::{
+    resolve_stick_state, stick_transition, StickActivity, StickModeKind,
+};
 pub use state::{ControllerMode, ControllerRuntimeState, RuntimeMode, RuntimeState};
 pub use timer_reduce::reduce_timer_event;
 pub use transition::{
-    ControllerTransition, ModeTransition, ShellTransition, StickTransition,
-    Transition, WakeTransition,
+    ControllerTransition, HookRun, ModeTransition, ReplyTransition, ShellTransition,
+    StickTransition, Transition, WakeTransition,
 };
 pub use wake::{apply_wake_intents, overdue_wake_event, reschedule_wake, WakeState};
@@ -1,25 +1,31 @@
+use std::time::Instant;
+
 use colored::Colorize;
-use padjutsu_workspace::ProfileEvent;
+use padjutsu_workspace::{load_profile_file, HookEvent, ProfileEvent};
 
 use crate::app::Padjutsu;
-use crate::domain::{DomainStep, RuntimeMode, WakeTransition};
+use crate::domain::{push_hook, DomainStep, RuntimeMode, WakeTransition};
 use crate::{print_error, print_info};
 
 pub fn reduce_profile_event(
     profile_event: ProfileEvent,
     step: &mut DomainStep,
     padjutsu: &mut Padjutsu,
+    now: Instant,
 ) {
     match profile_event {
         ProfileEvent::Changed(workspace) => {
             print_info!("profile changed, updating workspace");
-            padjutsu.set_workspace(workspace);
+            padjutsu.set_workspace(*workspace);
+            padjutsu.set_profile_loaded_at(now);
             step.transition.shell = Some(crate::domain::ShellTransition::Set(
                 padjutsu.current_shell(),
             ));
             step.transition.wake.push(WakeTransition::Reschedule);
             step.transition.mode =
                 Some(crate::domain::ModeTransition::Set(RuntimeMode::Active));
+            let env = vec![("PADJUTSU_PROFILE", profile_path(padjutsu))];
+            push_hook(step, padjutsu, HookEvent::ProfileLoaded, env);
         }
         ProfileEvent::Removed => {
             padjutsu.remove_workspace();
@@ -33,6 +39,44 @@ pub fn reduce_profile_event(
         }
         ProfileEvent::Error(error) => {
             print_error!("profile error: {error}");
+            // The profile in use, if any, has the hook.
+            let env = vec![
+                ("PADJUTSU_PROFILE", profile_path(padjutsu)),
+                ("PADJUTSU_ERROR", error.to_string()),
+            ];
+            push_hook(step, padjutsu, HookEvent::ProfileFailed, env);
+        }
+    }
+}
+
+fn profile_path(padjutsu: &Padjutsu) -> String {
+    padjutsu
+        .profile_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default()
+}
+
+/// Re-read the profile from disk as if the watcher had seen it change. On
+/// failure the current profile stays and the error is returned.
+pub fn reload_profile(
+    step: &mut DomainStep,
+    padjutsu: &mut Padjutsu,
+    now: Instant,
+) -> Result<(), String> {
+    let Some(path) = padjutsu.profile_path().map(ToOwned::to_owned) else {
+        return Err("no workspace profile to reload".into());
+    };
+    print_info!("reloading profile from {}", path.display());
+    match load_profile_file(&path) {
+        Ok(profile) => {
+            let event = ProfileEvent::Changed(Box::new(profile));
+            reduce_profile_event(event, step, padjutsu, now);
+            Ok(())
+        }
+        Err(error) => {
+            let message = error.to_string();
+            reduce_profile_event(ProfileEvent::Error(error), step, padjutsu, now);
+            Err(message)
         }
     }
 }
@@ -51,6 +95,7 @@ mod tests {
             blacklist: AHashSet::new(),
             rules: AHashMap::new(),
             shell: Some(shell.into()),
+            hooks: Default::default(),
         }
     }
 
@@ -60,11 +105,13 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
 
         reduce_profile_event(
-            ProfileEvent::Changed(profile_with_shell("/bin/zsh")),
+            ProfileEvent::Changed(Box::new(profile_with_shell("/bin/zsh"))),
             &mut step,
             &mut padjutsu,
+            Instant::now(),
         );
 
+        assert!(padjutsu.profile_loaded_at().is_some());
         assert!(matches!(
             step.transition.mode,
             Some(ModeTransition::Set(RuntimeMode::Active))
@@ -84,8 +131,16 @@ mod tests {
         let mut step = DomainStep::continue_();
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_shell("/bin/zsh"));
+        padjutsu.set_profile_loaded_at(Instant::now());
+
+        reduce_profile_event(
+            ProfileEvent::Removed,
+            &mut step,
+            &mut padjutsu,
+            Instant::now(),
+        );
 
-        reduce_profile_event(ProfileEvent::Removed, &mut step, &mut padjutsu);
+        assert!(padjutsu.profile_loaded_at().is_none());
 
         assert!(matches!(
             step.transition.mode,
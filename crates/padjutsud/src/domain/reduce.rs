@@ -2,11 +2,11 @@ use colored::Colorize;
 use padjutsu_gamepad::ControllerManager;
 use crate::app::Padjutsu;
 use crate::domain::{
-    reduce_activity_event, reduce_api_command, reduce_controller_event,
-    reduce_profile_event, reduce_timer_event, DomainEvent, RuntimeMode,
-    RuntimeState, SystemEvent, Transition, WakeState,
+    dump_state, reduce_activity_event, reduce_api_command, reduce_controller_event,
+    reduce_profile_event, reduce_timer_event, reload_profile, Clock, DomainEvent,
+    HookWatch, RuntimeMode, RuntimeState, SystemEvent, Transition, WakeState,
 };
-use crate::print_debug;
+use crate::{print_debug, print_info};
 
 pub enum DomainControl {
     Continue,
@@ -57,6 +57,7 @@ pub fn reduce_event(
     manager: &ControllerManager,
     runtime_state: &RuntimeState,
     wake_state: &WakeState,
+    clock: &dyn Clock,
 ) -> DomainStep {
     let mut step = DomainStep::continue_();
 
@@ -67,6 +68,7 @@ pub fn reduce_event(
         return step;
     }
 
+    let watch = HookWatch::new(padjutsu);
     match event {
         DomainEvent::Controller(controller_event) => {
             if let Some(ignored_step) = reduce_controller_event(
@@ -75,6 +77,7 @@ pub fn reduce_event(
                 padjutsu,
                 runtime_state,
                 wake_state,
+                clock.now(),
                 |what| ignored_for_mode(runtime_state, what),
             ) {
                 return ignored_step;
@@ -84,10 +87,17 @@ pub fn reduce_event(
             reduce_activity_event(activity_event, &mut step, padjutsu);
         }
         DomainEvent::Profile(profile_event) => {
-            reduce_profile_event(profile_event, &mut step, padjutsu);
+            reduce_profile_event(profile_event, &mut step, padjutsu, clock.now());
         }
         DomainEvent::Api(command) => {
-            reduce_api_command(command, &mut step, manager);
+            reduce_api_command(
+                command,
+                &mut step,
+                padjutsu,
+                manager,
+                runtime_state,
+                clock.now(),
+            );
         }
         DomainEvent::Timer(timer_event) => {
             reduce_timer_event(
@@ -96,12 +106,30 @@ pub fn reduce_event(
                 padjutsu,
                 runtime_state,
                 wake_state,
+                clock.now(),
             );
         }
         DomainEvent::System(SystemEvent::ShutdownRequested) => {
-            return transition_to_shutting_down(DomainStep::break_());
+            let mut step = DomainStep::break_();
+            step.transition.effects = padjutsu.release_held();
+            return transition_to_shutting_down(step);
+        }
+        DomainEvent::System(SystemEvent::Reload) => {
+            // Failures are already logged; the signal has no one to answer.
+            let _ = reload_profile(&mut step, padjutsu, clock.now());
+        }
+        DomainEvent::System(SystemEvent::DumpState) => {
+            let state = dump_state(
+                padjutsu,
+                manager,
+                runtime_state,
+                wake_state,
+                clock.now(),
+            );
+            print_info!("runtime state:\n{state}");
         }
     }
+    watch.push_changes(&mut step, padjutsu, clock.now());
 
     step
 }
@@ -113,12 +141,14 @@ mod tests {
     use ahash::{AHashMap, AHashSet};
 
     use super::*;
+    use crate::app::Effect;
     use crate::domain::{
-        ControllerMode, ModeTransition, RuntimeMode, ShellTransition, WakeTransition,
+        ControllerMode, ManualClock, ModeTransition, RuntimeMode, ShellTransition,
+        TimerEvent, WakeTransition,
     };
     use padjutsu_bit_mask::Bitmask;
     use padjutsu_control::{Key, KeyCombo};
-    use padjutsu_gamepad::{Button, ControllerEvent, ControllerInfo};
+    use padjutsu_gamepad::{Backend, Button, ControllerEvent, ControllerInfo};
     use padjutsu_workspace::{AppRules, ButtonAction, ButtonRule, Profile, ProfileEvent};
 
     fn controller_info(id: u32) -> ControllerInfo {
@@ -140,6 +170,7 @@ mod tests {
             blacklist: AHashSet::new(),
             rules,
             shell: Some("/bin/zsh".into()),
+            hooks: Default::default(),
         }
     }
 
@@ -149,7 +180,7 @@ mod tests {
             Bitmask::new(&[Button::A]),
             ButtonRule {
                 action: ButtonAction::Keystroke(Arc::new(KeyCombo::from_key(
-                    Key::Unicode('a'),
+                    Key::A,
                 ))),
                 vibrate: None,
                 repeat_delay_ms: Some(1),
@@ -171,6 +202,7 @@ mod tests {
             blacklist: AHashSet::new(),
             rules,
             shell: Some("/bin/zsh".into()),
+            hooks: Default::default(),
         }
     }
 
@@ -180,7 +212,7 @@ mod tests {
             Bitmask::new(&[Button::A]),
             ButtonRule {
                 action: ButtonAction::HoldKeystroke(Arc::new(KeyCombo::from_key(
-                    Key::Unicode('a'),
+                    Key::A,
                 ))),
                 vibrate: None,
                 repeat_delay_ms: None,
@@ -202,22 +234,28 @@ mod tests {
             blacklist: AHashSet::new(),
             rules,
             shell: Some("/bin/zsh".into()),
+            hooks: Default::default(),
         }
     }
 
     #[test]
     fn reduce_event_profile_changed_emits_runtime_transition() {
         let mut padjutsu = Padjutsu::new();
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
-            DomainEvent::Profile(ProfileEvent::Changed(profile_with_common_rules())),
+            DomainEvent::Profile(ProfileEvent::Changed(Box::new(
+                profile_with_common_rules(),
+            ))),
             &mut padjutsu,
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(matches!(
@@ -239,7 +277,8 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
         padjutsu.add_controller(controller_info(7));
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
         runtime_state.set_controller_state(
             7,
@@ -257,7 +296,8 @@ mod tests {
                 ),
             ),
         );
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
             DomainEvent::Controller(ControllerEvent::ButtonPressed {
@@ -268,6 +308,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert_eq!(step.transition.controller_updates.len(), 1);
@@ -284,9 +325,11 @@ mod tests {
     #[test]
     fn reduce_event_timer_wake_outside_active_runtime_only_disables_fast_mode() {
         let mut padjutsu = Padjutsu::new();
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
-        let mut wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let mut wake_state = WakeState::new(clock.now());
         wake_state.fast_mode = true;
 
         let step = reduce_event(
@@ -295,6 +338,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(step.transition.effects.is_empty());
@@ -307,9 +351,11 @@ mod tests {
     #[test]
     fn reduce_event_shutdown_requested_breaks_and_sets_shutting_down_mode() {
         let mut padjutsu = Padjutsu::new();
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
             DomainEvent::System(SystemEvent::ShutdownRequested),
@@ -317,6 +363,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(matches!(step.control, DomainControl::Break));
@@ -324,6 +371,10 @@ mod tests {
             step.transition.mode,
             Some(ModeTransition::Set(RuntimeMode::ShuttingDown))
         ));
+        assert!(matches!(
+            step.transition.effects.as_slice(),
+            [Effect::ReleaseAll]
+        ));
     }
 
     #[test]
@@ -331,9 +382,11 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
         padjutsu.add_controller(controller_info(7));
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let runtime_state = RuntimeState::new(RuntimeMode::ShuttingDown);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
             DomainEvent::Controller(ControllerEvent::ButtonPressed {
@@ -344,6 +397,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(matches!(step.control, DomainControl::Continue));
@@ -355,8 +409,10 @@ mod tests {
     #[test]
     fn reduce_event_activity_then_profile_then_controller_forms_expected_trace() {
         let mut padjutsu = Padjutsu::new();
-        let manager = ControllerManager::new().expect("manager init");
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
         let mut runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
 
         let activity_step = reduce_event(
@@ -369,6 +425,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             activity_step.transition.shell,
@@ -376,11 +433,14 @@ mod tests {
         ));
 
         let profile_step = reduce_event(
-            DomainEvent::Profile(ProfileEvent::Changed(profile_with_common_rules())),
+            DomainEvent::Profile(ProfileEvent::Changed(Box::new(
+                profile_with_common_rules(),
+            ))),
             &mut padjutsu,
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             profile_step.transition.mode,
@@ -416,6 +476,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert_eq!(controller_step.transition.controller_updates.len(), 1);
@@ -428,8 +489,10 @@ mod tests {
     #[test]
     fn reduce_event_connect_then_disconnect_forms_expected_lifecycle_trace() {
         let mut padjutsu = Padjutsu::new();
-        let manager = ControllerManager::new().expect("manager init");
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
 
         let connect_step = reduce_event(
@@ -438,6 +501,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert_eq!(connect_step.transition.controller_updates.len(), 1);
@@ -452,6 +516,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert_eq!(disconnect_step.transition.controller_updates.len(), 1);
@@ -465,8 +530,10 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_repeating_button_rule());
         padjutsu.add_controller(controller_info(11));
-        let manager = ControllerManager::new().expect("manager init");
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
         let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
         runtime_state.set_controller_state(
             11,
@@ -494,6 +561,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             press_step.transition.controller_updates[0].next_state,
@@ -516,6 +584,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             release_step.transition.controller_updates[0].next_state,
@@ -528,7 +597,8 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_hold_button_rule());
         padjutsu.add_controller(controller_info(21));
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
         runtime_state.set_controller_state(
             21,
@@ -546,7 +616,8 @@ mod tests {
                 ),
             ),
         );
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let press_step = reduce_event(
             DomainEvent::Controller(ControllerEvent::ButtonPressed {
@@ -557,6 +628,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             press_step.transition.effects.as_slice(),
@@ -572,6 +644,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         assert!(matches!(
             release_step.transition.effects.as_slice(),
@@ -584,9 +657,11 @@ mod tests {
     ) {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
             DomainEvent::Profile(ProfileEvent::Removed),
@@ -594,6 +669,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(matches!(
@@ -611,8 +687,10 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
         padjutsu.add_controller(controller_info(5));
-        let manager = ControllerManager::new().expect("manager init");
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
         let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
         runtime_state.set_controller_state(
             5,
@@ -641,6 +719,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
         runtime_state.set_controller_state(
             5,
@@ -658,6 +737,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert!(matches!(
@@ -671,7 +751,8 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
         padjutsu.add_controller(controller_info(13));
-        let manager = ControllerManager::new().expect("manager init");
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
         let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
         runtime_state.set_controller_state(
             13,
@@ -689,7 +770,8 @@ mod tests {
                 ),
             ),
         );
-        let wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
 
         let step = reduce_event(
             DomainEvent::Controller(ControllerEvent::AxisMotion {
@@ -701,6 +783,7 @@ mod tests {
             &manager,
             &runtime_state,
             &wake_state,
+            &clock,
         );
 
         assert_eq!(step.transition.stick_updates.len(), 1);
@@ -713,4 +796,192 @@ mod tests {
             crate::domain::StickActivity::Active
         ));
     }
+
+    #[test]
+    fn button_repeat_follows_manual_clock() {
+        let mut padjutsu = Padjutsu::new();
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
+        let mut reduce = |event| {
+            reduce_event(
+                event,
+                &mut padjutsu,
+                &manager,
+                &runtime_state,
+                &wake_state,
+                &clock,
+            )
+        };
+        let key_taps = |step: DomainStep| {
+            step.transition
+                .effects
+                .iter()
+                .filter(|effect| matches!(effect, Effect::KeyTap(_)))
+                .count()
+        };
+
+        reduce(DomainEvent::Profile(ProfileEvent::Changed(Box::new(
+            profile_with_repeating_button_rule(),
+        ))));
+        reduce(DomainEvent::Controller(ControllerEvent::Connected(
+            controller_info(1),
+        )));
+        reduce(DomainEvent::Controller(ControllerEvent::ButtonPressed {
+            id: 1,
+            button: Button::A,
+        }));
+
+        // The 1ms repeat delay has not elapsed on the clock.
+        assert_eq!(key_taps(reduce(DomainEvent::Timer(TimerEvent::Wake))), 0);
+        clock.advance(std::time::Duration::from_millis(1));
+        assert_eq!(key_taps(reduce(DomainEvent::Timer(TimerEvent::Wake))), 1);
+        assert_eq!(key_taps(reduce(DomainEvent::Timer(TimerEvent::Wake))), 0);
+        clock.advance(std::time::Duration::from_millis(1));
+        assert_eq!(key_taps(reduce(DomainEvent::Timer(TimerEvent::Wake))), 1);
+
+        reduce(DomainEvent::Controller(ControllerEvent::ButtonReleased {
+            id: 1,
+            button: Button::A,
+        }));
+        clock.advance(std::time::Duration::from_millis(5));
+        assert_eq!(key_taps(reduce(DomainEvent::Timer(TimerEvent::Wake))), 0);
+    }
+
+    #[test]
+    fn hooks_are_queued_for_lifecycle_events_with_their_details() {
+        use crate::activity::ActivityEvent;
+        use crate::api::{ApiRequest, Command};
+        use padjutsu_workspace::HookEvent;
+
+        let mut profile = profile_with_common_rules();
+        profile.rules.insert("com.app".into(), AppRules::default());
+        for event in [
+            HookEvent::ControllerConnected,
+            HookEvent::AppChanged,
+            HookEvent::LayerChanged,
+            HookEvent::Paused,
+        ] {
+            profile.hooks.insert(event, event.name().into());
+        }
+        let mut padjutsu = Padjutsu::new();
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
+        let mut reduce = |event| {
+            let step = reduce_event(
+                event,
+                &mut padjutsu,
+                &manager,
+                &runtime_state,
+                &wake_state,
+                &clock,
+            );
+            step.transition
+                .hooks
+                .into_iter()
+                .map(|hook| {
+                    let env: Vec<String> = hook
+                        .env
+                        .into_iter()
+                        .map(|(name, value)| format!("{name}={value}"))
+                        .collect();
+                    format!("{}: {}", hook.command, env.join(" "))
+                })
+                .collect::<Vec<_>>()
+        };
+        let app = |app: &str| {
+            DomainEvent::Activity(ActivityEvent::DidActivateApplication(app.into()))
+        };
+
+        assert_eq!(
+            reduce(DomainEvent::Profile(ProfileEvent::Changed(Box::new(
+                profile
+            )))),
+            [
+                "layer_changed: PADJUTSU_EVENT=layer_changed PADJUTSU_LAYER=common \
+              PADJUTSU_APP="
+            ]
+        );
+        assert_eq!(
+            reduce(DomainEvent::Controller(ControllerEvent::Connected(
+                controller_info(1)
+            ))),
+            ["controller_connected: PADJUTSU_EVENT=controller_connected \
+              PADJUTSU_CONTROLLER_ID=1 PADJUTSU_CONTROLLER_NAME=Test Controller"]
+        );
+        assert_eq!(
+            reduce(app("com.other")),
+            [
+                "app_changed: PADJUTSU_EVENT=app_changed PADJUTSU_APP=com.other \
+              PADJUTSU_PREVIOUS_APP="
+            ]
+        );
+        assert_eq!(
+            reduce(app("com.app")),
+            [
+                "app_changed: PADJUTSU_EVENT=app_changed PADJUTSU_APP=com.app \
+                 PADJUTSU_PREVIOUS_APP=com.other",
+                "layer_changed: PADJUTSU_EVENT=layer_changed PADJUTSU_LAYER=app \
+                 PADJUTSU_APP=com.app",
+            ]
+        );
+
+        let (reply, _) = crossbeam_channel::bounded(1);
+        let pause = |for_ms| {
+            DomainEvent::Api(ApiRequest {
+                command: Command::Pause { for_ms },
+                reply: reply.clone(),
+                events: None,
+            })
+        };
+        assert_eq!(
+            reduce(pause(Some(5000))),
+            ["paused: PADJUTSU_EVENT=paused PADJUTSU_RESUMES_IN_MS=5000"]
+        );
+        // Already paused: nothing changed, nothing runs.
+        assert!(reduce(pause(None)).is_empty());
+    }
+
+    #[test]
+    fn an_app_shell_overrides_the_profile_shell_while_it_is_active() {
+        let mut profile = profile_with_common_rules();
+        profile.rules.insert(
+            "com.apple.Terminal".into(),
+            AppRules {
+                shell: Some("/bin/bash".into()),
+                ..AppRules::default()
+            },
+        );
+        let mut padjutsu = Padjutsu::new();
+        padjutsu.set_workspace(profile);
+        let manager =
+            ControllerManager::with_backend(Backend::Virtual).expect("manager init");
+        let runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
+
+        let mut activate = |app: &str| {
+            use crate::activity::ActivityEvent;
+            let step = reduce_event(
+                DomainEvent::Activity(ActivityEvent::DidActivateApplication(
+                    app.into(),
+                )),
+                &mut padjutsu,
+                &manager,
+                &runtime_state,
+                &wake_state,
+                &clock,
+            );
+            match step.transition.shell {
+                Some(ShellTransition::Set(shell)) => shell,
+                None => panic!("no shell transition for {app}"),
+            }
+        };
+        assert_eq!(activate("com.apple.Terminal").as_deref(), Some("/bin/bash"));
+        assert_eq!(activate("com.apple.Safari").as_deref(), Some("/bin/zsh"));
+    }
 }
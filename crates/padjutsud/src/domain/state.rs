@@ -1,9 +1,14 @@
 use ahash::AHashMap;
+use bitcode::{Decode, Encode};
+use padjutsu_control::WorkerStats;
 use padjutsu_gamepad::ControllerId;
+use serde::Serialize;
 
+use crate::api::EventFeed;
 use crate::domain::stick_state::StickState;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuntimeMode {
     Booting,
     AwaitingProfile,
@@ -11,7 +16,8 @@ pub enum RuntimeMode {
     ShuttingDown,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ControllerMode {
     ConnectedIdle,
     ButtonsActive,
@@ -24,6 +30,9 @@ pub enum ControllerMode {
 pub struct RuntimeState {
     mode: RuntimeMode,
     controllers: AHashMap<ControllerId, ControllerRuntimeState>,
+    feed: EventFeed,
+    /// Last snapshot of the performer worker's queues.
+    output: Option<WorkerStats>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +47,8 @@ impl RuntimeState {
         Self {
             mode,
             controllers: AHashMap::new(),
+            feed: EventFeed::default(),
+            output: None,
         }
     }
 
@@ -75,6 +86,19 @@ impl RuntimeState {
     pub fn disconnect_controller(&mut self, id: ControllerId) {
         self.controllers.remove(&id);
     }
+
+    /// Observers of handled events and emitted effects.
+    pub fn feed(&mut self) -> &mut EventFeed {
+        &mut self.feed
+    }
+
+    pub fn output_stats(&self) -> Option<WorkerStats> {
+        self.output
+    }
+
+    pub fn set_output_stats(&mut self, stats: WorkerStats) {
+        self.output = Some(stats);
+    }
 }
 
 impl ControllerRuntimeState {
@@ -0,0 +1,74 @@
+use std::fmt::Write;
+use std::time::Instant;
+
+use padjutsu_gamepad::ControllerManager;
+use padjutsu_workspace::{chord_name, StickSide};
+
+use crate::app::describe::{describe_action, summarize_stick};
+use crate::app::Padjutsu;
+use crate::domain::api_reduce::status;
+use crate::domain::{RuntimeState, WakeState};
+
+/// Everything the event loop knows, for a SIGUSR1 dump to the log: the
+/// status report, the bindings in effect and the wake schedule.
+pub fn dump_state(
+    padjutsu: &Padjutsu,
+    manager: &ControllerManager,
+    runtime_state: &RuntimeState,
+    wake_state: &WakeState,
+    now: Instant,
+) -> String {
+    let mut out = status(padjutsu, manager, runtime_state, now).to_string();
+
+    let mut bindings: Vec<(String, String)> = padjutsu
+        .button_rules()
+        .into_iter()
+        .flatten()
+        .map(|(chord, rule)| (chord_name(chord), describe_action(&rule.action)))
+        .collect();
+    bindings.sort();
+    if bindings.is_empty() {
+        out.push_str("bindings:    none\n");
+    }
+    for (chord, action) in bindings {
+        let _ = writeln!(out, "binding:     {chord} -> {action}");
+    }
+    if let Some(sticks) = padjutsu.stick_rules() {
+        for side in [StickSide::Left, StickSide::Right] {
+            if let Some(mode) = sticks.get(&side) {
+                let _ = writeln!(
+                    out,
+                    "stick:       {side:?} -> {}",
+                    summarize_stick(mode)
+                );
+            }
+        }
+    }
+
+    let in_ms = |at: Option<Instant>| match at {
+        Some(at) => {
+            format!("in {}ms", at.saturating_duration_since(now).as_millis())
+        }
+        None => "none".to_string(),
+    };
+    let fast = if wake_state.fast_mode {
+        format!("fast {}", in_ms(Some(wake_state.fast_until)))
+    } else {
+        "idle".to_string()
+    };
+    let _ = writeln!(
+        out,
+        "wake:        ticking {}, {fast}, next tick {}, reschedule pending {}",
+        wake_state.ticking_enabled,
+        in_ms(wake_state.next_tick_due),
+        wake_state.need_reschedule,
+    );
+    let _ = writeln!(
+        out,
+        "due:         stick repeat {}, button repeat {}, resume {}",
+        in_ms(padjutsu.next_repeat_due()),
+        in_ms(padjutsu.next_button_repeat_due()),
+        in_ms(padjutsu.paused_until()),
+    );
+    out
+}
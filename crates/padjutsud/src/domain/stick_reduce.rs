@@ -158,8 +158,11 @@ pub fn push_controller_state_update(
 mod tests {
     use ahash::{AHashMap, AHashSet};
 
+    use std::time::Duration;
+
     use super::*;
-    use crate::app::ButtonPhase;
+    use crate::app::{ButtonPhase, Effect};
+    use crate::domain::{Clock, ManualClock};
     use padjutsu_gamepad::{Axis, Button, ControllerInfo};
     use padjutsu_workspace::{AppRules, Profile};
 
@@ -182,6 +185,7 @@ mod tests {
             blacklist: AHashSet::new(),
             rules,
             shell: None,
+            hooks: Default::default(),
         }
     }
 
@@ -214,7 +218,12 @@ mod tests {
         let mut padjutsu = Padjutsu::new();
         padjutsu.set_workspace(profile_with_common_rules());
         padjutsu.add_controller(controller_info(1));
-        let _ = padjutsu.on_button_effects(1, Button::A, ButtonPhase::Pressed);
+        let _ = padjutsu.on_button_effects(
+            1,
+            Button::A,
+            ButtonPhase::Pressed,
+            std::time::Instant::now(),
+        );
 
         let state = resolve_controller_state(&padjutsu, 1);
 
@@ -246,4 +255,141 @@ mod tests {
             StickActivity::Active
         ));
     }
+
+    /// The right stick scrolling on controller 1, ticked on a manual clock.
+    struct Scroller {
+        padjutsu: Padjutsu,
+        clock: ManualClock,
+    }
+
+    impl Scroller {
+        fn new(horizontal: bool, axis_lock: bool) -> Self {
+            let mut padjutsu = Padjutsu::new();
+            padjutsu.set_workspace(
+                padjutsu_workspace::parse_profile(&format!(
+                    "version: 1
+rules:
+  common:
+    sticks:
+      right:
+        mode: scroll
+        deadzone: 0.15
+        speed_lines_s: 120
+        horizontal: {horizontal}
+        axis_lock: {axis_lock}
+        trigger_boost_max: 0.0
+"
+                ))
+                .unwrap(),
+            );
+            padjutsu.add_controller(controller_info(1));
+            Self {
+                padjutsu,
+                clock: ManualClock::default(),
+            }
+        }
+
+        /// Hold the stick at (`x`, `y`) for `ticks` ticks `dt_ms` apart and
+        /// return the scrolls they produce.
+        fn hold(
+            &mut self,
+            x: f32,
+            y: f32,
+            ticks: u32,
+            dt_ms: u64,
+        ) -> Vec<(f64, f64)> {
+            self.padjutsu.on_axis_motion(1, Axis::RightX, x);
+            self.padjutsu.on_axis_motion(1, Axis::RightY, y);
+            let mut scrolls = Vec::new();
+            for _ in 0..ticks {
+                self.clock.advance(Duration::from_millis(dt_ms));
+                for effect in self.padjutsu.on_tick_effects(self.clock.now()) {
+                    if let Effect::Scroll { h, v } = effect {
+                        scrolls.push((h, v));
+                    }
+                }
+            }
+            scrolls
+        }
+    }
+
+    #[test]
+    fn axis_lock_locks_to_vertical_when_y_dominant() {
+        let mut scroller = Scroller::new(true, true);
+
+        // Mostly down with slight horizontal drift.
+        let scrolls = scroller.hold(0.1, 0.8, 20, 10);
+
+        assert!(!scrolls.is_empty(), "should produce scroll effects");
+        assert!(scrolls.iter().all(|(h, _)| *h == 0.0), "{scrolls:?}");
+        assert!(scrolls.iter().any(|(_, v)| *v != 0.0), "{scrolls:?}");
+    }
+
+    #[test]
+    fn axis_lock_locks_to_horizontal_when_x_dominant() {
+        let mut scroller = Scroller::new(true, true);
+
+        // Mostly right with slight vertical drift.
+        let scrolls = scroller.hold(0.8, 0.1, 20, 10);
+
+        assert!(!scrolls.is_empty(), "should produce scroll effects");
+        assert!(scrolls.iter().all(|(_, v)| *v == 0.0), "{scrolls:?}");
+        assert!(scrolls.iter().any(|(h, _)| *h != 0.0), "{scrolls:?}");
+    }
+
+    #[test]
+    fn axis_lock_resets_when_stick_returns_to_deadzone() {
+        let mut scroller = Scroller::new(true, true);
+
+        scroller.hold(0.1, 0.8, 10, 10);
+        // The lock is released after 200ms in the deadzone.
+        scroller.hold(0.0, 0.0, 25, 10);
+        let scrolls = scroller.hold(0.8, 0.1, 20, 10);
+
+        assert!(!scrolls.is_empty(), "should produce scroll effects");
+        assert!(scrolls.iter().all(|(_, v)| *v == 0.0), "{scrolls:?}");
+    }
+
+    #[test]
+    fn no_axis_lock_allows_both_axes() {
+        let mut scroller = Scroller::new(true, false);
+
+        let scrolls = scroller.hold(0.5, 0.5, 20, 10);
+
+        assert!(scrolls.iter().any(|(h, _)| *h != 0.0), "{scrolls:?}");
+        assert!(scrolls.iter().any(|(_, v)| *v != 0.0), "{scrolls:?}");
+    }
+
+    #[test]
+    fn scroll_works_with_small_dt() {
+        // With 2ms ticks the smoothing filter moves little per tick; resetting
+        // it in the deadzone would keep it from ever leaving the deadzone.
+        let mut scroller = Scroller::new(false, false);
+
+        let scrolls = scroller.hold(0.0, 0.8, 100, 2);
+
+        assert!(scrolls.iter().any(|(_, v)| *v != 0.0), "{scrolls:?}");
+    }
+
+    #[test]
+    fn scroll_survives_direction_change() {
+        let mut scroller = Scroller::new(false, false);
+
+        assert!(!scroller.hold(0.0, 0.8, 20, 10).is_empty());
+        // Reversing passes briefly through the center.
+        scroller.hold(0.0, 0.0, 2, 10);
+        let scrolls = scroller.hold(0.0, -0.8, 20, 10);
+
+        assert!(!scrolls.is_empty(), "scroll must resume after reversing");
+    }
+
+    #[test]
+    fn horizontal_false_zeroes_x_axis() {
+        let mut scroller = Scroller::new(false, false);
+
+        let scrolls = scroller.hold(0.5, 0.8, 20, 10);
+
+        assert!(!scrolls.is_empty(), "should produce scroll effects");
+        assert!(scrolls.iter().all(|(h, _)| *h == 0.0), "{scrolls:?}");
+    }
 }
@@ -1,9 +1,12 @@
+use bitcode::{Decode, Encode};
 use padjutsu_gamepad::ControllerId;
 use padjutsu_workspace::{StickMode, StickSide};
+use serde::Serialize;
 
 use crate::app::Padjutsu;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StickActivity {
     Neutral,
     Active,
@@ -17,7 +20,8 @@ pub struct StickState {
     pub activity: StickActivity,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StickModeKind {
     Arrows,
     Volume,
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
 
@@ -12,9 +12,12 @@ pub fn reduce_timer_event(
     padjutsu: &mut Padjutsu,
     runtime_state: &RuntimeState,
     wake_state: &WakeState,
+    now: Instant,
 ) {
     match timer_event {
         TimerEvent::Wake => {
+            // A timed pause ends even while the runtime ignores input.
+            padjutsu.resume_if_due(now);
             if !runtime_state.handles_timer_wake() {
                 if wake_state.fast_mode {
                     step.transition.wake.push(WakeTransition::DisableFastMode);
@@ -25,7 +28,6 @@ pub fn reduce_timer_event(
                 );
                 return;
             }
-            let now = std::time::Instant::now();
             print_debug!(
                 "wake timer fired: next_tick_due={:?} fast_mode={} need_reschedule={}",
                 wake_state.next_tick_due,
@@ -38,7 +40,9 @@ pub fn reduce_timer_event(
                         "wake timer: tick due lateness_us={}",
                         now.duration_since(due).as_micros()
                     );
-                    step.transition.effects.extend(padjutsu.on_tick_effects());
+                    step.transition
+                        .effects
+                        .extend(padjutsu.on_tick_effects(now));
                     if padjutsu.wants_fast_tick() {
                         let fast_until = now + Duration::from_millis(250);
                         step.transition
@@ -77,14 +81,15 @@ pub fn reduce_timer_event(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{RuntimeMode, RuntimeState};
+    use crate::domain::{Clock, ManualClock, RuntimeMode, RuntimeState};
 
     #[test]
     fn wake_timer_disables_fast_mode_outside_active_runtime() {
         let mut step = DomainStep::continue_();
         let mut padjutsu = Padjutsu::new();
         let runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
-        let mut wake_state = WakeState::new(std::time::Instant::now());
+        let clock = ManualClock::default();
+        let mut wake_state = WakeState::new(clock.now());
         wake_state.fast_mode = true;
 
         reduce_timer_event(
@@ -93,6 +98,7 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
         );
 
         assert!(matches!(
@@ -117,7 +123,7 @@ mod tests {
                 Bitmask::new(&[Button::A]),
                 ButtonRule {
                     action: ButtonAction::Keystroke(Arc::new(KeyCombo::from_key(
-                        Key::Unicode('a'),
+                        Key::A,
                     ))),
                     vibrate: None,
                     repeat_delay_ms: Some(1),
@@ -139,6 +145,7 @@ mod tests {
                 blacklist: AHashSet::new(),
                 rules,
                 shell: None,
+                hooks: Default::default(),
             }
         }
 
@@ -154,17 +161,20 @@ mod tests {
 
         let mut step = DomainStep::continue_();
         let mut padjutsu = Padjutsu::new();
+        let clock = ManualClock::default();
         padjutsu.set_workspace(profile_with_repeating_button_rule());
         padjutsu.add_controller(controller_info(1));
         let _ = padjutsu.on_button_effects(
             1,
             Button::A,
             crate::app::ButtonPhase::Pressed,
+            clock.now(),
         );
+        clock.advance(std::time::Duration::from_millis(1));
 
         let runtime_state = RuntimeState::new(RuntimeMode::Active);
-        let mut wake_state = WakeState::new(std::time::Instant::now());
-        wake_state.next_tick_due = Some(std::time::Instant::now());
+        let mut wake_state = WakeState::new(clock.now());
+        wake_state.next_tick_due = Some(clock.now());
 
         reduce_timer_event(
             TimerEvent::Wake,
@@ -172,11 +182,50 @@ mod tests {
             &mut padjutsu,
             &runtime_state,
             &wake_state,
+            clock.now(),
         );
 
         assert!(matches!(
             step.transition.wake.as_slice(),
             [.., WakeTransition::Reschedule]
         ));
+        assert!(step
+            .transition
+            .effects
+            .iter()
+            .any(|effect| matches!(effect, crate::app::Effect::KeyTap(_))));
+    }
+
+    #[test]
+    fn wake_timer_ends_a_timed_pause() {
+        let mut padjutsu = Padjutsu::new();
+        let runtime_state = RuntimeState::new(RuntimeMode::AwaitingProfile);
+        let clock = ManualClock::default();
+        let wake_state = WakeState::new(clock.now());
+        padjutsu.pause(Some(clock.now() + Duration::from_millis(100)));
+
+        let mut plan_state = WakeState::new(clock.now());
+        let plan = crate::domain::reschedule_wake(
+            &padjutsu,
+            &mut plan_state,
+            clock.now(),
+            Duration::from_millis(100),
+            Duration::from_millis(16),
+        );
+        assert_eq!(plan.next_due, padjutsu.paused_until());
+
+        for (advance, paused) in [(99, true), (1, false)] {
+            clock.advance(Duration::from_millis(advance));
+            reduce_timer_event(
+                TimerEvent::Wake,
+                &mut DomainStep::continue_(),
+                &mut padjutsu,
+                &runtime_state,
+                &wake_state,
+                clock.now(),
+            );
+            assert_eq!(padjutsu.is_paused(), paused);
+        }
+        assert_eq!(padjutsu.paused_until(), None);
     }
 }
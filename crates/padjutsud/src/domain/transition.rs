@@ -1,5 +1,9 @@
 use std::time::Instant;
 
+use crossbeam_channel::Sender;
+use padjutsu_gamepad::ControllerEvent;
+
+use crate::api::{ApiReply, Subscriber};
 use crate::app::Effect;
 use crate::domain::stick_state::StickState;
 use crate::domain::{ControllerRuntimeState, RuntimeMode};
@@ -29,6 +33,19 @@ pub enum WakeTransition {
     DisableFastMode,
 }
 
+/// A profile hook to run once the step is applied.
+pub struct HookRun {
+    pub command: Box<str>,
+    /// `PADJUTSU_*` variables describing the event.
+    pub env: Vec<(&'static str, String)>,
+}
+
+/// An answer for an api client, sent once the step is applied.
+pub struct ReplyTransition {
+    pub to: Sender<ApiReply>,
+    pub reply: ApiReply,
+}
+
 pub struct Transition {
     pub effects: Vec<Effect>,
     pub shell: Option<ShellTransition>,
@@ -36,6 +53,13 @@ pub struct Transition {
     pub controller_updates: Vec<ControllerTransition>,
     pub stick_updates: Vec<StickTransition>,
     pub mode: Option<ModeTransition>,
+    pub replies: Vec<ReplyTransition>,
+    /// Observers to add to the event feed, before the replies go out.
+    pub subscriptions: Vec<Subscriber>,
+    /// Controller events to handle right after this step, as if a
+    /// controller had sent them.
+    pub injected: Vec<ControllerEvent>,
+    pub hooks: Vec<HookRun>,
 }
 
 impl Transition {
@@ -47,6 +71,10 @@ impl Transition {
             controller_updates: Vec::new(),
             stick_updates: Vec::new(),
             mode: None,
+            replies: Vec::new(),
+            subscriptions: Vec::new(),
+            injected: Vec::new(),
+            hooks: Vec::new(),
         }
     }
 }
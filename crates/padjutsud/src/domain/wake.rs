@@ -53,10 +53,10 @@ pub fn apply_wake_intents(wake_state: &mut WakeState, intents: Vec<WakeTransitio
 pub fn reschedule_wake(
     padjutsu: &Padjutsu,
     wake_state: &mut WakeState,
+    now: Instant,
     idle_period: Duration,
     fast_period: Duration,
 ) -> WakePlan {
-    let now = Instant::now();
     if padjutsu.needs_tick() {
         let was_ticking_enabled = wake_state.ticking_enabled;
         let previous_tick_due = wake_state.next_tick_due;
@@ -102,7 +102,7 @@ pub fn reschedule_wake(
     let repeat_due = padjutsu.next_repeat_due();
     let button_repeat_due = padjutsu.next_button_repeat_due();
     let mut next_due = wake_state.next_tick_due;
-    for candidate in [repeat_due, button_repeat_due] {
+    for candidate in [repeat_due, button_repeat_due, padjutsu.paused_until()] {
         next_due = match (next_due, candidate) {
             (Some(a), Some(b)) => Some(core::cmp::min(a, b)),
             (Some(a), None) => Some(a),
@@ -127,6 +127,7 @@ pub fn has_overdue_work(
         || padjutsu
             .next_button_repeat_due()
             .is_some_and(|due| due <= now)
+        || padjutsu.paused_until().is_some_and(|due| due <= now)
 }
 
 pub fn overdue_wake_event(
@@ -0,0 +1,317 @@
+//! Binding explanation.
+//!
+//! Answers "why does this chord do nothing here": resolves the rules for
+//! an app the way the daemon does, then reports which profile section the
+//! winning rule came from, which sections it overrides, the chords it
+//! shadows or is shadowed by, and its parameters after defaults.
+
+use std::fmt::Write as _;
+
+use padjutsu_workspace::{
+    chord_name, parse_chord, parse_profile, parse_rule_sources, ButtonAction,
+    ButtonChord, ButtonRule, Profile, RuleOrigin, RuleSource, StickSide,
+};
+
+use crate::app::binding::{BindingContext, BindingSource};
+use crate::app::describe::describe_action;
+use crate::app::padjutsu::{DEFAULT_REPEAT_DELAY_MS, DEFAULT_REPEAT_INTERVAL_MS};
+use crate::read_profile;
+
+/// What to explain.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Target {
+    /// A chord in profile syntax, e.g. `lt+a`.
+    Chord(String),
+    Stick(StickSide),
+}
+
+fn describe_origin(origin: &RuleOrigin) -> String {
+    match origin {
+        RuleOrigin::Common => "common".to_string(),
+        RuleOrigin::App(selector) => format!("`{selector}`"),
+        RuleOrigin::Group { selector, group } => {
+            format!("`{selector}` via group ${group}")
+        }
+    }
+}
+
+fn describe_source(source: BindingSource) -> &'static str {
+    match source {
+        BindingSource::App => "app rules merged over common",
+        BindingSource::Common => "no app rules, common only",
+        BindingSource::None => "no rules",
+        BindingSource::Blacklisted => "blacklisted",
+    }
+}
+
+/// Button names of `chord`, joined with `joiner`.
+fn buttons_of(chord: &ButtonChord, joiner: &str) -> String {
+    chord_name(chord)
+        .split('+')
+        .collect::<Vec<_>>()
+        .join(joiner)
+}
+
+/// Buttons in `outer` but not in `inner`.
+fn difference(outer: &ButtonChord, inner: &ButtonChord) -> ButtonChord {
+    let mut rest = *outer;
+    for button in padjutsu_gamepad::Button::ALL {
+        if inner.contains(button) {
+            rest.remove(button);
+        }
+    }
+    rest
+}
+
+/// The sections that apply in `binding`, in merge order.
+fn effective_sources<'a>(
+    binding: &BindingContext,
+    sources: &'a [RuleSource],
+) -> &'a [RuleSource] {
+    match binding.source() {
+        BindingSource::App => sources,
+        BindingSource::Common => &sources[..1],
+        BindingSource::None | BindingSource::Blacklisted => &[],
+    }
+}
+
+fn explain_rule(out: &mut String, rule: &ButtonRule) {
+    if let ButtonAction::Keystroke(_) = rule.action {
+        let delay = rule.repeat_delay_ms.unwrap_or(DEFAULT_REPEAT_DELAY_MS);
+        let interval = rule
+            .repeat_interval_ms
+            .unwrap_or(DEFAULT_REPEAT_INTERVAL_MS);
+        let _ = writeln!(out, "repeat:       after {delay}ms, every {interval}ms");
+    }
+    match rule.vibrate {
+        Some(ms) => {
+            let _ = writeln!(out, "vibrate:      {ms}ms");
+        }
+        None => {
+            let _ = writeln!(out, "vibrate:      none");
+        }
+    }
+}
+
+fn explain_chord(
+    out: &mut String,
+    binding: &BindingContext,
+    sources: &[RuleSource],
+    chord: &ButtonChord,
+) {
+    let name = chord_name(chord);
+    let _ = writeln!(out, "chord:        {name}");
+    let empty = Default::default();
+    let rules = binding.button_rules().unwrap_or(&empty);
+
+    match rules.get(chord) {
+        Some(rule) => {
+            let defining: Vec<&RuleSource> = sources
+                .iter()
+                .filter(|source| source.rules.buttons.contains_key(chord))
+                .collect();
+            let _ = writeln!(out, "fires:        {}", describe_action(&rule.action));
+            if let Some((winner, overridden)) = defining.split_last() {
+                let _ = writeln!(
+                    out,
+                    "from:         {}",
+                    describe_origin(&winner.origin)
+                );
+                for source in overridden.iter().rev() {
+                    let _ = writeln!(
+                        out,
+                        "overrides:    {}: {}",
+                        describe_origin(&source.origin),
+                        describe_action(&source.rules.buttons[chord].action),
+                    );
+                }
+            }
+            explain_rule(out, rule);
+        }
+        None => {
+            let _ = writeln!(out, "fires:        nothing, no rule for {name}");
+        }
+    }
+
+    // Of the rules a press completes, only those with the most buttons fire.
+    let mut shadows: Vec<(String, String)> = Vec::new();
+    let mut shadowed_by: Vec<(String, String)> = Vec::new();
+    for other in rules.keys() {
+        if other == chord {
+            continue;
+        }
+        if chord.is_superset(other) {
+            shadows.push((chord_name(other), buttons_of(other, " or ")));
+        } else if other.is_superset(chord) {
+            let first = buttons_of(&difference(other, chord), " and ");
+            shadowed_by.push((chord_name(other), first));
+        }
+    }
+    shadows.sort();
+    shadowed_by.sort();
+    let rule_exists = rules.contains_key(chord);
+    for (other, last) in shadows {
+        if rule_exists {
+            let _ =
+                writeln!(out, "shadows:      {other} when {last} is pressed last");
+        } else {
+            let _ = writeln!(out, "contains:     {other}, which fires on its own");
+        }
+    }
+    for (other, first) in shadowed_by {
+        let _ = writeln!(out, "shadowed by:  {other} when {first} is held first");
+    }
+}
+
+fn explain_stick(
+    out: &mut String,
+    binding: &BindingContext,
+    sources: &[RuleSource],
+    side: StickSide,
+) {
+    let _ = writeln!(out, "stick:        {}", format!("{side:?}").to_lowercase());
+    let Some(mode) = binding.stick_rules().and_then(|rules| rules.get(&side)) else {
+        let _ = writeln!(out, "mode:         none");
+        return;
+    };
+    let defining: Vec<&RuleSource> = sources
+        .iter()
+        .filter(|source| source.rules.sticks.contains_key(&side))
+        .collect();
+    let _ = writeln!(out, "mode:         {mode:?}");
+    if let Some((winner, overridden)) = defining.split_last() {
+        let _ = writeln!(out, "from:         {}", describe_origin(&winner.origin));
+        for source in overridden.iter().rev() {
+            let _ = writeln!(
+                out,
+                "overrides:    {}: {:?}",
+                describe_origin(&source.origin),
+                source.rules.sticks[&side],
+            );
+        }
+    }
+}
+
+/// Explain `target` for `app` given the merged `profile` and the unmerged
+/// `sources` for the same app.
+pub(crate) fn explain(
+    profile: &Profile,
+    sources: &[RuleSource],
+    app: &str,
+    target: &Target,
+) -> Result<String, String> {
+    let binding = BindingContext::rebuild(Some(profile), app);
+    let mut out = String::new();
+    let app_label = if app.is_empty() { "(none)" } else { app };
+    let _ = writeln!(
+        out,
+        "app:          {app_label} ({})",
+        describe_source(binding.source())
+    );
+    let sources = effective_sources(&binding, sources);
+    match target {
+        Target::Chord(text) => {
+            let chord = parse_chord(text)
+                .ok_or_else(|| format!("invalid chord `{text}`"))?;
+            explain_chord(&mut out, &binding, sources, &chord);
+        }
+        Target::Stick(side) => explain_stick(&mut out, &binding, sources, *side),
+    }
+    Ok(out)
+}
+
+/// Explain `target` against the workspace profile.
+pub(crate) fn run(
+    workspace: Option<&str>,
+    app: Option<&str>,
+    target: Target,
+) -> Result<String, String> {
+    let (path, content) = read_profile(workspace)?;
+    let app = app.unwrap_or_default();
+    let parse_error = |e| format!("failed to parse {}: {e}", path.display());
+    let profile = parse_profile(&content).map_err(parse_error)?;
+    let sources = parse_rule_sources(&content, app).map_err(parse_error)?;
+    explain(&profile, &sources, app, &target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+version: 1
+groups:
+  messaging: [com.tinyspeck.slackmacgap, ru.keepcoder.Telegram]
+blacklist: [com.apple.Terminal]
+rules:
+  common:
+    buttons:
+      dpad_left:
+        tap: arrow_left
+      lt+dpad_left:
+        keystroke: cmd+shift+tab
+  $messaging:
+    buttons:
+      lt+dpad_left:
+        keystroke: ctrl+shift+tab
+        vibrate: 20
+      lt+rt+dpad_left:
+        tap: cmd+1
+    sticks:
+      left:
+        mode: arrows
+"#;
+
+    fn explain_for(app: &str, target: Target) -> String {
+        let profile = parse_profile(PROFILE).unwrap();
+        let sources = parse_rule_sources(PROFILE, app).unwrap();
+        explain(&profile, &sources, app, &target).unwrap()
+    }
+
+    #[test]
+    fn reports_source_overrides_and_shadowing() {
+        let report = explain_for(
+            "com.tinyspeck.slackmacgap",
+            Target::Chord("dpad_left+lt".into()),
+        );
+        assert_eq!(
+            report,
+            "\
+app:          com.tinyspeck.slackmacgap (app rules merged over common)
+chord:        lt+dpad_left
+fires:        keystroke ctrl+shift+tab
+from:         `$messaging` via group $messaging
+overrides:    common: keystroke shift+cmd+tab
+repeat:       after 400ms, every 50ms
+vibrate:      20ms
+shadows:      dpad_left when dpad_left is pressed last
+shadowed by:  lt+rt+dpad_left when rt is held first
+"
+        );
+    }
+
+    #[test]
+    fn reports_common_only_and_missing_rules() {
+        let report =
+            explain_for("com.apple.Safari", Target::Chord("lt+dpad_left".into()));
+        assert!(report.contains("(no app rules, common only)"), "{report}");
+        assert!(report.contains("from:         common\n"), "{report}");
+        assert!(!report.contains("shadowed by"), "{report}");
+
+        let report =
+            explain_for("com.apple.Terminal", Target::Chord("dpad_left".into()));
+        assert!(report.contains("(blacklisted)"), "{report}");
+        assert!(report.contains("fires:        nothing"), "{report}");
+    }
+
+    #[test]
+    fn explains_sticks() {
+        let report =
+            explain_for("ru.keepcoder.Telegram", Target::Stick(StickSide::Left));
+        assert!(report.contains("mode:         Arrows("), "{report}");
+        assert!(report.contains("from:         `$messaging` via group $messaging"));
+        let report =
+            explain_for("ru.keepcoder.Telegram", Target::Stick(StickSide::Right));
+        assert!(report.ends_with("mode:         none\n"), "{report}");
+    }
+}
@@ -1,6 +1,7 @@
 pub mod app;
 pub mod runner;
 pub mod logging;
+pub mod shell;
 
 pub use app::Padjutsu;
 pub use app::Effect;
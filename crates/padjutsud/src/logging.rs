@@ -88,6 +88,15 @@ macro_rules! print_warning {
 
 /// Setup the logger.
 pub fn setup(verbose: bool, no_color: bool) {
+    setup_output(verbose, no_color, std::io::stdout());
+}
+
+/// Setup the logger on stderr, leaving stdout to the command's own output.
+pub fn setup_stderr(verbose: bool, no_color: bool) {
+    setup_output(verbose, no_color, std::io::stderr());
+}
+
+fn setup_output(verbose: bool, no_color: bool, output: impl Into<fern::Output>) {
     let log_level = if verbose {
         log::LevelFilter::Debug
     } else {
@@ -98,7 +107,7 @@ pub fn setup(verbose: bool, no_color: bool) {
         .level_for("padjutsud", log_level)
         .level_for("padjutsu_control", log_level)
         .level_for("padjutsu_workspace", log_level)
-        .chain(std::io::stdout())
+        .chain(output)
         .apply()
         .expect("Unable to set up logger");
 
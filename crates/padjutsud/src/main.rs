@@ -5,53 +5,103 @@ mod cli;
 mod domain;
 mod logging;
 mod runner;
+mod session;
+mod shell;
+mod simulate;
+mod profile_test;
+mod explain;
+mod cheatsheet;
+mod observe;
 #[cfg(target_os = "macos")]
 mod accessibility;
 
-use std::fs::File;
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 use std::{process, time::Duration};
 
 use clap::Parser;
 use colored::Colorize;
 use crossbeam_channel::{select, unbounded};
 use lunchctl::{LaunchAgent, LaunchControllable};
+use signal_hook::consts::{SIGHUP, SIGUSR1};
+use signal_hook::iterator::Signals;
 use crate::activity::{Monitor, NotificationListener};
 
 use padjutsu_control::{Performer, PerformerWorker};
 use padjutsu_gamepad::{ControllerEvent, ControllerManager, set_realtime_priority};
-use padjutsu_workspace::Workspace;
+use padjutsu_workspace::{parse_profile, Profile, StickSide, Workspace};
 
-use crate::api::{ApiTransport, Command as ApiCommand, UnixSocket};
+use crate::api::{
+    ApiRequest, ApiTransport, Command as ApiCommand, FeedEvent, FeedFilter,
+    HttpServer, InjectedInput, Response, UnixSocket,
+};
 use crate::app::Padjutsu;
-use crate::cli::{Cli, Command, ControlCommand};
+use crate::cli::{
+    Cli, Command, ControlCommand, InjectArg, InputBackendKind, OutputBackendKind,
+    StickArg,
+};
 use crate::domain::{
-    apply_wake_intents, overdue_wake_event, reduce_event, reschedule_wake,
-    DomainControl, DomainEvent, DomainStep, RuntimeMode, RuntimeState, SystemEvent,
-    TimerEvent, WakeState,
+    apply_wake_intents, overdue_wake_event, reduce_event, reschedule_wake, Clock,
+    DomainControl, DomainEvent, DomainStep, RuntimeMode, RuntimeState, SystemClock,
+    SystemEvent, TimerEvent, WakeState,
 };
 use crate::runner::ActionRunner;
+use crate::session::StopOnDrop;
 
 const APP_LABEL: &str = "me.gulya.padjutsu";
 
+/// Wake period while only idle ticking is needed.
+const WAKE_IDLE_PERIOD: Duration = Duration::from_millis(16);
+/// Wake period while sticks are moving.
+const WAKE_FAST_PERIOD: Duration = Duration::from_millis(5);
+
 fn main() -> process::ExitCode {
     let cli = Cli::parse();
-    if cli.command != Command::Observe {
-        logging::setup(cli.verbose, cli.no_color);
+    match cli.command {
+        // Reports go to stdout; keep logs out of the way.
+        Command::Observe { .. }
+        | Command::Simulate { .. }
+        | Command::Explain { .. }
+        | Command::Cheatsheet { .. }
+        | Command::Replay { output: None, .. } => {
+            logging::setup_stderr(cli.verbose, cli.no_color)
+        }
+        // One simulation per case; their logs only matter when debugging.
+        Command::TestProfile { .. } if !cli.verbose => {}
+        Command::TestProfile { .. } => logging::setup_stderr(true, cli.no_color),
+        _ => logging::setup(cli.verbose, cli.no_color),
     }
 
     let bin_path = std::env::current_exe().unwrap();
 
     match cli.command {
-        Command::Run { workspace } => {
+        Command::Run {
+            workspace,
+            output,
+            input,
+            http_port,
+        } => {
             let workspace_path = resolve_workspace_path(workspace.as_deref());
+            #[cfg(target_os = "linux")]
+            if output == OutputBackendKind::Uinput {
+                // A missing or broken profile is reported by the daemon.
+                if let Ok((path, profile)) = load_profile(workspace_path.to_str()) {
+                    if let Err(e) = check_uinput_text(&profile) {
+                        print_error!("{}: {e}", path.display());
+                        return process::ExitCode::FAILURE;
+                    }
+                }
+            }
             #[cfg(target_os = "macos")]
             {
                 let _ = accessibility::request_if_needed();
             }
-            run_event_loop(Some(workspace_path));
+            run_event_loop(Some(workspace_path), output, input, http_port);
         }
-        Command::Start { workspace } => {
+        Command::Start {
+            workspace,
+            http_port,
+        } => {
             let workspace_path = resolve_workspace_path(workspace.as_deref());
 
             #[cfg(target_os = "macos")]
@@ -66,6 +116,10 @@ fn main() -> process::ExitCode {
             arguments.push("run".to_string());
             arguments.push("--workspace".to_string());
             arguments.push(workspace_path.display().to_string());
+            if let Some(port) = http_port {
+                arguments.push("--http-port".to_string());
+                arguments.push(port.to_string());
+            }
 
             let agent = LaunchAgent {
                 label: APP_LABEL.to_string(),
@@ -125,7 +179,20 @@ fn main() -> process::ExitCode {
                 }
             }
         }
-        Command::Status => {
+        Command::Status { workspace } => {
+            let workspace_path = resolve_workspace_path(workspace.as_deref());
+            match control_socket(&workspace_path).send_event(ApiCommand::Status) {
+                Ok(Response::Status(report)) => {
+                    print!("{report}");
+                    return process::ExitCode::SUCCESS;
+                }
+                Ok(other) => {
+                    print_error!("unexpected response {other:?}");
+                    return process::ExitCode::FAILURE;
+                }
+                Err(e) => print_debug!("daemon did not answer: {e}"),
+            }
+
             if !LaunchAgent::exists(APP_LABEL) {
                 print_info!("Agent does not exist");
                 return process::ExitCode::FAILURE;
@@ -145,25 +212,184 @@ fn main() -> process::ExitCode {
                 }
             }
         }
-        Command::Observe => {
-            logging::setup(true, cli.no_color);
+        Command::Observe {
+            workspace,
+            controller,
+            kind,
+            json,
+        } => {
+            let workspace_path = resolve_workspace_path(workspace.as_deref());
+            let filter = FeedFilter {
+                controller,
+                kinds: kind.into_iter().map(Into::into).collect(),
+            };
+            let socket = control_socket(&workspace_path);
+            if let Err(e) = observe::run(&socket, filter, json) {
+                print_error!("{e}");
+                return process::ExitCode::FAILURE;
+            }
+        }
+        Command::Record { output, input } => {
+            if let Err(e) = session::record(&output, input) {
+                print_error!("{e}");
+                return process::ExitCode::FAILURE;
+            }
+        }
+        Command::Replay {
+            session,
+            workspace,
+            speed,
+            output,
+        } => {
             #[cfg(target_os = "macos")]
-            {
+            if output.is_some() {
                 let _ = accessibility::request_if_needed();
             }
-            run_event_loop(None);
+            if let Err(e) =
+                session::replay(&session, workspace.as_deref(), speed, output)
+            {
+                print_error!("{e}");
+                return process::ExitCode::FAILURE;
+            }
+        }
+        Command::Simulate {
+            script,
+            workspace,
+            app,
+        } => {
+            if let Err(e) =
+                simulate::run(&script, workspace.as_deref(), app.as_deref())
+            {
+                print_error!("{e}");
+                return process::ExitCode::FAILURE;
+            }
+        }
+        Command::TestProfile { workspace } => {
+            match profile_test::run(workspace.as_deref()) {
+                Ok(true) => {}
+                Ok(false) => return process::ExitCode::FAILURE,
+                Err(e) => {
+                    print_error!("{e}");
+                    return process::ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Explain {
+            workspace,
+            app,
+            buttons,
+            stick,
+        } => {
+            let target = match (buttons, stick) {
+                (Some(chord), _) => explain::Target::Chord(chord),
+                (None, Some(StickArg::Left)) => {
+                    explain::Target::Stick(StickSide::Left)
+                }
+                (None, Some(StickArg::Right)) => {
+                    explain::Target::Stick(StickSide::Right)
+                }
+                (None, None) => unreachable!("clap requires --buttons or --stick"),
+            };
+            match explain::run(workspace.as_deref(), app.as_deref(), target) {
+                Ok(report) => print!("{report}"),
+                Err(e) => {
+                    print_error!("{e}");
+                    return process::ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Cheatsheet {
+            workspace,
+            format,
+            output,
+        } => {
+            let written =
+                cheatsheet::run(workspace.as_deref(), format).and_then(|sheet| {
+                    match output {
+                        Some(path) => fs::write(&path, sheet).map_err(|e| {
+                            format!("failed to write {}: {e}", path.display())
+                        }),
+                        None => {
+                            print!("{sheet}");
+                            Ok(())
+                        }
+                    }
+                });
+            if let Err(e) = written {
+                print_error!("{e}");
+                return process::ExitCode::FAILURE;
+            }
         }
         Command::Command { workspace, command } => match command {
             ControlCommand::Rumble { id, ms } => {
                 let workspace_path = resolve_workspace_path(workspace.as_deref());
-                match UnixSocket::new(workspace_path)
+                match control_socket(&workspace_path)
                     .send_event(ApiCommand::Rumble { id, ms })
                 {
-                    Ok(_) => {
-                        print_info!("Rumbled controller {:?} for {ms}ms", id);
+                    Ok(Response::Rumbled { controllers }) => {
+                        print_info!(
+                            "Rumbled controllers {controllers:?} for {ms}ms"
+                        );
+                    }
+                    Ok(other) => {
+                        print_error!("unexpected response {other:?}");
+                        return process::ExitCode::FAILURE;
                     }
                     Err(e) => {
-                        print_error!("failed to send rumble command: {e}");
+                        print_error!("rumble failed: {e}");
+                        return process::ExitCode::FAILURE;
+                    }
+                };
+            }
+            ControlCommand::Inject { id, input } => {
+                let workspace_path = resolve_workspace_path(workspace.as_deref());
+                let input = match input {
+                    InjectArg::Press { button } => InjectedInput::Press(button),
+                    InjectArg::Release { button } => InjectedInput::Release(button),
+                    InjectArg::Axis { axis, value } => {
+                        InjectedInput::Axis { axis, value }
+                    }
+                };
+                match control_socket(&workspace_path).send_event(
+                    ApiCommand::Inject {
+                        controller: id,
+                        input,
+                    },
+                ) {
+                    Ok(Response::Injected { controller }) => {
+                        print_debug!("injected into controller {controller}");
+                    }
+                    Ok(other) => {
+                        print_error!("unexpected response {other:?}");
+                        return process::ExitCode::FAILURE;
+                    }
+                    Err(e) => {
+                        print_error!("inject failed: {e}");
+                        return process::ExitCode::FAILURE;
+                    }
+                };
+            }
+            ControlCommand::Pause { for_ms } => {
+                return set_enabled(workspace, ApiCommand::Pause { for_ms });
+            }
+            ControlCommand::Resume => {
+                return set_enabled(workspace, ApiCommand::Resume);
+            }
+            ControlCommand::Toggle => {
+                return set_enabled(workspace, ApiCommand::Toggle);
+            }
+            ControlCommand::Reload => {
+                let workspace_path = resolve_workspace_path(workspace.as_deref());
+                let socket = control_socket(&workspace_path);
+                match socket.send_event(ApiCommand::Reload) {
+                    Ok(Response::Reloaded) => print_info!("Profile reloaded"),
+                    Ok(other) => {
+                        print_error!("unexpected response {other:?}");
+                        return process::ExitCode::FAILURE;
+                    }
+                    Err(e) => {
+                        print_error!("reload failed: {e}");
+                        return process::ExitCode::FAILURE;
                     }
                 };
             }
@@ -173,6 +399,37 @@ fn main() -> process::ExitCode {
     process::ExitCode::SUCCESS
 }
 
+/// Send a pause, resume or toggle and report the resulting state.
+fn set_enabled(workspace: Option<String>, command: ApiCommand) -> process::ExitCode {
+    let workspace_path = resolve_workspace_path(workspace.as_deref());
+    match control_socket(&workspace_path).send_event(command) {
+        Ok(Response::Enabled { enabled: true, .. }) => {
+            print_info!("Mappings enabled");
+        }
+        Ok(Response::Enabled {
+            enabled: false,
+            resumes_in_ms: None,
+        }) => {
+            print_info!("Mappings paused");
+        }
+        Ok(Response::Enabled {
+            enabled: false,
+            resumes_in_ms: Some(ms),
+        }) => {
+            print_info!("Mappings paused, resuming in {}s", ms.div_ceil(1000));
+        }
+        Ok(other) => {
+            print_error!("unexpected response {other:?}");
+            return process::ExitCode::FAILURE;
+        }
+        Err(e) => {
+            print_error!("{e}");
+            return process::ExitCode::FAILURE;
+        }
+    }
+    process::ExitCode::SUCCESS
+}
+
 fn resolve_workspace_path(workspace: Option<&str>) -> PathBuf {
     let workspace = workspace.map(PathBuf::from);
     if let Some(workspace) = workspace {
@@ -193,6 +450,60 @@ fn resolve_workspace_path(workspace: Option<&str>) -> PathBuf {
     }
 }
 
+/// The daemon's control socket for a workspace; it lives next to the lock
+/// file.
+fn control_socket(workspace_path: &Path) -> UnixSocket {
+    UnixSocket::new(workspace_path.parent().unwrap_or(workspace_path))
+}
+
+/// Load a profile for offline use: `workspace` may name a profile file
+/// directly or a workspace directory.
+fn read_profile(workspace: Option<&str>) -> Result<(PathBuf, String), String> {
+    let path = match workspace.map(PathBuf::from) {
+        Some(path) if !path.is_dir() => path,
+        path => Workspace::new(path.as_deref())
+            .map_err(|e| e.to_string())?
+            .profile_path(),
+    };
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    Ok((path, content))
+}
+
+fn load_profile(workspace: Option<&str>) -> Result<(PathBuf, Profile), String> {
+    let (path, content) = read_profile(workspace)?;
+    let profile = parse_profile(&content)
+        .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+    Ok((path, profile))
+}
+
+/// uinput types ASCII only, so `type` text outside it fails before the
+/// daemon starts instead of on the button press.
+#[cfg(target_os = "linux")]
+fn check_uinput_text(profile: &Profile) -> Result<(), String> {
+    use padjutsu_workspace::ButtonAction;
+
+    let untypeable = profile
+        .rules
+        .iter()
+        .flat_map(|(app, rules)| rules.buttons.values().map(move |rule| (app, rule)))
+        .filter_map(|(app, rule)| match &rule.action {
+            ButtonAction::Type { text, .. }
+                if !padjutsu_control::UinputBackend::can_type(text) =>
+            {
+                Some((app, text))
+            }
+            _ => None,
+        })
+        .min();
+    match untypeable {
+        Some((app, text)) => Err(format!(
+            "{app}: uinput can only type ASCII text, not {text:?}"
+        )),
+        None => Ok(()),
+    }
+}
+
 fn run_effects(
     action_runner: &mut ActionRunner<'_>,
     effects: Vec<crate::app::Effect>,
@@ -207,6 +518,7 @@ fn apply_domain_step(
     runtime_state: &mut RuntimeState,
     action_runner: &mut ActionRunner<'_>,
     wake_state: &mut WakeState,
+    origin: Option<padjutsu_gamepad::ControllerId>,
 ) -> DomainControl {
     for update in step.transition.controller_updates {
         match update.next_state {
@@ -216,6 +528,14 @@ fn apply_domain_step(
             }
         }
     }
+    for update in step.transition.stick_updates {
+        print_debug!(
+            "stick state transition: controller={} {:?} -> {:?}",
+            update.controller_id,
+            update.previous,
+            update.next
+        );
+    }
     if let Some(crate::domain::ModeTransition::Set(next_mode)) = step.transition.mode
     {
         runtime_state.set_mode(next_mode);
@@ -223,7 +543,24 @@ fn apply_domain_step(
     if let Some(crate::domain::ShellTransition::Set(shell)) = step.transition.shell {
         action_runner.set_shell(shell);
     }
+    for subscriber in step.transition.subscriptions {
+        runtime_state.feed().subscribe(subscriber);
+    }
+    if !runtime_state.feed().is_empty() {
+        for effect in &step.transition.effects {
+            runtime_state
+                .feed()
+                .publish(FeedEvent::from_effect(effect, origin));
+        }
+    }
     run_effects(action_runner, step.transition.effects);
+    for hook in step.transition.hooks {
+        action_runner.run_hook(&hook.command, hook.env);
+    }
+    for reply in step.transition.replies {
+        // The client may have hung up already; nothing to do then.
+        let _ = reply.to.send(reply.reply);
+    }
     apply_wake_intents(wake_state, step.transition.wake);
     step.control
 }
@@ -234,15 +571,15 @@ fn process_overdue_wake(
     action_runner: &mut ActionRunner<'_>,
     manager: &ControllerManager,
     wake_state: &mut WakeState,
+    clock: &dyn Clock,
 ) -> DomainControl {
-    let Some(event) =
-        overdue_wake_event(padjutsu, wake_state, std::time::Instant::now())
-    else {
+    let Some(event) = overdue_wake_event(padjutsu, wake_state, clock.now()) else {
         return DomainControl::Continue;
     };
 
-    let step = reduce_event(event, padjutsu, manager, runtime_state, wake_state);
-    apply_domain_step(step, runtime_state, action_runner, wake_state)
+    let step =
+        reduce_event(event, padjutsu, manager, runtime_state, wake_state, clock);
+    apply_domain_step(step, runtime_state, action_runner, wake_state, None)
 }
 
 fn dispatch_domain_event(
@@ -252,9 +589,42 @@ fn dispatch_domain_event(
     action_runner: &mut ActionRunner<'_>,
     manager: &ControllerManager,
     wake_state: &mut WakeState,
+    clock: &dyn Clock,
 ) -> DomainControl {
-    let step = reduce_event(event, padjutsu, manager, runtime_state, wake_state);
-    apply_domain_step(step, runtime_state, action_runner, wake_state)
+    let observed = if runtime_state.feed().is_empty() {
+        None
+    } else {
+        FeedEvent::from_domain(&event)
+    };
+    let origin = observed.as_ref().and_then(FeedEvent::controller);
+    if let Some(observed) = observed {
+        runtime_state.feed().publish(observed);
+    }
+    if matches!(event, DomainEvent::Api(_)) {
+        runtime_state.set_output_stats(action_runner.worker_stats());
+    }
+    let mut step =
+        reduce_event(event, padjutsu, manager, runtime_state, wake_state, clock);
+    let injected = std::mem::take(&mut step.transition.injected);
+    let control =
+        apply_domain_step(step, runtime_state, action_runner, wake_state, origin);
+    if let DomainControl::Break = control {
+        return control;
+    }
+    for event in injected {
+        if let DomainControl::Break = dispatch_domain_event(
+            DomainEvent::Controller(event),
+            padjutsu,
+            runtime_state,
+            action_runner,
+            manager,
+            wake_state,
+            clock,
+        ) {
+            return DomainControl::Break;
+        }
+    }
+    DomainControl::Continue
 }
 
 fn dispatch_and_process_overdue(
@@ -264,6 +634,7 @@ fn dispatch_and_process_overdue(
     action_runner: &mut ActionRunner<'_>,
     manager: &ControllerManager,
     wake_state: &mut WakeState,
+    clock: &dyn Clock,
 ) -> DomainControl {
     let control = dispatch_domain_event(
         event,
@@ -272,14 +643,41 @@ fn dispatch_and_process_overdue(
         action_runner,
         manager,
         wake_state,
+        clock,
     );
     if let DomainControl::Break = control {
         return DomainControl::Break;
     }
-    process_overdue_wake(padjutsu, runtime_state, action_runner, manager, wake_state)
+    process_overdue_wake(
+        padjutsu,
+        runtime_state,
+        action_runner,
+        manager,
+        wake_state,
+        clock,
+    )
 }
 
-fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
+fn spawn_performer_worker(
+    output: OutputBackendKind,
+) -> Result<PerformerWorker, String> {
+    match output {
+        OutputBackendKind::Enigo => Performer::new()
+            .map(PerformerWorker::spawn)
+            .map_err(|e| e.to_string()),
+        #[cfg(target_os = "linux")]
+        OutputBackendKind::Uinput => padjutsu_control::UinputBackend::new()
+            .map(PerformerWorker::spawn)
+            .map_err(|e| format!("/dev/uinput: {e}")),
+    }
+}
+
+fn run_event_loop(
+    maybe_workspace_path: Option<PathBuf>,
+    output: OutputBackendKind,
+    input: InputBackendKind,
+    http_port: Option<u16>,
+) {
     // Ensure only one instance runs per workspace by holding an exclusive flock.
     if let Some(ws) = maybe_workspace_path.as_ref() {
         let lock_path = ws.parent().unwrap_or(ws).join("padjutsud.lock");
@@ -339,19 +737,53 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
 
     // Handle Ctrl+C to exit cleanly
     let (stop_tx, stop_rx) = unbounded::<()>();
+    let loop_done_tx = monitor_stop_tx.clone();
     ctrlc::set_handler(move || {
         let _ = stop_tx.send(());
         let _ = monitor_stop_tx.send(());
     })
     .expect("failed to set Ctrl+C handler");
 
+    // SIGHUP reloads the profile, SIGUSR1 dumps the runtime state to the log.
+    let (signal_tx, signal_rx) = unbounded::<SystemEvent>();
+    match Signals::new([SIGHUP, SIGUSR1]) {
+        Ok(mut signals) => {
+            std::thread::spawn(move || {
+                for signal in signals.forever() {
+                    let event = match signal {
+                        SIGHUP => SystemEvent::Reload,
+                        _ => SystemEvent::DumpState,
+                    };
+                    if signal_tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Err(e) => print_error!("failed to set signal handlers: {e}"),
+    }
+
     let workspace_path = maybe_workspace_path.to_owned();
 
     // Start control socket on the main thread and forward commands into the event loop.
-    let (api_tx, api_rx) = unbounded::<ApiCommand>();
+    let (api_tx, api_rx) = unbounded::<ApiRequest>();
+    let http = http_port.zip(workspace_path.as_deref());
+    let _http_handle = http.and_then(|(port, ws_path)| {
+        // The token goes next to the control socket.
+        let token_dir = ws_path.parent().unwrap_or(ws_path);
+        match HttpServer::write_token(token_dir)
+            .and_then(|token| HttpServer::bind(port, token))
+            .and_then(|server| server.listen_events(api_tx.clone()))
+        {
+            Ok(h) => Some(h),
+            Err(e) => {
+                print_error!("failed to start http api on port {port}: {e}");
+                None
+            }
+        }
+    });
     let _control_handle = workspace_path.clone().and_then(|ws_path| {
-        let sock_dir = ws_path.parent().unwrap_or(&ws_path).to_owned();
-        match UnixSocket::new(sock_dir).listen_events(api_tx) {
+        match control_socket(&ws_path).listen_events(api_tx) {
             Ok(h) => Some(h),
             Err(e) => {
                 print_error!("failed to start api server: {e}");
@@ -364,26 +796,32 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
     let event_loop = std::thread::Builder::new()
         .name("event-loop".into())
         .stack_size(512 * 1024)
-        .spawn(move || {
+        .spawn(move || -> bool {
+            // Let the monitor loop return however the event loop ends.
+            let _stop_monitor = StopOnDrop(loop_done_tx);
             set_realtime_priority();
 
-            let manager = ControllerManager::new()
+            let manager = ControllerManager::with_backend(input.into())
                 .expect("failed to start controller manager");
             let rx = manager.subscribe();
-            let keypress = Performer::new().expect("failed to start keypress");
-            let worker = PerformerWorker::spawn(keypress);
+            let worker = match spawn_performer_worker(output) {
+                Ok(worker) => worker,
+                Err(e) => {
+                    print_error!("failed to start {output:?} output backend: {e}");
+                    return false;
+                }
+            };
             // Single coalesced wake timer: earliest of movement tick and repeat deadlines.
             let mut wake_rx = crossbeam_channel::never::<std::time::Instant>();
-            let idle_period = Duration::from_millis(16);
-            let fast_period = Duration::from_millis(5);
             let mut runtime_state = RuntimeState::new(RuntimeMode::Booting);
-            let mut wake_state = WakeState::new(std::time::Instant::now());
+            let clock = SystemClock;
+            let mut wake_state = WakeState::new(clock.now());
 
             let workspace = match Workspace::new(workspace_path.as_deref()) {
                 Ok(workspace) => workspace,
                 Err(e) => {
                     print_error!("failed to start workspace: {e}");
-                    return;
+                    return false;
                 }
             };
 
@@ -392,6 +830,9 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                 .map(|_| workspace.start_profile_watcher())
                 .transpose()
                 .expect("failed to start workspace watcher");
+            if maybe_watcher.is_some() {
+                padjutsu.set_profile_path(workspace.profile_path());
+            }
 
             let (maybe_workspace_rx, _profile_watcher) = match maybe_watcher {
                 Some((watcher, rx)) => (Some(rx), Some(watcher)),
@@ -413,6 +854,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                     &mut action_runner,
                     &manager,
                     &mut wake_state,
+                    &clock,
                 ) {
                     break;
                 }
@@ -431,6 +873,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                             &mut action_runner,
                             &manager,
                             &mut wake_state,
+                            &clock,
                         ) {
                             break;
                         }
@@ -445,6 +888,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                                 &mut action_runner,
                                 &manager,
                                 &mut wake_state,
+                                &clock,
                             ) {
                                 break;
                             }
@@ -459,6 +903,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                                     &mut action_runner,
                                     &manager,
                                     &mut wake_state,
+                                    &clock,
                                 ) {
                                     break;
                                 }
@@ -471,14 +916,30 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                         }
                     }
                 recv(api_rx) -> cmd => {
-                        if let Ok(command) = cmd {
+                        if let Ok(request) = cmd {
+                            if let DomainControl::Break = dispatch_domain_event(
+                                DomainEvent::Api(request),
+                                &mut padjutsu,
+                                &mut runtime_state,
+                                &mut action_runner,
+                                &manager,
+                                &mut wake_state,
+                                &clock,
+                            ) {
+                                break;
+                            }
+                        }
+                }
+                recv(signal_rx) -> event => {
+                        if let Ok(event) = event {
                             if let DomainControl::Break = dispatch_domain_event(
-                                DomainEvent::Api(command),
+                                DomainEvent::System(event),
                                 &mut padjutsu,
                                 &mut runtime_state,
                                 &mut action_runner,
                                 &manager,
                                 &mut wake_state,
+                                &clock,
                             ) {
                                 break;
                             }
@@ -492,6 +953,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                         &mut action_runner,
                         &manager,
                         &mut wake_state,
+                        &clock,
                     ) {
                         break;
                     }
@@ -505,6 +967,7 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                     &mut action_runner,
                     &manager,
                     &mut wake_state,
+                    &clock,
                 ) {
                     break;
                 }
@@ -521,17 +984,19 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                     &mut action_runner,
                     &manager,
                     &mut wake_state,
+                    &clock,
                 ) {
                     break;
                 }
                 }
                 if wake_state.need_reschedule {
-                    let now = std::time::Instant::now();
+                    let now = clock.now();
                     let wake_plan = reschedule_wake(
                         &padjutsu,
                         &mut wake_state,
-                        idle_period,
-                        fast_period,
+                        now,
+                        WAKE_IDLE_PERIOD,
+                        WAKE_FAST_PERIOD,
                     );
                     if let Some(due) = wake_plan.next_due {
                         let dur = if due > now { due - now } else { Duration::ZERO };
@@ -550,12 +1015,165 @@ fn run_event_loop(maybe_workspace_path: Option<PathBuf>) {
                     wake_state.need_reschedule = false;
                 }
             }
+            let stats = worker.stats();
+            print_debug!(
+                "performer worker: lossless_depth={} lossless_peak={} motion_depth={} motion_dropped={} disconnected_dropped={}",
+                stats.lossless_depth,
+                stats.lossless_peak,
+                stats.motion_depth,
+                stats.motion_dropped,
+                stats.disconnected_dropped
+            );
+            true
         })
         .expect("failed to spawn event loop thread");
 
     // Start monitoring on the main thread (blocks until error/exit)
     monitor.run();
-    if let Err(e) = event_loop.join() {
-        print_error!("event loop error: {e:?}");
+    match event_loop.join() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            print_error!("event loop error: {e:?}");
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use padjutsu_control::{Key, OutputOp, RecordingBackend};
+    use padjutsu_gamepad::{Backend, Button};
+
+    use crate::domain::ManualClock;
+
+    use super::*;
+
+    /// Run `events` through the event loop's dispatch with `profile` loaded
+    /// and return what reached the output backend.
+    fn run(
+        profile: &str,
+        manager: &ControllerManager,
+        events: impl IntoIterator<Item = DomainEvent>,
+    ) -> Vec<OutputOp> {
+        let mut padjutsu = Padjutsu::new();
+        padjutsu.set_workspace(parse_profile(profile).unwrap());
+        let mut runtime_state = RuntimeState::new(RuntimeMode::Active);
+        let clock = ManualClock::default();
+        let mut wake_state = WakeState::new(clock.now());
+        let (backend, log) = RecordingBackend::new();
+        let worker = PerformerWorker::spawn(backend);
+        let mut action_runner = ActionRunner::new(&worker, manager);
+        for event in events {
+            if let DomainControl::Break = dispatch_domain_event(
+                event,
+                &mut padjutsu,
+                &mut runtime_state,
+                &mut action_runner,
+                manager,
+                &mut wake_state,
+                &clock,
+            ) {
+                break;
+            }
+        }
+        drop(action_runner);
+        // Dropping the worker runs everything queued before it stops.
+        drop(worker);
+        log.ops()
+    }
+
+    #[test]
+    fn a_button_press_reaches_the_output_backend() {
+        use enigo::Direction::{Press, Release};
+
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let pad = manager.connect_virtual("Virtual").unwrap();
+        let info = pad.info().clone();
+        let id = info.id;
+        let ops = run(
+            r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: shift+x
+"#,
+            &manager,
+            [
+                ControllerEvent::Connected(info),
+                ControllerEvent::ButtonPressed {
+                    id,
+                    button: Button::A,
+                },
+                ControllerEvent::ButtonReleased {
+                    id,
+                    button: Button::A,
+                },
+            ]
+            .map(DomainEvent::Controller),
+        );
+        assert_eq!(
+            ops,
+            [
+                OutputOp::Key(Key::Shift, Press),
+                OutputOp::Key(Key::X, Press),
+                OutputOp::Key(Key::X, Release),
+                OutputOp::Key(Key::Shift, Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_virtual_controller_drives_the_event_loop() {
+        use enigo::Direction::{Press, Release};
+
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let rx = manager.subscribe();
+        let mut pad = manager.connect_virtual("Virtual").unwrap();
+        pad.press(Button::B);
+        pad.press(Button::A);
+        pad.release(Button::A);
+        pad.release(Button::B);
+        let ops = run(
+            r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        keystroke: x
+      b:
+        hold: ctrl
+"#,
+            &manager,
+            rx.try_iter().map(DomainEvent::Controller),
+        );
+        assert_eq!(
+            ops,
+            [
+                OutputOp::Key(Key::Control, Press),
+                OutputOp::Key(Key::X, Press),
+                OutputOp::Key(Key::X, Release),
+                OutputOp::Key(Key::Control, Release),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn uinput_refuses_non_ascii_type_text() {
+        let profile = |text: &str| {
+            parse_profile(&format!(
+                "version: 1\nrules:\n  common:\n    buttons:\n      a:\n        type: {text:?}\n"
+            ))
+            .unwrap()
+        };
+        assert_eq!(check_uinput_text(&profile("git status\n")), Ok(()));
+        assert_eq!(
+            check_uinput_text(&profile("café")),
+            Err(r#"common: uinput can only type ASCII text, not "café""#.into())
+        );
     }
 }
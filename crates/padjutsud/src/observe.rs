@@ -0,0 +1,58 @@
+//! `padjutsud observe`: a thin client printing the running daemon's event
+//! feed.
+
+use std::io::{self, Write};
+
+use crate::api::{ApiTransport, FeedEvent, FeedFilter, FeedKind, UnixSocket};
+use crate::cli::ObserveKind;
+
+impl From<ObserveKind> for FeedKind {
+    fn from(kind: ObserveKind) -> Self {
+        match kind {
+            ObserveKind::Controller => Self::Controller,
+            ObserveKind::App => Self::App,
+            ObserveKind::Profile => Self::Profile,
+            ObserveKind::Effects => Self::Effects,
+        }
+    }
+}
+
+fn write_event(
+    out: &mut impl Write,
+    event: &FeedEvent,
+    json: bool,
+) -> io::Result<()> {
+    if json {
+        serde_json::to_writer(&mut *out, event)?;
+        writeln!(out)?;
+    } else {
+        writeln!(out, "{event}")?;
+    }
+    // Readers are often pipes; don't hold events back in a buffer.
+    out.flush()
+}
+
+/// Print events from the daemon behind `socket` until it goes away.
+pub(crate) fn run(
+    socket: &UnixSocket,
+    filter: FeedFilter,
+    json: bool,
+) -> Result<(), String> {
+    let mut client = socket
+        .connect(&["observe"])
+        .map_err(|e| format!("failed to reach the daemon: {e}"))?;
+    client
+        .subscribe(filter)
+        .map_err(|e| format!("failed to subscribe: {e}"))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    while let Some(event) = client.next_event().map_err(|e| e.to_string())? {
+        match write_event(&mut out, &event, json) {
+            Ok(()) => {}
+            // The reader went away, e.g. `observe | head`.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(())
+}
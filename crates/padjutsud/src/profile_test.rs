@@ -0,0 +1,183 @@
+//! Binding tests embedded in a profile.
+//!
+//! Each case in the profile's `tests` section is run through the
+//! [simulator](crate::simulate) with every app it names frontmost, and the
+//! actions it produces are compared with `expect`. Mouse motion and
+//! scrolling depend on stick tuning rather than bindings, so they are left
+//! out of the comparison.
+//!
+//! ```yaml
+//! tests:
+//!   - app: $messaging
+//!     input: press lt; press dpad_left; release dpad_left; release lt
+//!     expect: [tap cmd+shift+tab]
+//! ```
+
+use std::str::FromStr;
+
+use colored::Colorize;
+use padjutsu_control::KeyCombo;
+use padjutsu_workspace::{parse_profile, parse_profile_tests, Profile, ProfileTest};
+
+use crate::app::Effect;
+use crate::simulate::{parse_script, simulate};
+use crate::read_profile;
+
+/// The result of one test case with one frontmost app.
+#[derive(Debug)]
+pub(crate) struct Outcome {
+    pub name: String,
+    pub app: Option<String>,
+    pub expected: Vec<String>,
+    pub actual: Vec<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// Bring an expected action to the form [`Effect`] prints as, so key
+/// combos can be written in any modifier order.
+fn normalize(expected: &str) -> Result<String, String> {
+    let expected = expected.trim();
+    let (kind, rest) = expected.split_once(' ').unwrap_or((expected, ""));
+    match kind {
+        "tap" | "key_press" | "key_release" => {
+            let combo = KeyCombo::from_str(rest.trim())
+                .map_err(|e| format!("`{expected}`: {e}"))?;
+            Ok(format!("{kind} {combo}"))
+        }
+        _ => Ok(expected.to_string()),
+    }
+}
+
+fn compared(effect: &Effect) -> bool {
+    !matches!(effect, Effect::MouseMove { .. } | Effect::Scroll { .. })
+}
+
+/// Run every test case against `profile`.
+pub(crate) fn run_tests(
+    profile: &Profile,
+    tests: &[ProfileTest],
+) -> Result<Vec<Outcome>, String> {
+    let mut outcomes = Vec::new();
+    for test in tests {
+        let steps =
+            parse_script(&test.input).map_err(|e| format!("{}: {e}", test.name))?;
+        let expected = test
+            .expect
+            .iter()
+            .map(|e| normalize(e))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{}: {e}", test.name))?;
+        let apps: Vec<Option<&str>> = if test.apps.is_empty() {
+            vec![None]
+        } else {
+            test.apps.iter().map(|app| Some(&**app)).collect()
+        };
+        for app in apps {
+            let actual = simulate(profile.clone(), app, steps.clone())
+                .iter()
+                .map(|(_, effect)| effect)
+                .filter(|effect| compared(effect))
+                .map(Effect::to_string)
+                .collect();
+            outcomes.push(Outcome {
+                name: test.name.clone(),
+                app: app.map(str::to_string),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Run the tests of the workspace profile and print a line per case.
+/// Returns whether all of them passed.
+pub(crate) fn run(workspace: Option<&str>) -> Result<bool, String> {
+    let (path, content) = read_profile(workspace)?;
+    let parse_error = |e| format!("failed to parse {}: {e}", path.display());
+    let profile = parse_profile(&content).map_err(parse_error)?;
+    let tests = parse_profile_tests(&content).map_err(parse_error)?;
+    if tests.is_empty() {
+        println!("no tests in {}", path.display());
+        return Ok(true);
+    }
+
+    let outcomes = run_tests(&profile, &tests)?;
+    let mut failed = 0;
+    for outcome in &outcomes {
+        let label = match &outcome.app {
+            Some(app) => format!("{} [{app}]", outcome.name),
+            None => outcome.name.clone(),
+        };
+        if outcome.passed() {
+            println!("{} {label}", "ok  ".green());
+            continue;
+        }
+        failed += 1;
+        println!("{} {label}", "FAIL".red());
+        println!("       expected: {}", outcome.expected.join(", "));
+        println!("       actual:   {}", outcome.actual.join(", "));
+    }
+    println!("{} passed, {failed} failed", outcomes.len() - failed);
+    Ok(failed == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+version: 1
+groups:
+  messaging: [com.tinyspeck.slackmacgap, ru.keepcoder.Telegram]
+rules:
+  common:
+    buttons:
+      lt+dpad_left:
+        keystroke: cmd+shift+tab
+  ru.keepcoder.Telegram:
+    buttons:
+      lt+dpad_left:
+        keystroke: cmd+[
+tests:
+  - name: previous tab
+    app: $messaging
+    input: press lt; 10ms press dpad_left; 20ms release dpad_left; release lt
+    expect: [tap cmd+shift+tab]
+  - input: press lt; 10ms press dpad_left; release dpad_left; release lt
+    expect: ["tap shift+cmd+tab"]
+"#;
+
+    #[test]
+    fn reports_chord_shadowed_in_group_member() {
+        let profile = parse_profile(PROFILE).unwrap();
+        let tests = parse_profile_tests(PROFILE).unwrap();
+        let outcomes = run_tests(&profile, &tests).unwrap();
+        let summary: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.app.as_deref(), o.passed()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("com.tinyspeck.slackmacgap"), true),
+                (Some("ru.keepcoder.Telegram"), false),
+                (None, true),
+            ]
+        );
+        assert_eq!(outcomes[1].expected, ["tap shift+cmd+tab"]);
+        assert_eq!(outcomes[1].actual, ["tap cmd+bracket_left"]);
+    }
+
+    #[test]
+    fn rejects_bad_expectation() {
+        let err = normalize("tap cmd+nope").unwrap_err();
+        assert!(err.starts_with("`tap cmd+nope`"), "{err}");
+        assert_eq!(normalize(" shell make ").unwrap(), "shell make");
+    }
+}
@@ -1,12 +1,17 @@
-use std::{process::Command, time::Duration};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use ahash::{AHashMap, AHashSet};
 use colored::Colorize;
-use padjutsu_control::{PerformerCmd, PerformerWorker};
+use crossbeam_channel::TrySendError;
+use padjutsu_control::{PerformerCmd, PerformerWorker, WorkerStats};
 use padjutsu_gamepad::ControllerManager;
 
 use padjutsu_workspace::{MouseButton, MouseClickType};
 
-use crate::{app::Effect, print_error, print_info};
+use crate::app::{Effect, HeldShell, ShellRun};
+use crate::shell::{self, ShellChild};
+use crate::{print_debug, print_error, print_info};
 
 const DEFAULT_SHELL: &str = "/bin/zsh";
 
@@ -14,6 +19,10 @@ pub struct ActionRunner<'a> {
     worker: &'a PerformerWorker,
     manager: &'a ControllerManager,
     shell: Option<Box<str>>,
+    /// Commands of `single_instance` actions still running.
+    running: Arc<Mutex<AHashSet<String>>>,
+    /// `shell_hold` children, stopped on release.
+    held: AHashMap<HeldShell, ShellChild>,
 }
 
 impl<'a> ActionRunner<'a> {
@@ -22,15 +31,28 @@ impl<'a> ActionRunner<'a> {
             worker,
             manager,
             shell: None,
+            running: Arc::default(),
+            held: AHashMap::new(),
         }
     }
 
+    /// Current queues of the performer worker.
+    pub fn worker_stats(&self) -> WorkerStats {
+        self.worker.stats()
+    }
+
     fn send(&self, cmd: PerformerCmd) {
-        // Non-blocking: if the worker queue is full (worker stuck on a slow
-        // CGEventPost), drop the command. For movement/scroll the next tick
-        // produces a fresh state; for keys this is rare and acceptable.
-        if let Err(e) = self.worker.try_send(cmd) {
-            print_error!("performer queue full, dropping command: {e:?}");
+        // Non-blocking. Discrete commands are never dropped; motion is
+        // dropped when the worker is backed up (slow CGEventPost) and the
+        // next tick produces a fresh state.
+        match self.worker.send(cmd) {
+            Ok(()) => {}
+            Err(TrySendError::Full(cmd)) => {
+                print_debug!("performer motion lane full, dropping {cmd:?}");
+            }
+            Err(TrySendError::Disconnected(cmd)) => {
+                print_error!("performer worker gone, dropping {cmd:?}");
+            }
         }
     }
 
@@ -54,9 +76,35 @@ impl<'a> ActionRunner<'a> {
                     self.send(PerformerCmd::KeyTap(k.clone()));
                 }
             }
-            Effect::Shell(ref s) => {
-                print_info!("ACTION: Shell cmd={s}");
-                let _ = self.run_shell(s);
+            Effect::Shell(run) => {
+                print_info!("ACTION: Shell cmd={}", run.action.command);
+                self.run_shell(*run);
+            }
+            Effect::ShellStart { held, run } => {
+                print_info!("ACTION: ShellStart cmd={}", run.action.command);
+                let ShellRun { action, env } = *run;
+                let timeout = action.timeout_ms.map(Duration::from_millis);
+                if let Some(child) =
+                    self.spawn_shell(&action.command, env, timeout, || {})
+                {
+                    // A press while the last child is still going replaces it.
+                    if let Some(old) = self.held.insert(held, child) {
+                        old.terminate();
+                    }
+                }
+            }
+            Effect::ShellStop(held) => {
+                if let Some(child) = self.held.remove(&held) {
+                    print_info!("ACTION: ShellStop pid={}", child.pid());
+                    child.terminate();
+                }
+            }
+            Effect::Type { text, delay_ms } => {
+                print_info!(
+                    "ACTION: Type chars={} delay_ms={delay_ms}",
+                    text.chars().count()
+                );
+                self.send(PerformerCmd::Type { text, delay_ms });
             }
             Effect::MouseClick { button, click_type } => {
                 print_info!(
@@ -130,25 +178,60 @@ impl<'a> ActionRunner<'a> {
             Effect::RawModifierPress(_) | Effect::RawModifierRelease(_) => {
                 print_error!("ACTION: RawModifier not supported on this platform");
             }
+            Effect::ReleaseAll => {
+                print_info!("ACTION: ReleaseAll");
+                self.send(PerformerCmd::ReleaseAll);
+            }
         }
     }
 
-    fn run_shell(&mut self, cmd: &str) -> Result<(), String> {
-        let shell = self.shell.clone().unwrap_or(DEFAULT_SHELL.into());
-        match Command::new(shell.into_string().as_str())
-            .args(["-c", cmd])
-            .stdin(std::process::Stdio::null())
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
+    /// Run a profile hook with the shell shell actions use; `env` describes
+    /// the event.
+    pub fn run_hook(&mut self, cmd: &str, env: Vec<(&'static str, String)>) {
+        print_info!("HOOK: cmd={cmd}");
+        self.spawn_shell(cmd, env, None, || {});
+    }
+
+    fn run_shell(&mut self, run: ShellRun) {
+        let ShellRun { action, env } = run;
+        let timeout = action.timeout_ms.map(Duration::from_millis);
+        if !action.single_instance {
+            self.spawn_shell(&action.command, env, timeout, || {});
+            return;
+        }
+        let command = action.command.clone();
+        if !self.running.lock().unwrap().insert(command.clone()) {
+            print_info!("shell command still running, skipped");
+            return;
+        }
+        let running = self.running.clone();
+        let on_exit = move || {
+            running.lock().unwrap().remove(&command);
+        };
+        if self
+            .spawn_shell(&action.command, env, timeout, on_exit)
+            .is_none()
         {
-            Ok(_) => {
-                print_info!("shell command spawned");
-                Ok(())
+            self.running.lock().unwrap().remove(&action.command);
+        }
+    }
+
+    fn spawn_shell(
+        &self,
+        cmd: &str,
+        env: Vec<(&'static str, String)>,
+        timeout: Option<Duration>,
+        on_exit: impl FnOnce() + Send + 'static,
+    ) -> Option<ShellChild> {
+        let shell_path = self.shell.as_deref().unwrap_or(DEFAULT_SHELL);
+        match shell::spawn(shell_path, cmd, env, timeout, on_exit) {
+            Ok(child) => {
+                print_info!("shell command spawned pid={}", child.pid());
+                Some(child)
             }
             Err(e) => {
                 print_error!("shell command error: {}", e);
-                Err(e.to_string())
+                None
             }
         }
     }
@@ -157,3 +240,65 @@ impl<'a> ActionRunner<'a> {
         self.shell = shell;
     }
 }
+
+impl Drop for ActionRunner<'_> {
+    /// `shell_hold` commands don't outlive the event loop: the daemon exits
+    /// right after it, so wait (boundedly) for them to be gone.
+    fn drop(&mut self) {
+        let children: Vec<ShellChild> =
+            self.held.drain().map(|(_, child)| child).collect();
+        for child in &children {
+            print_info!("stopping held shell command pid={}", child.pid());
+            child.terminate();
+        }
+        let deadline = Instant::now() + shell::STOP_TIMEOUT;
+        for child in &children {
+            if !child.wait_until(deadline) {
+                print_error!("held shell command pid={} did not stop", child.pid());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use padjutsu_control::RecordingBackend;
+    use padjutsu_gamepad::{Backend, Button};
+    use padjutsu_workspace::{ButtonChord, ShellAction};
+
+    use super::*;
+
+    #[test]
+    fn dropping_the_runner_stops_held_shell_commands() {
+        let manager = ControllerManager::with_backend(Backend::Virtual).unwrap();
+        let worker = PerformerWorker::spawn(RecordingBackend::new().0);
+        let mut runner = ActionRunner::new(&worker, &manager);
+        runner.set_shell(Some("/bin/sh".into()));
+        runner.run_effect(Effect::ShellStart {
+            held: HeldShell {
+                controller: 0,
+                chord: ButtonChord::new(&[Button::A]),
+            },
+            run: Box::new(ShellRun {
+                action: Arc::new(ShellAction {
+                    command: "sleep 30; true".into(),
+                    timeout_ms: None,
+                    single_instance: false,
+                }),
+                env: Vec::new(),
+            }),
+        });
+        let pid = runner.held.values().next().expect("held child").pid();
+
+        let started = Instant::now();
+        drop(runner);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        // SAFETY: kill(2) takes no pointers; signal 0 only checks that the
+        // process exists, which it does until the supervisor reaps it.
+        let running = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+        assert!(!running, "pid {pid} still running");
+    }
+}
@@ -0,0 +1,481 @@
+//! Recorded input sessions.
+//!
+//! A session file is JSON lines, one [`Entry`] per line: a controller event
+//! or an application switch, stamped with microseconds since recording
+//! started. `record` writes them from live input; `replay` feeds them back
+//! through the reducer against a chosen profile and prints the effects, or
+//! posts them through an output backend at the recorded pace.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{select, unbounded};
+use serde::{Deserialize, Serialize};
+
+use padjutsu_gamepad::{Backend, ControllerEvent, ControllerManager};
+use padjutsu_workspace::ProfileEvent;
+
+use crate::activity::{ActivityEvent, Monitor, NotificationListener};
+use crate::app::Padjutsu;
+use crate::cli::{InputBackendKind, OutputBackendKind};
+use crate::domain::{
+    reschedule_wake, Clock, DomainControl, DomainEvent, ManualClock, RuntimeMode,
+    RuntimeState, SystemEvent, TimerEvent, WakeState,
+};
+use crate::runner::ActionRunner;
+use crate::simulate::{print_effects, simulate_events};
+use crate::{
+    dispatch_and_process_overdue, dispatch_domain_event, load_profile, print_info,
+    spawn_performer_worker, WAKE_FAST_PERIOD, WAKE_IDLE_PERIOD,
+};
+
+/// How long (in session time) replay keeps running timers after the last
+/// recorded event, so repeats and motion started by it play out.
+const REPLAY_TAIL: Duration = Duration::from_millis(500);
+
+/// How often the recorder checks for application switches when no
+/// controller input arrives.
+const ACTIVITY_POLL: Duration = Duration::from_millis(50);
+
+/// One line of a session file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    /// Microseconds since recording started.
+    pub t_us: u64,
+    pub event: SessionEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SessionEvent {
+    Controller(ControllerEvent),
+    Activity { app: String },
+}
+
+impl From<SessionEvent> for DomainEvent {
+    fn from(event: SessionEvent) -> Self {
+        match event {
+            SessionEvent::Controller(event) => DomainEvent::Controller(event),
+            SessionEvent::Activity { app } => {
+                DomainEvent::Activity(ActivityEvent::DidActivateApplication(app))
+            }
+        }
+    }
+}
+
+impl Entry {
+    /// Time since the start of the recording.
+    fn offset(&self) -> Duration {
+        Duration::from_micros(self.t_us)
+    }
+}
+
+/// Appends entries to a session file.
+struct SessionWriter<W: Write> {
+    out: W,
+    started: Instant,
+    written: usize,
+}
+
+impl<W: Write> SessionWriter<W> {
+    fn new(out: W, started: Instant) -> Self {
+        Self {
+            out,
+            started,
+            written: 0,
+        }
+    }
+
+    fn write(&mut self, event: SessionEvent) -> io::Result<()> {
+        let entry = Entry {
+            t_us: self.started.elapsed().as_micros() as u64,
+            event,
+        };
+        serde_json::to_writer(&mut self.out, &entry)?;
+        self.out.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+}
+
+/// Parse a session. Blank lines are skipped; entries must be in time order.
+pub(crate) fn parse_session(reader: impl BufRead) -> Result<Vec<Entry>, String> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line)
+            .map_err(|e| format!("line {}: {e}", index + 1))?;
+        if entries.last().is_some_and(|last| last.t_us > entry.t_us) {
+            return Err(format!("line {}: timestamp goes backwards", index + 1));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Record controller events and application switches to `path` until Ctrl+C.
+pub(crate) fn record(path: &Path, input: InputBackendKind) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|e| format!("failed to create {}: {e}", path.display()))?;
+
+    // Activity monitor must run on the main thread.
+    let Some((monitor, activity_std_rx, monitor_stop_tx)) = Monitor::new() else {
+        return Err("failed to start activity monitor".into());
+    };
+    monitor.subscribe(NotificationListener::DidActivateApplication);
+
+    let started = Instant::now();
+    let mut writer = SessionWriter::new(BufWriter::new(file), started);
+    if let Some(app) = monitor.get_active_application() {
+        writer
+            .write(SessionEvent::Activity { app })
+            .map_err(|e| e.to_string())?;
+    }
+
+    let (stop_tx, stop_rx) = unbounded::<()>();
+    let recorder_done_tx = monitor_stop_tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop_tx.send(());
+        let _ = monitor_stop_tx.send(());
+    })
+    .map_err(|e| format!("failed to set Ctrl+C handler: {e}"))?;
+
+    let recorder = std::thread::Builder::new()
+        .name("recorder".into())
+        .spawn(move || -> io::Result<usize> {
+            // Let the monitor loop return however recording ends.
+            let _stop_monitor = StopOnDrop(recorder_done_tx);
+            let manager = ControllerManager::with_backend(input.into())
+                .map_err(io::Error::other)?;
+            let rx = manager.subscribe();
+            for info in manager.controllers() {
+                writer.write(SessionEvent::Controller(
+                    ControllerEvent::Connected(info),
+                ))?;
+            }
+            print_info!("recording; press Ctrl+C to stop");
+            loop {
+                select! {
+                    recv(stop_rx) -> _ => break,
+                    recv(rx) -> msg => {
+                        let Ok(event) = msg else { break };
+                        writer.write(SessionEvent::Controller(event))?;
+                    }
+                    default(ACTIVITY_POLL) => {}
+                }
+                #[cfg_attr(
+                    not(target_os = "macos"),
+                    allow(irrefutable_let_patterns)
+                )]
+                while let Ok(event) = activity_std_rx.try_recv() {
+                    if let ActivityEvent::DidActivateApplication(app) = event {
+                        writer.write(SessionEvent::Activity { app })?;
+                    }
+                }
+            }
+            writer.out.flush()?;
+            Ok(writer.written)
+        })
+        .map_err(|e| format!("failed to spawn recorder thread: {e}"))?;
+
+    monitor.run();
+    match recorder.join() {
+        Ok(Ok(written)) => {
+            print_info!("recorded {written} events to {}", path.display());
+            Ok(())
+        }
+        Ok(Err(e)) => Err(format!("recording failed: {e}")),
+        Err(e) => Err(format!("recorder thread panicked: {e:?}")),
+    }
+}
+
+/// Stops the activity monitor's loop when dropped.
+pub(crate) struct StopOnDrop(pub(crate) std::sync::mpsc::Sender<()>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Reducer state for a replay, driven on the calling thread.
+///
+/// The reducer runs on a [`ManualClock`] that follows the recording's own
+/// timeline, so timing behavior is the same at any speed and from run to
+/// run; only the real sleeps between steps are scaled.
+struct Replayer<'a> {
+    padjutsu: Padjutsu,
+    runtime_state: RuntimeState,
+    action_runner: ActionRunner<'a>,
+    manager: &'a ControllerManager,
+    wake_state: WakeState,
+    wake_due: Option<Instant>,
+    clock: ManualClock,
+    /// Session time zero on `clock`.
+    origin: Instant,
+    /// Wall time the replay started.
+    started: Instant,
+    speed: f64,
+}
+
+impl Replayer<'_> {
+    /// Session time of an entry.
+    fn at(&self, entry: &Entry) -> Instant {
+        self.origin + entry.offset()
+    }
+
+    /// Sleep until the wall time matching session time `at`, then move the
+    /// clock there.
+    fn advance_to(&self, at: Instant) {
+        let elapsed = at.saturating_duration_since(self.origin);
+        let wall = self.started + elapsed.div_f64(self.speed);
+        let now = Instant::now();
+        if wall > now {
+            std::thread::sleep(wall - now);
+        }
+        self.clock.set(at);
+    }
+
+    fn dispatch(&mut self, event: DomainEvent) -> DomainControl {
+        let control = dispatch_and_process_overdue(
+            event,
+            &mut self.padjutsu,
+            &mut self.runtime_state,
+            &mut self.action_runner,
+            self.manager,
+            &mut self.wake_state,
+            &self.clock,
+        );
+        self.rearm();
+        control
+    }
+
+    /// Same re-arming as the daemon's event loop.
+    fn rearm(&mut self) {
+        if self.wake_state.need_reschedule {
+            let plan = reschedule_wake(
+                &self.padjutsu,
+                &mut self.wake_state,
+                self.clock.now(),
+                WAKE_IDLE_PERIOD,
+                WAKE_FAST_PERIOD,
+            );
+            self.wake_due = plan.next_due;
+            self.wake_state.need_reschedule = false;
+        }
+    }
+
+    /// Advance to session time `deadline`, firing timer wakes that fall
+    /// before it.
+    fn run_until(&mut self, deadline: Instant) -> DomainControl {
+        loop {
+            match self.wake_due {
+                Some(due) if due <= deadline => {
+                    self.advance_to(due);
+                    self.wake_due = None;
+                    let control = dispatch_domain_event(
+                        DomainEvent::Timer(TimerEvent::Wake),
+                        &mut self.padjutsu,
+                        &mut self.runtime_state,
+                        &mut self.action_runner,
+                        self.manager,
+                        &mut self.wake_state,
+                        &self.clock,
+                    );
+                    self.rearm();
+                    if let DomainControl::Break = control {
+                        return control;
+                    }
+                }
+                _ => {
+                    self.advance_to(deadline);
+                    return DomainControl::Continue;
+                }
+            }
+        }
+    }
+}
+
+/// Replay a session through the reducer and print the effects, or with
+/// `output` post them at `speed` times the recorded pace.
+pub(crate) fn replay(
+    path: &Path,
+    workspace: Option<&str>,
+    speed: f64,
+    output: Option<OutputBackendKind>,
+) -> Result<(), String> {
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(format!("speed must be a positive number, got {speed}"));
+    }
+    let file = File::open(path)
+        .map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+    let entries = parse_session(BufReader::new(file))
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let (profile_path, profile) = load_profile(workspace)?;
+    let Some(output) = output else {
+        // Nothing is posted or run; the timeline is followed, not waited on.
+        let events = entries
+            .into_iter()
+            .map(|entry| (entry.offset(), entry.event.into()));
+        print_effects(simulate_events(profile, None, events));
+        return Ok(());
+    };
+
+    // Recorded controllers are not attached, so nothing reaches real hardware.
+    let manager = ControllerManager::with_backend(Backend::Virtual)
+        .map_err(|e| format!("failed to start controller manager: {e}"))?;
+    let worker = spawn_performer_worker(output)
+        .map_err(|e| format!("failed to start {output:?} output backend: {e}"))?;
+    let origin = Instant::now();
+    let mut replayer = Replayer {
+        padjutsu: Padjutsu::new(),
+        runtime_state: RuntimeState::new(RuntimeMode::AwaitingProfile),
+        action_runner: ActionRunner::new(&worker, &manager),
+        manager: &manager,
+        wake_state: WakeState::new(origin),
+        wake_due: None,
+        clock: ManualClock::new(origin),
+        origin,
+        started: origin,
+        speed,
+    };
+    let loaded = ProfileEvent::Changed(Box::new(profile));
+    replayer.dispatch(DomainEvent::Profile(loaded));
+
+    print_info!(
+        "replaying {} events from {} against {} at {speed}x",
+        entries.len(),
+        path.display(),
+        profile_path.display()
+    );
+    let total = entries.len();
+    let mut replayed = 0;
+    replayer.started = Instant::now();
+    for entry in entries {
+        let due = replayer.at(&entry);
+        if let DomainControl::Break = replayer.run_until(due) {
+            break;
+        }
+        if let DomainControl::Break = replayer.dispatch(entry.event.into()) {
+            break;
+        }
+        replayed += 1;
+    }
+    replayer.run_until(replayer.clock.now() + REPLAY_TAIL);
+    replayer.dispatch(DomainEvent::System(SystemEvent::ShutdownRequested));
+    print_info!(
+        "replayed {replayed}/{total} events in {:.2}s",
+        replayer.started.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use padjutsu_gamepad::{Axis, Button, ControllerInfo};
+
+    use super::*;
+
+    fn sample() -> Vec<SessionEvent> {
+        vec![
+            SessionEvent::Activity {
+                app: "com.figma.Desktop".into(),
+            },
+            SessionEvent::Controller(ControllerEvent::Connected(ControllerInfo {
+                id: 3,
+                name: "Xbox Wireless Controller".into(),
+                supports_rumble: true,
+                vendor_id: 0x045e,
+                product_id: 0x0b13,
+            })),
+            SessionEvent::Controller(ControllerEvent::ButtonPressed {
+                id: 3,
+                button: Button::A,
+            }),
+            SessionEvent::Controller(ControllerEvent::AxisMotion {
+                id: 3,
+                axis: Axis::RightX,
+                value: -0.25,
+            }),
+            SessionEvent::Controller(ControllerEvent::Disconnected(3)),
+        ]
+    }
+
+    #[test]
+    fn written_session_parses_back() {
+        let mut buf = Vec::new();
+        let mut writer = SessionWriter::new(&mut buf, Instant::now());
+        for event in sample() {
+            writer.write(event).unwrap();
+        }
+        assert_eq!(writer.written, 5);
+
+        let entries = parse_session(buf.as_slice()).unwrap();
+        let events: Vec<_> = entries.iter().map(|e| e.event.clone()).collect();
+        assert_eq!(events, sample());
+        assert!(entries.windows(2).all(|w| w[0].t_us <= w[1].t_us));
+    }
+
+    #[test]
+    fn parses_hand_written_lines() {
+        let text = r#"
+{"t_us":0,"event":{"activity":{"app":"com.figma.Desktop"}}}
+
+{"t_us":1500,"event":{"controller":{"ButtonReleased":{"id":0,"button":"DPadLeft"}}}}
+"#;
+        let entries = parse_session(text.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].t_us, 1500);
+        assert_eq!(entries[1].offset(), Duration::from_micros(1500));
+        assert!(matches!(
+            entries[1].event,
+            SessionEvent::Controller(ControllerEvent::ButtonReleased {
+                id: 0,
+                button: Button::DPadLeft
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let err = parse_session("{\"t_us\":0}\n".as_bytes()).unwrap_err();
+        assert!(err.starts_with("line 1:"), "{err}");
+
+        let text = concat!(
+            r#"{"t_us":10,"event":{"activity":{"app":"a"}}}"#,
+            "\n",
+            r#"{"t_us":5,"event":{"activity":{"app":"b"}}}"#,
+        );
+        let err = parse_session(text.as_bytes()).unwrap_err();
+        assert_eq!(err, "line 2: timestamp goes backwards");
+    }
+
+    #[test]
+    fn replays_effects_at_their_recorded_times() {
+        let text = concat!(
+            r#"{"t_us":0,"event":{"controller":{"Connected":{"id":0,"name":"pad","supports_rumble":false,"vendor_id":0,"product_id":0}}}}"#,
+            "\n",
+            r#"{"t_us":20000,"event":{"controller":{"ButtonPressed":{"id":0,"button":"A"}}}}"#,
+            "\n",
+            r#"{"t_us":50000,"event":{"controller":{"ButtonReleased":{"id":0,"button":"A"}}}}"#,
+        );
+        let profile = padjutsu_workspace::parse_profile(
+            "version: 1\nrules:\n  common:\n    buttons:\n      a:\n        hold: x\n",
+        )
+        .unwrap();
+        let events = parse_session(text.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.offset(), entry.event.into()));
+        let effects: Vec<String> = simulate_events(profile, None, events)
+            .iter()
+            .map(|(t, e)| format!("{}ms {e}", t.as_millis()))
+            .collect();
+        assert_eq!(effects, ["20ms key_press x", "50ms key_release x"]);
+    }
+}
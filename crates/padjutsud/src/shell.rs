@@ -0,0 +1,267 @@
+//! Processes started by shell actions and hooks.
+//!
+//! Every child gets a supervisor thread that polls it until it exits, so
+//! none is left a zombie. The supervisor terminates the child when its
+//! timeout runs out, [`ShellChild::terminate`] does so right away, and two
+//! more threads copy its stdout and stderr into the daemon log line by line.
+//! Both signal and reap under one lock, so a reaped pid is never signalled.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+use crate::{print_debug, print_error, print_info};
+
+/// How often the supervisor checks on its child.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How long a terminated child has to exit before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+/// How long a terminated child takes to be gone at most, killing included.
+pub const STOP_TIMEOUT: Duration =
+    KILL_GRACE.saturating_add(Duration::from_millis(200));
+
+/// Where the child is, shared by [`ShellChild`] and the supervisor.
+#[derive(Debug, Default)]
+struct Status {
+    /// SIGTERM went to the group.
+    terminated: bool,
+    /// The child was waited for; its pid may belong to someone else now.
+    reaped: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    status: Mutex<Status>,
+    reaped: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Status> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A running child. Dropping it leaves the child running.
+pub struct ShellChild {
+    pid: u32,
+    shared: Arc<Shared>,
+}
+
+impl ShellChild {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Send SIGTERM to the child's process group now; the supervisor sends
+    /// SIGKILL if it is still there [`KILL_GRACE`] later.
+    pub fn terminate(&self) {
+        let mut status = self.shared.lock();
+        if !status.reaped && !status.terminated {
+            signal_group(self.pid, libc::SIGTERM);
+            status.terminated = true;
+        }
+    }
+
+    /// Wait until the child is reaped or `deadline` passes; true if reaped.
+    pub fn wait_until(&self, deadline: Instant) -> bool {
+        let mut status = self.shared.lock();
+        while !status.reaped {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            status = match self.shared.reaped.wait_timeout(status, left) {
+                Ok((status, _)) => status,
+                Err(e) => e.into_inner().0,
+            };
+        }
+        true
+    }
+}
+
+/// Start `$shell -c command` in its own process group with `env` added to
+/// the daemon's environment. `on_exit` runs on the supervisor thread once
+/// the child is reaped.
+pub fn spawn(
+    shell: &str,
+    command: &str,
+    env: Vec<(&'static str, String)>,
+    timeout: Option<Duration>,
+    on_exit: impl FnOnce() + Send + 'static,
+) -> io::Result<ShellChild> {
+    let mut child = Command::new(shell)
+        .args(["-c", command])
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Signals reach whatever the shell started, not just the shell.
+        .process_group(0)
+        .spawn()?;
+    let pid = child.id();
+    if let Some(stdout) = child.stdout.take() {
+        log_lines(pid, stdout, false);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        log_lines(pid, stderr, true);
+    }
+
+    let shared = Arc::new(Shared::default());
+    let shared_c = shared.clone();
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    thread::Builder::new()
+        .name(format!("shell-{pid}"))
+        .spawn(move || {
+            let mut terminated_at: Option<Instant> = None;
+            loop {
+                let mut status = shared_c.lock();
+                let done = match child.try_wait() {
+                    Ok(Some(exit)) if exit.success() => {
+                        print_debug!("[shell {pid}] exited");
+                        true
+                    }
+                    Ok(Some(exit)) => {
+                        print_info!("[shell {pid}] {exit}");
+                        true
+                    }
+                    Ok(None) => false,
+                    Err(e) => {
+                        print_error!("[shell {pid}] wait failed: {e}");
+                        true
+                    }
+                };
+                let now = Instant::now();
+                let done = done
+                    || match terminated_at {
+                        None => {
+                            if !status.terminated
+                                && deadline.is_some_and(|at| now >= at)
+                            {
+                                print_info!("[shell {pid}] timed out, terminating");
+                                signal_group(pid, libc::SIGTERM);
+                                status.terminated = true;
+                            }
+                            if status.terminated {
+                                terminated_at = Some(now);
+                            }
+                            false
+                        }
+                        Some(at) if now >= at + KILL_GRACE => {
+                            print_info!("[shell {pid}] still running, killing");
+                            signal_group(pid, libc::SIGKILL);
+                            let _ = child.wait();
+                            true
+                        }
+                        Some(_) => false,
+                    };
+                if done {
+                    status.reaped = true;
+                    shared_c.reaped.notify_all();
+                    break;
+                }
+                drop(status);
+                thread::sleep(POLL_INTERVAL);
+            }
+            on_exit();
+        })?;
+
+    Ok(ShellChild { pid, shared })
+}
+
+fn signal_group(pid: u32, signal: libc::c_int) {
+    // SAFETY: kill(2) takes no pointers; a negative pid addresses the
+    // process group the child leads.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+fn log_lines<R: Read + Send + 'static>(pid: u32, stream: R, stderr: bool) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if stderr {
+                print_error!("[shell {pid}] {line}");
+            } else {
+                print_info!("[shell {pid}] {line}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::bounded;
+
+    use super::*;
+
+    fn exits_within(
+        command: &str,
+        env: Vec<(&'static str, String)>,
+        timeout: Option<Duration>,
+        within: Duration,
+    ) -> (ShellChild, bool) {
+        let (tx, rx) = bounded(1);
+        let child = spawn("/bin/sh", command, env, timeout, move || {
+            let _ = tx.send(());
+        })
+        .unwrap();
+        let exited = rx.recv_timeout(within).is_ok();
+        (child, exited)
+    }
+
+    #[test]
+    fn timeout_and_terminate_end_the_whole_group() {
+        // The shell forks `sleep`; both must go.
+        let (_, exited) = exits_within(
+            "sleep 30; true",
+            Vec::new(),
+            Some(Duration::from_millis(50)),
+            Duration::from_secs(1),
+        );
+        assert!(exited);
+
+        let (tx, rx) = bounded(1);
+        let child =
+            spawn("/bin/sh", "sleep 30; true", Vec::new(), None, move || {
+                let _ = tx.send(());
+            })
+            .unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        child.terminate();
+        assert!(rx.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn terminate_signals_at_once_and_wait_sees_the_exit() {
+        let (tx, rx) = bounded(1);
+        let child =
+            spawn("/bin/sh", "sleep 30; true", Vec::new(), None, move || {
+                let _ = tx.send(());
+            })
+            .unwrap();
+        assert!(!child.wait_until(Instant::now() + Duration::from_millis(100)));
+        child.terminate();
+        assert!(child.wait_until(Instant::now() + STOP_TIMEOUT));
+        assert!(rx.recv_timeout(Duration::from_secs(1)).is_ok());
+        // Reaped: terminating again must not signal a reused pid.
+        child.terminate();
+        assert!(child.wait_until(Instant::now()));
+    }
+
+    #[test]
+    fn passes_env_and_reaps_quick_commands() {
+        let (child, exited) = exits_within(
+            "test \"$PADJUTSU_CHORD\" = lt+a || sleep 30",
+            vec![("PADJUTSU_CHORD", "lt+a".to_string())],
+            None,
+            Duration::from_secs(1),
+        );
+        assert!(exited, "env not passed to pid {}", child.pid());
+    }
+}
@@ -0,0 +1,561 @@
+//! Offline profile simulation.
+//!
+//! Runs a short script of timed controller input through the reducer on a
+//! [`ManualClock`] and returns the effects it produces, without touching
+//! the OS. The output is deterministic, so profile changes can be reviewed
+//! by diffing it.
+//!
+//! A script is a list of statements separated by newlines or `;`; `#`
+//! starts a comment. A statement may begin with a time since the start
+//! (`20ms`, `1.5s`); without one it runs where the previous statement
+//! ended.
+//!
+//! ```text
+//! 0ms press lt; 20ms press a
+//! 300ms release a
+//! stick left 0.8,0 for 500ms
+//! release lt
+//! app com.figma.Desktop
+//! wait 100ms
+//! ```
+
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use padjutsu_gamepad::{
+    Axis, Backend, Button, ControllerEvent, ControllerInfo, ControllerManager,
+};
+use padjutsu_workspace::{parse_button, Profile, ProfileEvent};
+
+use crate::activity::ActivityEvent;
+use crate::app::{Effect, Padjutsu};
+use crate::domain::{
+    apply_wake_intents, overdue_wake_event, reduce_event, reschedule_wake, Clock,
+    DomainEvent, DomainStep, ManualClock, ModeTransition, RuntimeMode, RuntimeState,
+    TimerEvent, WakeState,
+};
+use crate::{load_profile, WAKE_FAST_PERIOD, WAKE_IDLE_PERIOD};
+
+/// Id of the simulated controller.
+const CONTROLLER_ID: u32 = 0;
+
+/// How long timers keep running after the last statement, so repeats and
+/// motion it started show up in the output.
+const TAIL: Duration = Duration::from_secs(1);
+
+/// One controller or app change at a point in the script.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Input {
+    Press(Button),
+    Release(Button),
+    Axis(Axis, f32),
+    App(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Step {
+    pub at: Duration,
+    pub input: Input,
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let bad = || format!("bad duration `{text}`");
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 1e-3)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        return Err(bad());
+    };
+    let value: f64 = number.parse().map_err(|_| bad())?;
+    // Also refuses negative, NaN and overflowing values.
+    Duration::try_from_secs_f64(value * scale).map_err(|_| bad())
+}
+
+/// Move the script's clock on by `d`.
+fn advance(cursor: Duration, d: Duration) -> Result<Duration, String> {
+    cursor
+        .checked_add(d)
+        .ok_or_else(|| "script runs past the longest duration".to_string())
+}
+
+fn parse_button_word(word: Option<&str>) -> Result<Button, String> {
+    let word = word.ok_or("missing button")?;
+    parse_button(word).ok_or_else(|| format!("unknown button `{word}`"))
+}
+
+fn stick_axes(side: &str) -> Result<(Axis, Axis), String> {
+    match side {
+        "left" => Ok((Axis::LeftX, Axis::LeftY)),
+        "right" => Ok((Axis::RightX, Axis::RightY)),
+        _ => Err(format!("unknown stick `{side}`, expected left or right")),
+    }
+}
+
+fn parse_xy(text: &str) -> Result<(f32, f32), String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|v| (-1.0..=1.0).contains(v))
+            .ok_or_else(|| format!("bad stick value `{v}`, expected -1..1"))
+    };
+    let (x, y) = text
+        .split_once(',')
+        .ok_or_else(|| format!("bad stick position `{text}`, expected x,y"))?;
+    Ok((parse(x)?, parse(y)?))
+}
+
+/// Parse a script into steps ordered by time.
+pub(crate) fn parse_script(script: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    let mut cursor = Duration::ZERO;
+    let statements = script
+        .lines()
+        .enumerate()
+        .flat_map(|(n, line)| {
+            let line = line.split('#').next().unwrap_or("");
+            line.split(';').map(move |s| (n + 1, s.trim()))
+        })
+        .filter(|(_, s)| !s.is_empty());
+
+    for (line, statement) in statements {
+        let fail = |e: String| format!("line {line}: `{statement}`: {e}");
+        let mut words = statement.split_whitespace().peekable();
+        // Commands are words; anything starting like a number is a time.
+        let timed = words
+            .peek()
+            .filter(|w| w.starts_with(|c: char| c.is_ascii_digit() || c == '.'));
+        if let Some(at) = timed {
+            let at = parse_duration(at).map_err(fail)?;
+            if at < cursor {
+                return Err(fail(format!(
+                    "time goes backwards (already at {}ms)",
+                    cursor.as_millis()
+                )));
+            }
+            cursor = at;
+            words.next();
+        }
+        let mut push = |at, input| steps.push(Step { at, input });
+        match words.next() {
+            Some("press") => push(
+                cursor,
+                Input::Press(parse_button_word(words.next()).map_err(fail)?),
+            ),
+            Some("release") => push(
+                cursor,
+                Input::Release(parse_button_word(words.next()).map_err(fail)?),
+            ),
+            Some("stick") => {
+                let side = words
+                    .next()
+                    .ok_or("missing stick side".to_string())
+                    .map_err(fail)?;
+                let (ax, ay) = stick_axes(side).map_err(fail)?;
+                let xy = words
+                    .next()
+                    .ok_or("missing position".to_string())
+                    .map_err(fail)?;
+                let (x, y) = parse_xy(xy).map_err(fail)?;
+                push(cursor, Input::Axis(ax, x));
+                push(cursor, Input::Axis(ay, y));
+                match (words.next(), words.next()) {
+                    (None, _) => {}
+                    (Some("for"), Some(d)) => {
+                        let d = parse_duration(d).map_err(fail)?;
+                        cursor = advance(cursor, d).map_err(fail)?;
+                        push(cursor, Input::Axis(ax, 0.0));
+                        push(cursor, Input::Axis(ay, 0.0));
+                    }
+                    _ => return Err(fail("expected `for <duration>`".into())),
+                }
+            }
+            Some("app") => {
+                let app = words
+                    .next()
+                    .ok_or("missing app id".to_string())
+                    .map_err(fail)?;
+                push(cursor, Input::App(app.to_string()));
+            }
+            Some("wait") => {
+                let d = words
+                    .next()
+                    .ok_or("expected `wait <duration>`".to_string())
+                    .and_then(parse_duration)
+                    .map_err(fail)?;
+                cursor = advance(cursor, d).map_err(fail)?;
+            }
+            Some(other) => return Err(fail(format!("unknown command `{other}`"))),
+            None => return Err(fail("missing command".into())),
+        }
+        if let Some(extra) = words.next() {
+            return Err(fail(format!("unexpected `{extra}`")));
+        }
+    }
+    Ok(steps)
+}
+
+impl From<Input> for DomainEvent {
+    fn from(input: Input) -> Self {
+        let id = CONTROLLER_ID;
+        match input {
+            Input::Press(button) => {
+                DomainEvent::Controller(ControllerEvent::ButtonPressed {
+                    id,
+                    button,
+                })
+            }
+            Input::Release(button) => {
+                DomainEvent::Controller(ControllerEvent::ButtonReleased {
+                    id,
+                    button,
+                })
+            }
+            Input::Axis(axis, value) => {
+                DomainEvent::Controller(ControllerEvent::AxisMotion {
+                    id,
+                    axis,
+                    value,
+                })
+            }
+            Input::App(app) => {
+                DomainEvent::Activity(ActivityEvent::DidActivateApplication(app))
+            }
+        }
+    }
+}
+
+/// Reducer state for a simulation. Effects are collected instead of run.
+struct Simulator {
+    padjutsu: Padjutsu,
+    runtime_state: RuntimeState,
+    wake_state: WakeState,
+    manager: ControllerManager,
+    clock: ManualClock,
+    origin: Instant,
+    wake_due: Option<Instant>,
+    effects: Vec<(Duration, Effect)>,
+}
+
+impl Simulator {
+    fn apply(&mut self, step: DomainStep) {
+        let t = self.clock.now() - self.origin;
+        for update in step.transition.controller_updates {
+            match update.next_state {
+                Some(state) => {
+                    self.runtime_state.set_controller_state(update.id, state)
+                }
+                None => self.runtime_state.disconnect_controller(update.id),
+            }
+        }
+        if let Some(ModeTransition::Set(mode)) = step.transition.mode {
+            self.runtime_state.set_mode(mode);
+        }
+        self.effects
+            .extend(step.transition.effects.into_iter().map(|e| (t, e)));
+        apply_wake_intents(&mut self.wake_state, step.transition.wake);
+    }
+
+    fn reduce(&mut self, event: DomainEvent) {
+        let step = reduce_event(
+            event,
+            &mut self.padjutsu,
+            &self.manager,
+            &self.runtime_state,
+            &self.wake_state,
+            &self.clock,
+        );
+        self.apply(step);
+    }
+
+    /// Dispatch like the daemon: the event, any overdue wake, then re-arm.
+    fn dispatch(&mut self, event: DomainEvent) {
+        self.reduce(event);
+        if let Some(wake) =
+            overdue_wake_event(&self.padjutsu, &self.wake_state, self.clock.now())
+        {
+            self.reduce(wake);
+        }
+        self.rearm();
+    }
+
+    fn rearm(&mut self) {
+        if self.wake_state.need_reschedule {
+            let plan = reschedule_wake(
+                &self.padjutsu,
+                &mut self.wake_state,
+                self.clock.now(),
+                WAKE_IDLE_PERIOD,
+                WAKE_FAST_PERIOD,
+            );
+            self.wake_due = plan.next_due;
+            self.wake_state.need_reschedule = false;
+        }
+    }
+
+    /// Fire every timer wake up to `deadline`, then move the clock there.
+    fn run_until(&mut self, deadline: Instant) {
+        while let Some(due) = self.wake_due.filter(|due| *due <= deadline) {
+            self.clock.set(due);
+            self.wake_due = None;
+            self.reduce(DomainEvent::Timer(TimerEvent::Wake));
+            self.rearm();
+        }
+        self.clock.set(deadline);
+    }
+}
+
+/// Run `steps` against `profile` with `app` frontmost and return the
+/// effects with their times since the start.
+pub(crate) fn simulate(
+    profile: Profile,
+    app: Option<&str>,
+    steps: Vec<Step>,
+) -> Vec<(Duration, Effect)> {
+    let connected =
+        DomainEvent::Controller(ControllerEvent::Connected(ControllerInfo {
+            id: CONTROLLER_ID,
+            name: "Simulated Controller".into(),
+            supports_rumble: true,
+            vendor_id: 0,
+            product_id: 0,
+        }));
+    let events = std::iter::once((Duration::ZERO, connected))
+        .chain(steps.into_iter().map(|step| (step.at, step.input.into())));
+    simulate_events(profile, app, events)
+}
+
+/// Run `events`, each at its time since the start, against `profile` with
+/// `app` frontmost and return the effects with their times.
+pub(crate) fn simulate_events(
+    profile: Profile,
+    app: Option<&str>,
+    events: impl IntoIterator<Item = (Duration, DomainEvent)>,
+) -> Vec<(Duration, Effect)> {
+    let origin = Instant::now();
+    let mut sim = Simulator {
+        padjutsu: Padjutsu::new(),
+        runtime_state: RuntimeState::new(RuntimeMode::AwaitingProfile),
+        wake_state: WakeState::new(origin),
+        manager: ControllerManager::with_backend(Backend::Virtual)
+            .expect("virtual controller backend"),
+        clock: ManualClock::new(origin),
+        origin,
+        wake_due: None,
+        effects: Vec::new(),
+    };
+    if let Some(app) = app {
+        sim.dispatch(Input::App(app.to_string()).into());
+    }
+    sim.dispatch(DomainEvent::Profile(ProfileEvent::Changed(Box::new(
+        profile,
+    ))));
+
+    let mut end = Duration::ZERO;
+    for (at, event) in events {
+        sim.run_until(origin + at);
+        sim.dispatch(event);
+        end = at;
+    }
+    sim.run_until(origin + end + TAIL);
+    sim.effects
+}
+
+/// Simulate the script at `path` (`-` for stdin) and print one line per
+/// effect.
+pub(crate) fn run(
+    path: &Path,
+    workspace: Option<&str>,
+    app: Option<&str>,
+) -> Result<(), String> {
+    let script = if path.as_os_str() == "-" {
+        let mut script = String::new();
+        std::io::stdin()
+            .read_to_string(&mut script)
+            .map_err(|e| format!("failed to read stdin: {e}"))?;
+        script
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?
+    };
+    let steps = parse_script(&script)?;
+    let (_, profile) = load_profile(workspace)?;
+    print_effects(simulate(profile, app, steps));
+    Ok(())
+}
+
+/// Print one line per effect with its time since the start.
+pub(crate) fn print_effects(effects: Vec<(Duration, Effect)>) {
+    for (t, effect) in effects {
+        println!("{:>6}ms  {effect}", t.as_millis());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use padjutsu_workspace::parse_profile;
+
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn run(profile: &str, script: &str) -> Vec<String> {
+        let profile = parse_profile(profile).unwrap();
+        let steps = parse_script(script).unwrap();
+        simulate(profile, None, steps)
+            .iter()
+            .map(|(t, e)| format!("{}ms {e}", t.as_millis()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_example_script() {
+        let steps = parse_script(
+            "0ms press lt; 20ms press a\n300ms release a # done\nstick left 0.8,0 for 500ms\nrelease lt",
+        )
+        .unwrap();
+        assert_eq!(
+            steps,
+            [
+                Step {
+                    at: ms(0),
+                    input: Input::Press(Button::LeftTrigger)
+                },
+                Step {
+                    at: ms(20),
+                    input: Input::Press(Button::A)
+                },
+                Step {
+                    at: ms(300),
+                    input: Input::Release(Button::A)
+                },
+                Step {
+                    at: ms(300),
+                    input: Input::Axis(Axis::LeftX, 0.8)
+                },
+                Step {
+                    at: ms(300),
+                    input: Input::Axis(Axis::LeftY, 0.0)
+                },
+                Step {
+                    at: ms(800),
+                    input: Input::Axis(Axis::LeftX, 0.0)
+                },
+                Step {
+                    at: ms(800),
+                    input: Input::Axis(Axis::LeftY, 0.0)
+                },
+                Step {
+                    at: ms(800),
+                    input: Input::Release(Button::LeftTrigger)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_script_errors_with_line() {
+        let err = parse_script("10ms press a\n5ms release a").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `5ms release a`: time goes backwards"),
+            "{err}"
+        );
+        let err = parse_script("press q").unwrap_err();
+        assert_eq!(err, "line 1: `press q`: unknown button `q`");
+        let err = parse_script("stick left 2,0").unwrap_err();
+        assert!(err.contains("expected -1..1"), "{err}");
+        let err = parse_script("wait").unwrap_err();
+        assert!(err.contains("expected `wait <duration>`"), "{err}");
+        let err = parse_script("1e300s press a").unwrap_err();
+        assert_eq!(err, "line 1: `1e300s press a`: bad duration `1e300s`");
+        let err = parse_script("wait -1s").unwrap_err();
+        assert_eq!(err, "line 1: `wait -1s`: bad duration `-1s`");
+        let err = parse_script("wait 1e19s\nwait 1e19s").unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: `wait 1e19s`: script runs past the longest duration"
+        );
+        let err = parse_script("stick left 1,0 for 1e19s\nwait 1e19s").unwrap_err();
+        assert!(err.starts_with("line 2: "), "{err}");
+    }
+
+    #[test]
+    fn repeat_and_chord_are_timed_by_the_script() {
+        let profile = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        tap: enter
+      lt+dpad_left:
+        keystroke: cmd+shift+[
+        repeat_delay_ms: 300
+        repeat_interval_ms: 120
+"#;
+        let out = run(
+            profile,
+            "0ms press a; 10ms release a; 20ms press lt; 30ms press dpad_left; 600ms release dpad_left; release lt",
+        );
+        assert_eq!(
+            out,
+            [
+                "0ms tap enter",
+                "30ms tap shift+cmd+bracket_left",
+                "330ms tap shift+cmd+bracket_left",
+                "450ms tap shift+cmd+bracket_left",
+                "570ms tap shift+cmd+bracket_left",
+            ]
+        );
+    }
+
+    #[test]
+    fn shell_hold_runs_until_the_chord_is_released() {
+        let profile = r#"
+version: 1
+rules:
+  common:
+    buttons:
+      a:
+        shell: notify
+        single_instance: true
+      lt+b:
+        shell_hold: record
+        timeout_ms: 60000
+"#;
+        let out = run(
+            profile,
+            "0ms press a; 10ms release a; 20ms press lt; 30ms press b; 500ms release b; release lt",
+        );
+        assert_eq!(
+            out,
+            [
+                "0ms shell notify",
+                "30ms shell_start b+lt record",
+                "500ms shell_stop b+lt",
+            ]
+        );
+    }
+
+    #[test]
+    fn same_script_gives_same_output() {
+        let profile = r#"
+version: 1
+rules:
+  common:
+    sticks:
+      left:
+        mode: mouse_move
+        deadzone: 0.15
+        max_speed_px_s: 2000
+"#;
+        let script = "stick left 0.8,0 for 200ms";
+        let first = run(profile, script);
+        assert!(first.iter().any(|l| l.contains("mouse_move")), "{first:?}");
+        assert_eq!(first, run(profile, script));
+    }
+}